@@ -0,0 +1,118 @@
+//! Compares the decision heuristics of `sat::check_sat_with` by the number of
+//! decisions and the time taken on example instances, in total and in the
+//! heuristic per decision. The default heuristic is also run recounting the
+//! literals at every decision, which shows the time per decision saved by the
+//! incremental literal counts.
+//!
+//!     cargo bench --bench heuristics
+
+use dpll_sat::heuristic::{
+    DominantPhase, DominantVariable, Heuristic, JeroslowWang, LeastFrequent, LookAhead,
+};
+use dpll_sat::{dimacs, sat};
+use std::time::{Duration, Instant};
+
+/// Wraps a heuristic to count the decisions made with it and the time taken.
+struct Counting<H> {
+    inner: H,
+    decisions: u64,
+    choosing: Duration,
+}
+
+impl<H: Heuristic> Heuristic for Counting<H> {
+    fn choose(&mut self, formula: &sat::Formula, n_vars: usize) -> sat::Literal {
+        let start = Instant::now();
+        let lit = self.inner.choose(formula, n_vars);
+        self.choosing += start.elapsed();
+        self.decisions += 1;
+        lit
+    }
+
+    fn choose_by_counts(&mut self, counts: &[u64]) -> Option<sat::Literal> {
+        let start = Instant::now();
+        let lit = self.inner.choose_by_counts(counts);
+        self.choosing += start.elapsed();
+        if lit.is_some() {
+            self.decisions += 1;
+        }
+        lit
+    }
+}
+
+/// Hides the literal counts from a heuristic, so that it scans the formula at
+/// every decision.
+struct Recounting<H>(H);
+
+impl<H: Heuristic> Heuristic for Recounting<H> {
+    fn choose(&mut self, formula: &sat::Formula, n_vars: usize) -> sat::Literal {
+        self.0.choose(formula, n_vars)
+    }
+}
+
+fn run<H: Heuristic>(name: &str, formula: &sat::Formula, heuristic: H) {
+    let mut counting = Counting {
+        inner: heuristic,
+        decisions: 0,
+        choosing: Duration::default(),
+    };
+
+    let start = Instant::now();
+    let result = sat::check_sat_with(formula, &mut counting);
+    let elapsed = start.elapsed();
+
+    let decisions = counting.decisions.max(1) as f64;
+    println!(
+        "  {:<16} {:<6} {:>8} decisions {:>10.3}s {:>8.2}us/decision {:>8.2}us/choice",
+        name,
+        if result.is_some() { "sat" } else { "unsat" },
+        counting.decisions,
+        elapsed.as_secs_f64(),
+        elapsed.as_secs_f64() * 1e6 / decisions,
+        counting.choosing.as_secs_f64() * 1e6 / decisions
+    );
+}
+
+/// Generates the pigeonhole formula asserting that n + 1 pigeons fit in n
+/// holes. It is a small unsatisfiable instance with a lot of structure.
+fn pigeonhole(n: usize) -> sat::Formula {
+    let var = |pigeon: usize, hole: usize| pigeon * n + hole;
+    let mut formula = sat::Formula::new();
+
+    for pigeon in 0..=n {
+        formula.push((0..n).map(|hole| sat::Var(var(pigeon, hole))).collect());
+    }
+    for hole in 0..n {
+        for a in 0..=n {
+            for b in a + 1..=n {
+                formula.push(vec![sat::Not(var(a, hole)), sat::Not(var(b, hole))]);
+            }
+        }
+    }
+    formula
+}
+
+fn main() {
+    let sources = [
+        ("negative_3", include_str!("../examples/negative_3.cnf")),
+        ("qg3-08", include_str!("../examples/qg3-08.cnf")),
+    ];
+
+    let mut instances = Vec::new();
+    for (name, source) in sources.iter() {
+        match dimacs::load(&mut source.as_bytes(), true) {
+            Ok(formula) => instances.push((name.to_string(), formula)),
+            Err(err) => panic!("{}: {}", name, err),
+        }
+    }
+    instances.push(("pigeonhole_6".to_string(), pigeonhole(6)));
+
+    for (name, formula) in instances.iter() {
+        println!("{}", name);
+        run("dominant", formula, DominantVariable);
+        run("dominant-recount", formula, Recounting(DominantVariable));
+        run("dominant-phase", formula, DominantPhase);
+        run("jeroslow-wang", formula, JeroslowWang);
+        run("least-frequent", formula, LeastFrequent);
+        run("look-ahead", formula, LookAhead::default());
+    }
+}
@@ -0,0 +1,150 @@
+//! Compares the nested `sat::Formula` with the contiguous `sat::FlatFormula`
+//! by the time taken to scan the clauses under an assignment, and the lazy
+//! clause storage built from each by the time taken to probe literals.
+//!
+//!     cargo bench --bench storage
+
+use dpll_sat::dimacs;
+use dpll_sat::sat::{self, ClauseStore, FlatFormula, LazyClauses};
+use std::time::Instant;
+
+/// Xorshift generator of pseudo-random numbers.
+fn xorshift(seed: u64) -> impl FnMut() -> u64 {
+    let mut state = seed;
+    move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    }
+}
+
+/// Generates a random 3-SAT formula.
+fn random_3sat(n_vars: usize, n_clauses: usize, seed: u64) -> sat::Formula {
+    let mut next = xorshift(seed);
+    (0..n_clauses)
+        .map(|_| {
+            (0..3)
+                .map(|_| {
+                    let r = next();
+                    let var = (r >> 1) as usize % n_vars;
+                    if r & 1 == 1 {
+                        sat::Var(var)
+                    } else {
+                        sat::Not(var)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Counts the clauses satisfied by an assignment, `rounds` times over.
+fn scan<'a, I>(clauses: impl Fn() -> I, vars: &[bool], rounds: usize) -> usize
+where
+    I: Iterator<Item = &'a [sat::Literal]>,
+{
+    let mut satisfied = 0;
+    for _ in 0..rounds {
+        satisfied += clauses()
+            .filter(|clause| {
+                clause
+                    .iter()
+                    .any(|lit| vars[lit.variable()] == matches!(lit, sat::Var(_)))
+            })
+            .count();
+    }
+    satisfied
+}
+
+/// Propagates each of the first `n_probes` variables both ways on a fresh
+/// storage made by `make`, returning the number of conflicts.
+fn probe<'a>(make: impl Fn() -> LazyClauses<'a>, n_vars: usize, n_probes: usize) -> usize {
+    let mut conflicts = 0;
+    for var in 0..n_probes.min(n_vars) {
+        for &truth in &[true, false] {
+            let mut clauses = make();
+            let mut vars = vec![false; n_vars];
+            clauses.assign(var, truth);
+            sat::propagate_units(&mut clauses, &mut vars);
+            conflicts += clauses.has_conflict() as usize;
+        }
+    }
+    conflicts
+}
+
+fn run(name: &str, formula: &sat::Formula) {
+    let n_vars = sat::num_variables(formula);
+    let vars: Vec<bool> = (0..n_vars).map(|i| i % 3 == 0).collect();
+    let rounds = (20_000_000 / formula.len().max(1)).max(1);
+
+    println!("{} ({} variables, {} clauses)", name, n_vars, formula.len());
+
+    let start = Instant::now();
+    let flat = FlatFormula::from(formula);
+    println!(
+        "  {:<8} {:>10.3}ms",
+        "convert",
+        start.elapsed().as_secs_f64() * 1e3
+    );
+
+    let start = Instant::now();
+    let nested_count = scan(|| formula.iter().map(|clause| &clause[..]), &vars, rounds);
+    let nested = start.elapsed();
+
+    let start = Instant::now();
+    let flat_count = scan(|| flat.iter(), &vars, rounds);
+    let flat_scan = start.elapsed();
+    assert_eq!(flat_count, nested_count);
+
+    for (layout, elapsed) in [("nested", nested), ("flat", flat_scan)].iter() {
+        println!(
+            "  {:<8} {:>10.3}ms/scan",
+            layout,
+            elapsed.as_secs_f64() * 1e3 / rounds as f64
+        );
+    }
+
+    let n_probes = 20;
+    let start = Instant::now();
+    let copied = probe(|| LazyClauses::new(formula, n_vars), n_vars, n_probes);
+    let copying = start.elapsed();
+
+    let start = Instant::now();
+    let shared = probe(|| LazyClauses::from_flat(&flat, n_vars), n_vars, n_probes);
+    let sharing = start.elapsed();
+    assert_eq!(copied, shared);
+
+    for (storage, elapsed) in [("new", copying), ("shared", sharing)].iter() {
+        println!(
+            "  {:<8} {:>10.3}ms/probe",
+            storage,
+            elapsed.as_secs_f64() * 1e3 / (2 * n_probes) as f64
+        );
+    }
+}
+
+/// Shuffles the clauses of a formula. The clauses stay where they were
+/// allocated, so the nested formula no longer goes through memory in order,
+/// as after clauses are reordered by preprocessing.
+fn shuffled(mut formula: sat::Formula, seed: u64) -> sat::Formula {
+    let mut next = xorshift(seed);
+    for i in (1..formula.len()).rev() {
+        formula.swap(i, next() as usize % (i + 1));
+    }
+    formula
+}
+
+fn main() {
+    let source = include_str!("../examples/qg3-08.cnf");
+    let formula = match dimacs::load(&mut source.as_bytes(), true) {
+        Ok(formula) => formula,
+        Err(err) => panic!("qg3-08: {}", err),
+    };
+    run("qg3-08", &formula);
+    run("qg3-08 shuffled", &shuffled(formula, 2));
+
+    let formula = random_3sat(200_000, 1_000_000, 1);
+    run("random_3sat", &formula);
+    run("random_3sat shuffled", &shuffled(formula, 3));
+}
@@ -0,0 +1,59 @@
+#![no_main]
+
+use dpll_sat::sat;
+use libfuzzer_sys::fuzz_target;
+
+// Keep formulas small so that the exhaustive check below stays cheap.
+const MAX_VARIABLES: usize = 12;
+const MAX_CLAUSES: usize = 64;
+
+fuzz_target!(|data: &[u8]| {
+    let (formula, n_vars) = make_formula(data);
+
+    match sat::check_sat(&formula) {
+        Some(vars) => assert!(sat::verify(&formula, &vars)),
+        None => assert!(!has_model(&formula, n_vars)),
+    }
+});
+
+/// Builds a valid CNF formula from raw bytes. The first byte determines the
+/// number of variables. Each following byte is a literal, or a clause
+/// terminator if its low bits are zero, mimicking the DIMACS format.
+fn make_formula(data: &[u8]) -> (sat::Formula, usize) {
+    let n_vars = match data.first() {
+        Some(&byte) => 1 + byte as usize % MAX_VARIABLES,
+        None => return (sat::Formula::new(), 0),
+    };
+
+    let mut formula = sat::Formula::new();
+    let mut clause = sat::Clause::new();
+
+    for &byte in &data[1..] {
+        if byte % 8 == 0 {
+            formula.push(clause.to_vec());
+            clause.clear();
+
+            if formula.len() == MAX_CLAUSES {
+                break;
+            }
+            continue;
+        }
+
+        let var = (byte >> 1) as usize % n_vars;
+        if byte & 1 == 0 {
+            clause.push(sat::Var(var));
+        } else {
+            clause.push(sat::Not(var));
+        }
+    }
+
+    (formula, n_vars)
+}
+
+/// Checks if a formula has a model by enumerating all assignments.
+fn has_model(formula: &sat::Formula, n_vars: usize) -> bool {
+    (0..1u32 << n_vars).any(|bits| {
+        let vars: sat::Assignment = (0..n_vars).map(|i| bits >> i & 1 == 1).collect();
+        sat::verify(formula, &vars)
+    })
+}
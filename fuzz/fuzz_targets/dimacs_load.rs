@@ -0,0 +1,13 @@
+#![no_main]
+
+use dpll_sat::dimacs;
+use libfuzzer_sys::fuzz_target;
+
+// The loader must either succeed or report an error for any input. Panics are
+// caught by libfuzzer as crashes.
+fuzz_target!(|data: &[u8]| {
+    for &strict in &[true, false] {
+        let mut src = data;
+        let _ = dimacs::load(&mut src, strict);
+    }
+});
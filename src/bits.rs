@@ -0,0 +1,135 @@
+use super::sat::Assignment;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Variable assignment packing 64 truth values into a word. This takes an
+/// eighth of the memory of `Assignment`, which matters for formulas with many
+/// variables. The public API exchanges `Assignment`, so a `BitAssignment` is
+/// converted at the boundary.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct BitAssignment {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitAssignment {
+    /// Creates an assignment of `len` variables, all false.
+    pub fn new(len: usize) -> BitAssignment {
+        BitAssignment {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    /// Returns the number of variables.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the truth value of variable `var`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `var` is out of range.
+    pub fn get(&self, var: usize) -> bool {
+        assert!(var < self.len, "variable out of range");
+        self.words[var / 64] >> (var % 64) & 1 == 1
+    }
+
+    /// Sets the truth value of variable `var`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `var` is out of range.
+    pub fn set(&mut self, var: usize, truth: bool) {
+        assert!(var < self.len, "variable out of range");
+        let mask = 1 << (var % 64);
+        if truth {
+            self.words[var / 64] |= mask;
+        } else {
+            self.words[var / 64] &= !mask;
+        }
+    }
+
+    /// Sets every variable to `truth`.
+    pub fn fill(&mut self, truth: bool) {
+        let word = if truth { !0 } else { 0 };
+        for w in self.words.iter_mut() {
+            *w = word;
+        }
+        // Keep the bits past the end clear so that the words compare equal.
+        if let (Some(last), rest @ 1..) = (self.words.last_mut(), self.len % 64) {
+            *last &= (1 << rest) - 1;
+        }
+    }
+
+    /// Resizes to `len` variables, all false, reusing the buffer.
+    pub fn reset(&mut self, len: usize) {
+        self.words.clear();
+        self.words.resize(len.div_ceil(64), 0);
+        self.len = len;
+    }
+
+    /// Converts to an `Assignment`.
+    pub fn to_assignment(&self) -> Assignment {
+        (0..self.len).map(|var| self.get(var)).collect()
+    }
+}
+
+impl From<&[bool]> for BitAssignment {
+    fn from(vars: &[bool]) -> BitAssignment {
+        let mut bits = BitAssignment::new(vars.len());
+        for (var, &truth) in vars.iter().enumerate() {
+            bits.set(var, truth);
+        }
+        bits
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bit_assignment_get_set() {
+        let mut bits = BitAssignment::new(130);
+        assert_eq!(bits.len(), 130);
+        assert!((0..130).all(|var| !bits.get(var)));
+
+        for &var in &[0, 63, 64, 127, 128, 129] {
+            bits.set(var, true);
+            assert!(bits.get(var));
+        }
+        assert_eq!((0..130).filter(|&var| bits.get(var)).count(), 6);
+
+        bits.set(64, false);
+        assert!(!bits.get(64));
+        assert!(bits.get(63) && bits.get(127));
+
+        bits.fill(true);
+        assert!((0..130).all(|var| bits.get(var)));
+        bits.fill(false);
+        assert_eq!(bits, BitAssignment::new(130));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn test_bit_assignment_out_of_range() {
+        // The last word has room for more, which must not be reachable.
+        BitAssignment::new(70).get(70);
+    }
+
+    #[test]
+    fn test_bit_assignment_round_trip() {
+        for &len in &[0, 1, 63, 64, 65, 200] {
+            let vars: Assignment = (0..len).map(|i| i % 3 == 0).collect();
+            let bits = BitAssignment::from(vars.as_slice());
+            assert_eq!(bits.len(), len);
+            assert_eq!(bits.to_assignment(), vars);
+        }
+
+        let mut bits = BitAssignment::new(65);
+        bits.fill(true);
+        assert_eq!(bits, BitAssignment::from(&[true; 65][..]));
+    }
+}
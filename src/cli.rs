@@ -0,0 +1,814 @@
+use super::heuristic::Heuristic;
+use super::solver::Outcome;
+use super::{dimacs, heuristic, local_search, preprocess, sat};
+use std::fs;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Exit code for a satisfiable formula, following the SAT competition.
+pub const EXIT_SAT: i32 = 10;
+/// Exit code for an unsatisfiable formula, following the SAT competition.
+pub const EXIT_UNSAT: i32 = 20;
+/// Exit code for an invalid argument or input.
+pub const EXIT_ERROR: i32 = 1;
+/// Exit code for an interrupted search, following the SAT competition.
+pub const EXIT_UNKNOWN: i32 = 0;
+
+/// Runs the command-line program with arguments `args`, which exclude the
+/// program name. The formula is read from `input` unless it is given as an
+/// argument or in files. The result is written to `out` and diagnostics to
+/// `err`.
+///
+/// Multiple files are concatenated and parsed as a single DIMACS CNF. So, the
+/// header in the first file declares the numbers of variables and clauses of
+/// the whole formula, and the other files contain only clauses and comments.
+///
+/// Returns the exit code of the program.
+pub fn run<I: Iterator<Item = String>>(
+    args: I,
+    input: &mut dyn io::BufRead,
+    out: &mut dyn io::Write,
+    err: &mut dyn io::Write,
+) -> i32 {
+    run_with_interrupt(args, input, out, err, &AtomicBool::new(false))
+}
+
+/// Runs the command-line program like `run`, giving up the search when
+/// `interrupt` becomes true. An interrupted search prints "unknown" and the
+/// number of decisions made to stderr, and exits with `EXIT_UNKNOWN`.
+pub fn run_with_interrupt<I: Iterator<Item = String>>(
+    args: I,
+    input: &mut dyn io::BufRead,
+    out: &mut dyn io::Write,
+    err: &mut dyn io::Write,
+    interrupt: &AtomicBool,
+) -> i32 {
+    // Failing to write the output is an error as well.
+    run_io(args, input, out, err, interrupt).unwrap_or(EXIT_ERROR)
+}
+
+/// Installs a handler of SIGINT that sets the returned flag, so that Ctrl-C
+/// interrupts the search instead of killing the program. Pass the flag to
+/// `run_with_interrupt`.
+#[cfg(all(feature = "signal", unix))]
+pub fn install_sigint_handler() -> &'static AtomicBool {
+    static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn handle(_: i32) {
+        INTERRUPTED.store(true, Ordering::Relaxed);
+    }
+
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+    const SIGINT: i32 = 2;
+
+    // Storing to an atomic is async-signal-safe.
+    unsafe {
+        signal(SIGINT, handle);
+    }
+    &INTERRUPTED
+}
+
+fn run_io<I: Iterator<Item = String>>(
+    args: I,
+    input: &mut dyn io::BufRead,
+    out: &mut dyn io::Write,
+    err: &mut dyn io::Write,
+    interrupt: &AtomicBool,
+) -> io::Result<i32> {
+    let options = match parse_args(args) {
+        Ok(options) => options,
+        Err(msg) => {
+            writeln!(err, "error: {}", msg)?;
+            return Ok(EXIT_ERROR);
+        }
+    };
+
+    let result = match options.cnf_base64 {
+        Some(data) => match decode_base64(&data) {
+            Some(bytes) => dimacs::load_lenient(&mut bytes.as_slice(), !options.lenient),
+            None => {
+                writeln!(err, "error: invalid base64 data")?;
+                return Ok(EXIT_ERROR);
+            }
+        },
+        None if !options.files.is_empty() => match read_files(&options.files, !options.lenient) {
+            Ok(bytes) => dimacs::load_lenient(&mut bytes.as_slice(), !options.lenient),
+            Err((path, e)) => {
+                writeln!(err, "error: {}: {}", path, e)?;
+                return Ok(EXIT_ERROR);
+            }
+        },
+        None => dimacs::load_lenient(input, !options.lenient),
+    };
+
+    let (formula, header) = match result {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            writeln!(err, "error: {}", e)?;
+            return Ok(EXIT_ERROR);
+        }
+    };
+
+    let unknown = dimacs::is_unknown_count(&header, !options.lenient);
+    if formula.len() != header.num_clauses && !unknown {
+        if !options.lenient {
+            writeln!(err, "error: {}", dimacs::Error::ClauseCount)?;
+            return Ok(EXIT_ERROR);
+        }
+        writeln!(
+            err,
+            "warning: header declares {} clauses but {} found",
+            header.num_clauses,
+            formula.len()
+        )?;
+    }
+
+    if let Some(width) = options.warn_width {
+        for (i, clause) in formula.iter().enumerate() {
+            if clause.len() > width {
+                writeln!(
+                    err,
+                    "warning: clause {} has {} literals, exceeding {}",
+                    i + 1,
+                    clause.len(),
+                    width
+                )?;
+            }
+        }
+    }
+
+    if options.stats {
+        write_stats(err, &formula)?;
+    }
+
+    if options.analyze {
+        write_analysis(err, &formula)?;
+    }
+
+    let var_map = match &options.var_map {
+        Some(path) => match fs::read_to_string(path).map_err(|e| e.to_string()) {
+            Ok(text) => match parse_var_map(&text) {
+                Ok(map) => map,
+                Err(msg) => {
+                    writeln!(err, "error: {}: {}", path, msg)?;
+                    return Ok(EXIT_ERROR);
+                }
+            },
+            Err(msg) => {
+                writeln!(err, "error: {}: {}", path, msg)?;
+                return Ok(EXIT_ERROR);
+            }
+        },
+        None => Vec::new(),
+    };
+
+    let heuristic = match heuristic::by_name(&options.heuristic) {
+        Some(heuristic) => heuristic,
+        None => {
+            writeln!(err, "error: unknown heuristic: {}", options.heuristic)?;
+            return Ok(EXIT_ERROR);
+        }
+    };
+
+    let stop = AtomicBool::new(interrupt.load(Ordering::Relaxed));
+    let mut watchdog = Watchdog {
+        inner: heuristic,
+        interrupt,
+        // A limit too far in the future to represent is no limit.
+        deadline: options
+            .time_limit
+            .and_then(|limit| Instant::now().checked_add(limit)),
+        stop: &stop,
+    };
+
+    // The model is mapped back to the original variables below.
+    let (formula, shuffle) = match options.randomize {
+        Some(seed) => {
+            let mut shuffled = formula;
+            let shuffle = preprocess::shuffle(&mut shuffled, seed);
+            (shuffled, Some(shuffle))
+        }
+        None => (formula, None),
+    };
+
+    // Local search finds models of some satisfiable formulas quickly but never
+    // proves unsatisfiability, so DPLL takes over if it fails.
+    let start = Instant::now();
+    let found = options
+        .walksat
+        .and_then(|flips| local_search::walksat(&formula, flips, WALKSAT_NOISE, 0));
+
+    let (outcome, search_stats) = match found {
+        Some(vars) => (Outcome::Sat(vars), sat::SearchStats::default()),
+        None => sat::check_sat_until_with_stats(&formula, &mut watchdog, &stop),
+    };
+
+    if options.stats_json {
+        write_stats_json(err, &outcome, &search_stats, start.elapsed())?;
+    }
+
+    match outcome {
+        Outcome::Sat(mut vars) => {
+            if let Some(shuffle) = &shuffle {
+                vars = shuffle.restore(&vars);
+            }
+
+            // The model covers the variables used in the formula. Those only
+            // declared in the header are unconstrained and set to false.
+            if options.full_model && vars.len() < header.num_variables {
+                vars.resize(header.num_variables, false);
+            }
+
+            if !options.quiet {
+                writeln!(out, "sat")?;
+                write_assignment(&vars, out)?;
+                writeln!(out)?;
+
+                // Variables not in the formula are not in the model either.
+                for (var, name) in &var_map {
+                    if let Some(truth) = vars.get(*var) {
+                        writeln!(out, "{} = {}", name, truth)?;
+                    }
+                }
+            }
+            Ok(EXIT_SAT)
+        }
+        Outcome::Unsat => {
+            if !options.quiet {
+                writeln!(out, "unsat")?;
+            }
+            Ok(EXIT_UNSAT)
+        }
+        Outcome::Unknown => {
+            if !options.quiet {
+                writeln!(out, "unknown")?;
+            }
+            if interrupt.load(Ordering::Relaxed) {
+                writeln!(err, "interrupted")?;
+            } else {
+                writeln!(err, "time limit exceeded")?;
+            }
+            writeln!(err, "decisions: {}", search_stats.decisions)?;
+            writeln!(err, "max depth: {}", search_stats.max_depth)?;
+            Ok(EXIT_UNKNOWN)
+        }
+    }
+}
+
+/// Probability of a random walk step in WalkSAT tried by `--walksat`.
+const WALKSAT_NOISE: f64 = 0.5;
+
+/// Wraps a heuristic to stop the search when interrupted or past the
+/// deadline. The search checks only one flag, so the wrapper raises `stop`
+/// at a decision for both reasons, and the search stops at the next one.
+struct Watchdog<'a> {
+    inner: Box<dyn Heuristic>,
+    interrupt: &'a AtomicBool,
+    deadline: Option<Instant>,
+    stop: &'a AtomicBool,
+}
+
+impl Watchdog<'_> {
+    fn check(&self) {
+        let expired = self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline);
+        if expired || self.interrupt.load(Ordering::Relaxed) {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Heuristic for Watchdog<'_> {
+    fn choose(&mut self, formula: &sat::Formula, n_vars: usize) -> sat::Literal {
+        self.check();
+        self.inner.choose(formula, n_vars)
+    }
+
+    fn choose_with_origins(
+        &mut self,
+        formula: &sat::Formula,
+        origins: &[usize],
+        n_vars: usize,
+    ) -> sat::Literal {
+        self.check();
+        self.inner.choose_with_origins(formula, origins, n_vars)
+    }
+
+    fn choose_by_counts(&mut self, counts: &[u64]) -> Option<sat::Literal> {
+        self.check();
+        self.inner.choose_by_counts(counts)
+    }
+}
+
+/// Command-line options.
+#[derive(Debug, PartialEq)]
+struct Options {
+    /// Base64-encoded DIMACS CNF to solve instead of reading stdin.
+    cnf_base64: Option<String>,
+    /// Print statistics of the formula to stderr.
+    stats: bool,
+    /// Print statistics of the search to stderr as a JSON object.
+    stats_json: bool,
+    /// Print structural metrics of the formula to stderr.
+    analyze: bool,
+    /// Print nothing on stdout and report the result only by the exit code.
+    quiet: bool,
+    /// Name of the decision heuristic.
+    heuristic: String,
+    /// Paths of the files to read the formula from instead of stdin.
+    files: Vec<String>,
+    /// Path of the file naming the variables, which annotates the model.
+    var_map: Option<String>,
+    /// Accept a clause count differing from the header with a warning, and
+    /// loose headers as in non-strict DIMACS parsing.
+    lenient: bool,
+    /// Warn about clauses with more literals than this.
+    warn_width: Option<usize>,
+    /// Print the model for all the variables declared in the header.
+    full_model: bool,
+    /// Number of WalkSAT flips to try before the DPLL search.
+    walksat: Option<u64>,
+    /// Seed for shuffling the clauses and the variables before solving.
+    randomize: Option<u64>,
+    /// Time after which the search gives up.
+    time_limit: Option<Duration>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            cnf_base64: None,
+            stats: false,
+            stats_json: false,
+            analyze: false,
+            quiet: false,
+            heuristic: heuristic::DEFAULT_NAME.to_string(),
+            files: Vec::new(),
+            var_map: None,
+            lenient: false,
+            warn_width: None,
+            full_model: false,
+            walksat: None,
+            randomize: None,
+            time_limit: None,
+        }
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cnf-base64" => match args.next() {
+                Some(data) => options.cnf_base64 = Some(data),
+                None => return Err(format!("{} requires an argument", arg)),
+            },
+            "--heuristic" => match args.next() {
+                Some(name) => options.heuristic = name,
+                None => return Err(format!("{} requires an argument", arg)),
+            },
+            "--var-map" => match args.next() {
+                Some(path) => options.var_map = Some(path),
+                None => return Err(format!("{} requires an argument", arg)),
+            },
+            "--warn-width" => match args.next().map(|width| width.parse()) {
+                Some(Ok(width)) => options.warn_width = Some(width),
+                Some(Err(_)) => return Err(format!("{} requires a number", arg)),
+                None => return Err(format!("{} requires an argument", arg)),
+            },
+            "--walksat" => match args.next().map(|flips| flips.parse()) {
+                Some(Ok(flips)) => options.walksat = Some(flips),
+                Some(Err(_)) => return Err(format!("{} requires a number", arg)),
+                None => return Err(format!("{} requires an argument", arg)),
+            },
+            "--randomize" => match args.next().map(|seed| seed.parse()) {
+                Some(Ok(seed)) => options.randomize = Some(seed),
+                Some(Err(_)) => return Err(format!("{} requires a number", arg)),
+                None => return Err(format!("{} requires an argument", arg)),
+            },
+            "--time-limit" => match args.next().map(|secs| {
+                // Negative, NaN or too large seconds are not a duration.
+                secs.parse()
+                    .ok()
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            }) {
+                Some(Some(limit)) => options.time_limit = Some(limit),
+                Some(None) => return Err(format!("{} requires a number of seconds", arg)),
+                None => return Err(format!("{} requires an argument", arg)),
+            },
+            "--stats" => options.stats = true,
+            "--stats-json" => options.stats_json = true,
+            "--analyze" => options.analyze = true,
+            "--full-model" => options.full_model = true,
+            "--lenient" => options.lenient = true,
+            "-q" | "--quiet" => options.quiet = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown argument: {}", arg)),
+            _ => options.files.push(arg),
+        }
+    }
+
+    if options.cnf_base64.is_some() && !options.files.is_empty() {
+        return Err("--cnf-base64 cannot be used with files".to_string());
+    }
+
+    Ok(options)
+}
+
+/// Reads files and merges them into one DIMACS CNF formula. The header of each
+/// file, if any, is dropped and a single header is put in front, declaring the
+/// largest number of variables and the total number of clauses of the headers.
+/// So, each file may be a complete formula or just clauses counted by the
+/// header of another file. A newline is put between files so that the last
+/// line of a file does not run into the next file. Returns the path of the
+/// failed file with the error on failure.
+fn read_files(paths: &[String], strict: bool) -> Result<Vec<u8>, (&str, io::Error)> {
+    let mut body = Vec::new();
+    let mut header: Option<(usize, usize)> = None;
+
+    for path in paths {
+        let content = match fs::read(path) {
+            Ok(content) => content,
+            Err(e) => return Err((path, e)),
+        };
+
+        // The header is the first line that is neither blank nor a comment.
+        // A malformed one is kept for the parser to report.
+        let mut lines = content.split(|&byte| byte == b'\n');
+        let mut in_preamble = true;
+        for line in &mut lines {
+            if in_preamble {
+                let text = String::from_utf8_lossy(line);
+                if line.starts_with(b"c") || text.trim().is_empty() {
+                    body.extend(line);
+                    body.push(b'\n');
+                    continue;
+                }
+                in_preamble = false;
+                if let Some((n_vars, n_clauses)) = parse_cnf_header(&text, strict) {
+                    let (max_vars, sum_clauses) = header.unwrap_or((0, 0));
+                    header = Some((max_vars.max(n_vars), sum_clauses + n_clauses));
+                    continue;
+                }
+            }
+            body.extend(line);
+            body.push(b'\n');
+        }
+    }
+
+    let mut bytes = match header {
+        Some((n_vars, n_clauses)) => format!("p cnf {} {}\n", n_vars, n_clauses).into_bytes(),
+        None => Vec::new(),
+    };
+    bytes.extend(body);
+    Ok(bytes)
+}
+
+/// Parses a header line `p cnf <variables> <clauses>`, or `p <variables>
+/// <clauses>` in non-strict mode as `dimacs::load`, into the numbers.
+fn parse_cnf_header(line: &str, strict: bool) -> Option<(usize, usize)> {
+    let numbers = match line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["p", "cnf", n_vars, n_clauses] => [n_vars, n_clauses],
+        ["p", n_vars, n_clauses] if !strict => [n_vars, n_clauses],
+        _ => return None,
+    };
+    Some((numbers[0].parse().ok()?, numbers[1].parse().ok()?))
+}
+
+/// Parses a variable map, which names the variables of a formula. Each line
+/// consists of a one-based variable index and a name separated by whitespace,
+/// e.g., `3 signal_foo`. Blank lines and lines starting with `#` are skipped.
+///
+/// Returns (variable, name) pairs in the order of the lines, where variables
+/// are zero-based. An error message names the offending line.
+fn parse_var_map(text: &str) -> Result<Vec<(usize, String)>, String> {
+    let mut map = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        let index = tokens.next().unwrap_or_default();
+        let name = match tokens.next() {
+            Some(name) if tokens.next().is_none() => name,
+            _ => return Err(format!("line {}: expected a variable and a name", i + 1)),
+        };
+        match index.parse::<usize>() {
+            Ok(var) if var > 0 => map.push((var - 1, name.to_string())),
+            _ => return Err(format!("line {}: bad variable: {}", i + 1, index)),
+        }
+    }
+
+    Ok(map)
+}
+
+/// Decodes base64 data. Both the standard and the URL-safe alphabets are
+/// accepted, and the trailing padding is optional.
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let data = data.trim_end_matches('=');
+    if data.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut n_bits = 0;
+
+    for c in data.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+
+        buffer = buffer << 6 | value as u32;
+        n_bits += 6;
+
+        if n_bits >= 8 {
+            n_bits -= 8;
+            bytes.push((buffer >> n_bits) as u8);
+            buffer &= (1 << n_bits) - 1;
+        }
+    }
+
+    Some(bytes)
+}
+
+/// Writes the size and the clause length histogram of a formula.
+fn write_stats(err: &mut dyn io::Write, formula: &sat::Formula) -> io::Result<()> {
+    let n_vars = sat::num_variables(formula);
+
+    writeln!(err, "variables: {}", n_vars)?;
+    writeln!(err, "clauses: {}", formula.len())?;
+    for (len, count) in sat::clause_length_histogram(formula) {
+        writeln!(err, "clauses of length {}: {}", len, count)?;
+    }
+    Ok(())
+}
+
+/// Writes the outcome and the statistics of a search as a JSON object on one
+/// line. The elapsed time is in seconds. A model found by local search comes
+/// with zero statistics.
+fn write_stats_json(
+    err: &mut dyn io::Write,
+    outcome: &Outcome,
+    stats: &sat::SearchStats,
+    elapsed: Duration,
+) -> io::Result<()> {
+    let outcome = match outcome {
+        Outcome::Sat(_) => "sat",
+        Outcome::Unsat => "unsat",
+        Outcome::Unknown => "unknown",
+    };
+    writeln!(
+        err,
+        "{{\"outcome\":\"{}\",\"decisions\":{},\"propagations\":{},\"conflicts\":{},\"max_depth\":{},\"elapsed\":{:.6}}}",
+        outcome,
+        stats.decisions,
+        stats.propagations,
+        stats.conflicts,
+        stats.max_depth,
+        elapsed.as_secs_f64()
+    )
+}
+
+/// Writes the polarities in which the variables occur, the number of unit
+/// clauses and the classes of the formula solved without search. A variable
+/// occurring in one polarity only is a pure literal.
+fn write_analysis(err: &mut dyn io::Write, formula: &sat::Formula) -> io::Result<()> {
+    let mut polarities: Vec<(bool, bool)> = Vec::new();
+    for lit in formula.iter().flatten() {
+        if polarities.len() <= lit.variable() {
+            polarities.resize(lit.variable() + 1, (false, false));
+        }
+        match *lit {
+            sat::Var(i) => polarities[i].0 = true,
+            sat::Not(i) => polarities[i].1 = true,
+        }
+    }
+    let count = |polarity| polarities.iter().filter(|&&p| p == polarity).count();
+    let yes_no = |flag| if flag { "yes" } else { "no" };
+
+    writeln!(err, "positive only: {}", count((true, false)))?;
+    writeln!(err, "negative only: {}", count((false, true)))?;
+    writeln!(err, "both polarities: {}", count((true, true)))?;
+    writeln!(
+        err,
+        "unit clauses: {}",
+        formula.iter().filter(|clause| clause.len() == 1).count()
+    )?;
+    writeln!(err, "horn: {}", yes_no(sat::is_horn(formula)))?;
+    writeln!(err, "2-sat: {}", yes_no(sat::is_2sat(formula)))?;
+    Ok(())
+}
+
+/// Writes an assignment as the signed one-based literals separated by spaces,
+/// e.g., `1 -2 3`. The literals are formatted into a fixed buffer that is
+/// written out whenever it fills up, so a model of millions of variables is
+/// printed without building the whole text in memory.
+fn write_assignment(vars: &sat::Assignment, dst: &mut dyn io::Write) -> io::Result<()> {
+    let mut buf = [0; 8192];
+    let mut len = 0;
+
+    for (i, &truth) in vars.iter().enumerate() {
+        // A separator, a sign and the digits of a usize fit in 22 bytes.
+        if len + 22 > buf.len() {
+            dst.write_all(&buf[..len])?;
+            len = 0;
+        }
+        if i > 0 {
+            buf[len] = b' ';
+            len += 1;
+        }
+        if !truth {
+            buf[len] = b'-';
+            len += 1;
+        }
+
+        let mut digits = [0; 20];
+        let mut start = digits.len();
+        let mut number = i + 1;
+        while number > 0 {
+            start -= 1;
+            digits[start] = b'0' + (number % 10) as u8;
+            number /= 10;
+        }
+        let n = digits.len() - start;
+        buf[len..len + n].copy_from_slice(&digits[start..]);
+        len += n;
+    }
+
+    dst.write_all(&buf[..len])
+}
+
+#[cfg(test)]
+fn format_assignment(vars: &sat::Assignment) -> String {
+    let mut text = Vec::new();
+    write_assignment(vars, &mut text).unwrap();
+    String::from_utf8(text).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(list: &[&str]) -> impl Iterator<Item = String> {
+        list.iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(args(&[])), Ok(Options::default()));
+
+        let options = parse_args(args(&["--cnf-base64", "cCBjbmYgMCAw"])).unwrap();
+        assert_eq!(options.cnf_base64, Some("cCBjbmYgMCAw".to_string()));
+
+        let options = parse_args(args(&["--stats"])).unwrap();
+        assert!(options.stats);
+
+        let options = parse_args(args(&["--stats-json"])).unwrap();
+        assert!(options.stats_json);
+        assert!(!options.stats);
+
+        let options = parse_args(args(&["--analyze"])).unwrap();
+        assert!(options.analyze);
+
+        let options = parse_args(args(&["--lenient"])).unwrap();
+        assert!(options.lenient);
+
+        let options = parse_args(args(&["--full-model"])).unwrap();
+        assert!(options.full_model);
+
+        let options = parse_args(args(&["-q"])).unwrap();
+        assert!(options.quiet);
+
+        let options = parse_args(args(&["--heuristic", "jeroslow-wang"])).unwrap();
+        assert_eq!(options.heuristic, "jeroslow-wang");
+        assert!(parse_args(args(&["--heuristic"])).is_err());
+
+        let options = parse_args(args(&["--var-map", "map.txt"])).unwrap();
+        assert_eq!(options.var_map, Some("map.txt".to_string()));
+        assert!(parse_args(args(&["--var-map"])).is_err());
+
+        let options = parse_args(args(&["--warn-width", "100"])).unwrap();
+        assert_eq!(options.warn_width, Some(100));
+        assert!(parse_args(args(&["--warn-width", "wide"])).is_err());
+        assert!(parse_args(args(&["--warn-width"])).is_err());
+
+        let options = parse_args(args(&["--time-limit", "1.5"])).unwrap();
+        assert_eq!(options.time_limit, Some(Duration::from_millis(1500)));
+        assert!(parse_args(args(&["--time-limit", "-1"])).is_err());
+        assert!(parse_args(args(&["--time-limit", "soon"])).is_err());
+        assert!(parse_args(args(&["--time-limit", "1e300"])).is_err());
+        assert!(parse_args(args(&["--time-limit", "nan"])).is_err());
+        let options = parse_args(args(&["--time-limit", "1e19"])).unwrap();
+        assert_eq!(
+            options.time_limit,
+            Some(Duration::from_secs(10_000_000_000_000_000_000))
+        );
+        assert!(parse_args(args(&["--time-limit"])).is_err());
+
+        let options = parse_args(args(&["--walksat", "10000"])).unwrap();
+        assert_eq!(options.walksat, Some(10000));
+        assert!(parse_args(args(&["--walksat", "many"])).is_err());
+        assert!(parse_args(args(&["--walksat"])).is_err());
+
+        let options = parse_args(args(&["--randomize", "42"])).unwrap();
+        assert_eq!(options.randomize, Some(42));
+        assert!(parse_args(args(&["--randomize", "-1"])).is_err());
+        assert!(parse_args(args(&["--randomize"])).is_err());
+
+        let options = parse_args(args(&["a.cnf", "--stats", "b.cnf"])).unwrap();
+        assert_eq!(options.files, vec!["a.cnf", "b.cnf"]);
+        assert!(parse_args(args(&["--cnf-base64", "cCBjbmYgMCAw", "a.cnf"])).is_err());
+
+        assert!(parse_args(args(&["--cnf-base64"])).is_err());
+        assert!(parse_args(args(&["--unknown"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_var_map() {
+        let text = "# signals\n1 clk\n\n  3   signal_foo  \n";
+        let expect = vec![(0, "clk".to_string()), (2, "signal_foo".to_string())];
+        assert_eq!(parse_var_map(text), Ok(expect));
+        assert_eq!(parse_var_map(""), Ok(vec![]));
+
+        assert_eq!(
+            parse_var_map("1 a\n0 b\n"),
+            Err("line 2: bad variable: 0".to_string())
+        );
+        assert_eq!(
+            parse_var_map("x a\n"),
+            Err("line 1: bad variable: x".to_string())
+        );
+        assert_eq!(
+            parse_var_map("1\n"),
+            Err("line 1: expected a variable and a name".to_string())
+        );
+        assert_eq!(
+            parse_var_map("1 a b\n"),
+            Err("line 1: expected a variable and a name".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decode_base64() {
+        assert_eq!(decode_base64(""), Some(vec![]));
+        assert_eq!(decode_base64("Zg=="), Some(b"f".to_vec()));
+        assert_eq!(decode_base64("Zm8"), Some(b"fo".to_vec()));
+        assert_eq!(decode_base64("Zm9v"), Some(b"foo".to_vec()));
+        assert_eq!(decode_base64("cCBjbmYgMCAw"), Some(b"p cnf 0 0".to_vec()));
+        assert_eq!(decode_base64("-_8="), decode_base64("+/8="));
+        assert_eq!(decode_base64("Zm9v!"), None);
+        assert_eq!(decode_base64("Zm9vY"), None);
+    }
+
+    #[test]
+    fn test_format_assignment_empty() {
+        let vars = sat::Assignment::new();
+        let actual = format_assignment(&vars);
+        let expect = "";
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_format_assignment_one_based_signed() {
+        let vars = vec![true, false, true, false];
+        let actual = format_assignment(&vars);
+        let expect = "1 -2 3 -4";
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_write_assignment() {
+        // Long enough to fill the buffer several times.
+        let vars: sat::Assignment = (0..20000).map(|i| i % 3 == 0 || i % 7 == 1).collect();
+
+        let mut expect = String::new();
+        for (i, &truth) in vars.iter().enumerate() {
+            if i > 0 {
+                expect.push(' ');
+            }
+            let lit = if truth { sat::Var(i) } else { sat::Not(i) };
+            expect.push_str(&lit.to_string());
+        }
+
+        let mut actual = Vec::new();
+        write_assignment(&vars, &mut actual).unwrap();
+        assert_eq!(String::from_utf8(actual).unwrap(), expect);
+        assert_eq!(format_assignment(&vars), expect);
+    }
+}
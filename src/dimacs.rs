@@ -1,84 +1,411 @@
 use super::sat;
+use std::error;
 use std::fmt;
 use std::io;
+use std::mem;
 
+#[derive(Debug)]
 pub enum Error {
+    EmptyInput,
     NoHeader,
     BadHeader,
     BadClause,
-    VariableCount,
+    BadSupport,
+    BadBinary,
+    VariableCount { literal: i32, declared: usize },
     ClauseCount,
+    Limit,
     IO(io::Error),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Error::EmptyInput => write!(f, "empty input"),
             Error::NoHeader => write!(f, "no header"),
             Error::BadHeader => write!(f, "bad header"),
             Error::BadClause => write!(f, "bad clause"),
-            Error::VariableCount => write!(f, "unexpected number of variables"),
+            Error::BadSupport => write!(f, "bad independent support"),
+            Error::BadBinary => write!(f, "bad binary formula"),
+            Error::VariableCount { literal, declared } => write!(
+                f,
+                "variable {} exceeds declared count {}",
+                literal.unsigned_abs(),
+                declared
+            ),
             Error::ClauseCount => write!(f, "unexpected number of clauses"),
+            Error::Limit => write!(f, "formula exceeds size limit"),
             Error::IO(err) => err.fmt(f),
         }
     }
 }
 
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::IO(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::IO(err)
+    }
+}
+
 /// Loads DIMACS CNF formula.
-pub fn load(mut src: &mut dyn io::BufRead) -> Result<sat::Formula, Error> {
-    let header = parse_header(&mut src)?;
-    let formula = parse_formula(&mut src, &header)?;
+///
+/// In strict mode, the header must precede any line other than comments and
+/// blank lines. Otherwise, unrecognized lines before the header are skipped,
+/// and a header lacking the `cnf` keyword like `p 3 2`, which some generators
+/// emit, is accepted as `p cnf 3 2`. Also, a clause count of zero is taken as
+/// unknown and any number of clauses is read up to the end, which suits
+/// generators streaming the clauses after the header.
+pub fn load(mut src: &mut dyn io::BufRead, strict: bool) -> Result<sat::Formula, Error> {
+    let header = parse_header(&mut src, strict, None)?;
+    let formula = parse_formula(&mut src, &header, strict, None)?;
+    Ok(formula)
+}
+
+/// Upper limits of the size of a formula accepted by `load_with_limits`. None
+/// means no limit, which is the default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Limits {
+    pub max_variables: Option<usize>,
+    pub max_clauses: Option<usize>,
+    /// Bytes taken by the clauses read, counting the vector of each clause
+    /// and its literals. The clauses are counted as they are read, so this
+    /// bounds the memory whatever the header declares.
+    pub max_bytes: Option<usize>,
+}
+
+/// Loads DIMACS CNF formula like `load`, failing with `Error::Limit` if the
+/// formula is larger than `limits`.
+///
+/// The declared numbers in the header are checked before reading any clause,
+/// and reading stops as soon as the clauses exceed the limit. So, a huge input
+/// is rejected without consuming memory for it. The source is left at the
+/// next line after too many clauses, but in the middle of the clause that
+/// exceeds the byte limit since its line may never end.
+pub fn load_with_limits(
+    mut src: &mut dyn io::BufRead,
+    strict: bool,
+    limits: &Limits,
+) -> Result<sat::Formula, Error> {
+    let header = parse_header(&mut src, strict, None)?;
+
+    let exceeds = |limit: Option<usize>, num| limit.is_some_and(|limit| num > limit);
+    if exceeds(limits.max_variables, header.num_variables)
+        || exceeds(limits.max_clauses, header.num_clauses)
+    {
+        return Err(Error::Limit);
+    }
+
+    let mut formula = sat::Formula::new();
+    parse_clauses(&mut src, &header, None, limits, &mut |clause| {
+        formula.push(clause)
+    })?;
+    check_clause_count(&formula, &header, strict)?;
+    Ok(formula)
+}
+
+/// Loads DIMACS CNF formula like `load`, also collecting the text of comment
+/// lines in the order they appear. The leading `c` and surrounding whitespace
+/// are stripped from each comment.
+///
+/// Comments often carry metadata such as the names of the variables.
+pub fn load_with_comments(
+    mut src: &mut dyn io::BufRead,
+    strict: bool,
+) -> Result<(sat::Formula, Vec<String>), Error> {
+    let mut comments = Vec::new();
+    let header = parse_header(&mut src, strict, Some(&mut comments))?;
+    let formula = parse_formula(&mut src, &header, strict, Some(&mut comments))?;
+    Ok((formula, comments))
+}
+
+/// Loads DIMACS CNF formula like `load`, also returning the header. Comment
+/// lines of the form `c ind 1 2 3 0` list the variables of the independent
+/// support, which are collected into the header. A list may span multiple
+/// `c ind` lines, each terminated by `0`. Other comments are ignored.
+pub fn load_with_header(
+    src: &mut dyn io::BufRead,
+    strict: bool,
+) -> Result<(sat::Formula, Header), Error> {
+    let (formula, header) = load_lenient(src, strict)?;
+    check_clause_count(&formula, &header, strict)?;
+    Ok((formula, header))
+}
+
+/// Loads DIMACS CNF formula like `load_with_header`, but accepts any number of
+/// clauses regardless of the count declared in the header. Some files in the
+/// wild have a wrong count but are otherwise fine. The declared count is
+/// returned in the header, so the caller can compare it with the actual
+/// number of clauses, e.g., to issue a warning.
+pub fn load_lenient(
+    mut src: &mut dyn io::BufRead,
+    strict: bool,
+) -> Result<(sat::Formula, Header), Error> {
+    let mut comments = Vec::new();
+    let mut header = parse_header(&mut src, strict, Some(&mut comments))?;
+    let mut formula = sat::Formula::new();
+    parse_clauses(
+        &mut src,
+        &header,
+        Some(&mut comments),
+        &Limits::default(),
+        &mut |clause| formula.push(clause),
+    )?;
+
+    for comment in &comments {
+        let mut tokens = comment.split_whitespace();
+        if tokens.next() == Some("ind") {
+            parse_independent_support(tokens, &mut header)?;
+        }
+    }
+
+    Ok((formula, header))
+}
+
+/// Parses the variables of a `c ind` line following the `ind` token.
+fn parse_independent_support<'a, I: Iterator<Item = &'a str>>(
+    tokens: I,
+    header: &mut Header,
+) -> Result<(), Error> {
+    let mut terminated = false;
+
+    for token in tokens {
+        let value = match token.parse::<usize>() {
+            Ok(value) if !terminated => value,
+            _ => return Err(Error::BadSupport),
+        };
+
+        if value == 0 {
+            terminated = true;
+            continue;
+        }
+
+        if value > header.num_variables {
+            return Err(Error::VariableCount {
+                literal: value as i32,
+                declared: header.num_variables,
+            });
+        }
+
+        header.independent_support.push(value - 1);
+    }
+
+    if !terminated {
+        return Err(Error::BadSupport);
+    }
+    Ok(())
+}
+
+/// Reads DIMACS CNF clauses one by one, passing each clause to `emit` as soon
+/// as it is read. This allows processing a large formula without holding it in
+/// memory. Errors are the same as `load`, but some clauses may have been
+/// passed to `emit` before an error is found.
+pub fn read_clauses(
+    mut src: &mut dyn io::BufRead,
+    strict: bool,
+    emit: &mut dyn FnMut(sat::Clause),
+) -> Result<(), Error> {
+    let header = parse_header(&mut src, strict, None)?;
+    let mut num_clauses = 0;
+    parse_clauses(&mut src, &header, None, &Limits::default(), &mut |clause| {
+        num_clauses += 1;
+        emit(clause)
+    })?;
+    if num_clauses != header.num_clauses && !is_unknown_count(&header, strict) {
+        return Err(Error::ClauseCount);
+    }
+    Ok(())
+}
+
+/// Writes a formula in DIMACS CNF, one clause per line. The header declares
+/// the variables up to the largest one used in the formula. `load` reads the
+/// output back into the same formula.
+pub fn write(dst: &mut dyn io::Write, formula: &sat::Formula) -> io::Result<()> {
+    let num_variables = sat::num_variables(formula);
+    writeln!(dst, "p cnf {} {}", num_variables, formula.len())?;
+
+    for clause in formula {
+        writeln!(dst, "{}", sat::display_clause(clause))?;
+    }
+    Ok(())
+}
+
+/// Magic bytes starting the binary format of `write_binary`.
+const BINARY_MAGIC: &[u8; 8] = b"DPLLSAT\0";
+
+/// Version of the binary format, bumped on any incompatible change.
+const BINARY_VERSION: u32 = 2;
+
+/// Writes a formula in a compact binary format, which `read_binary` loads much
+/// faster than `load` parses text. This suits caching a large formula solved
+/// repeatedly.
+///
+/// The format is the magic bytes `DPLLSAT\0`, the version, the numbers of
+/// variables and clauses and then the clauses, each being its length followed
+/// by the codes of its literals (see `Literal::code`). The version, the
+/// lengths and the codes are little-endian u32 and the numbers of variables
+/// and clauses are little-endian u64.
+pub fn write_binary(dst: &mut dyn io::Write, formula: &sat::Formula) -> io::Result<()> {
+    dst.write_all(BINARY_MAGIC)?;
+    dst.write_all(&BINARY_VERSION.to_le_bytes())?;
+    dst.write_all(&(sat::num_variables(formula) as u64).to_le_bytes())?;
+    dst.write_all(&(formula.len() as u64).to_le_bytes())?;
+
+    let mut buffer = Vec::new();
+    for clause in formula {
+        buffer.clear();
+        buffer.extend_from_slice(&(clause.len() as u32).to_le_bytes());
+        for lit in clause {
+            buffer.extend_from_slice(&(lit.code() as u32).to_le_bytes());
+        }
+        dst.write_all(&buffer)?;
+    }
+    Ok(())
+}
+
+/// Reads a formula written by `write_binary`. Fails with `Error::BadBinary` if
+/// the magic bytes or the version do not match or a literal exceeds the
+/// declared number of variables, and with an IO error of kind `UnexpectedEof`
+/// if the data is truncated.
+pub fn read_binary(src: &mut dyn io::Read) -> Result<sat::Formula, Error> {
+    read_binary_with_limits(src, &Limits::default())
+}
+
+/// Reads a formula written by `write_binary` like `read_binary`, failing with
+/// `Error::Limit` if the formula is larger than `limits`. The declared numbers
+/// are checked before reading any clause, as in `load_with_limits`.
+pub fn read_binary_with_limits(
+    src: &mut dyn io::Read,
+    limits: &Limits,
+) -> Result<sat::Formula, Error> {
+    let mut magic = [0; 8];
+    src.read_exact(&mut magic)?;
+    if &magic != BINARY_MAGIC || read_u32(src)? != BINARY_VERSION {
+        return Err(Error::BadBinary);
+    }
+
+    let num_variables = read_u64(src)?;
+    let num_clauses = read_u64(src)?;
+
+    let exceeds = |limit: Option<usize>, num| limit.is_some_and(|limit| num > limit as u64);
+    if exceeds(limits.max_variables, num_variables) || exceeds(limits.max_clauses, num_clauses) {
+        return Err(Error::Limit);
+    }
+
+    // The counts are not trusted for preallocation, so that corrupt data does
+    // not exhaust memory. The codes are checked against the declared number
+    // of variables since a huge one would make the solver allocate for it.
+    let clause_bytes =
+        |len: usize| mem::size_of::<sat::Clause>() + len * mem::size_of::<sat::Literal>();
+    let mut num_bytes = 0;
+    let mut formula = sat::Formula::new();
+    for _ in 0..num_clauses {
+        let len = read_u32(src)? as usize;
+        if limits
+            .max_bytes
+            .is_some_and(|max_bytes| num_bytes + clause_bytes(len) > max_bytes)
+        {
+            return Err(Error::Limit);
+        }
+        num_bytes += clause_bytes(len);
+
+        let mut clause = sat::Clause::new();
+        for _ in 0..len {
+            let code = read_u32(src)? as usize;
+            if (code / 2) as u64 >= num_variables {
+                return Err(Error::BadBinary);
+            }
+            clause.push(if code.is_multiple_of(2) {
+                sat::Var(code / 2)
+            } else {
+                sat::Not(code / 2)
+            });
+        }
+        formula.push(clause);
+    }
     Ok(formula)
 }
 
+fn read_u32(src: &mut dyn io::Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    src.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(src: &mut dyn io::Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    src.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Header information of a DIMACS CNF.
 #[derive(Debug, PartialEq)]
-struct Header {
-    num_variables: usize,
-    num_clauses: usize,
+pub struct Header {
+    /// Number of variables declared in the `p cnf` line.
+    pub num_variables: usize,
+    /// Number of clauses declared in the `p cnf` line.
+    pub num_clauses: usize,
+    /// Variables listed in `c ind` lines, in the order of appearance. These
+    /// form the independent support for projected model counting. Empty if
+    /// there is no such line.
+    pub independent_support: Vec<usize>,
 }
 
-fn parse_header(src: &mut dyn io::BufRead) -> Result<Header, Error> {
+fn parse_header(
+    src: &mut dyn io::BufRead,
+    strict: bool,
+    mut comments: Option<&mut Vec<String>>,
+) -> Result<Header, Error> {
     let mut line = String::new();
+    let mut empty = true;
 
     loop {
         line.clear();
-        match src.read_line(&mut line) {
-            Ok(0) => break,
-            Ok(_) => {}
-            Err(err) => return Err(Error::IO(err)),
+        match src.read_line(&mut line)? {
+            0 => break,
+            _ => empty = false,
         }
 
-        if line.starts_with("c") {
+        if let Some(text) = line.strip_prefix('c') {
+            if let Some(comments) = comments.as_deref_mut() {
+                comments.push(text.trim().to_string());
+            }
             continue;
         }
 
         let tokens: Vec<&str> = line.split_whitespace().collect();
-        if tokens.len() == 0 {
+        if tokens.is_empty() {
             continue;
         }
 
-        // p cnf <num> <num>
+        // p cnf <num> <num>, or p <num> <num> in non-strict mode
         if tokens[0] == "p" {
-            if tokens.len() != 4 {
-                return Err(Error::BadHeader);
-            }
-
-            if tokens[1] != "cnf" {
-                return Err(Error::BadHeader);
-            }
+            let numbers = match tokens[1..] {
+                ["cnf", vars, clauses] => [vars, clauses],
+                [vars, clauses] if !strict && vars.parse::<usize>().is_ok() => [vars, clauses],
+                _ => return Err(Error::BadHeader),
+            };
 
             let mut header = Header {
                 num_variables: 0,
                 num_clauses: 0,
+                independent_support: Vec::new(),
             };
 
-            if let Ok(num) = tokens[2].parse::<usize>() {
+            if let Ok(num) = numbers[0].parse::<usize>() {
                 header.num_variables = num
             } else {
                 return Err(Error::BadHeader);
             }
 
-            if let Ok(num) = tokens[3].parse::<usize>() {
+            if let Ok(num) = numbers[1].parse::<usize>() {
                 header.num_clauses = num;
             } else {
                 return Err(Error::BadHeader);
@@ -87,95 +414,838 @@ fn parse_header(src: &mut dyn io::BufRead) -> Result<Header, Error> {
             return Ok(header);
         }
 
-        break;
+        if strict {
+            break;
+        }
     }
 
+    // Tell a zero-byte source apart from content lacking a header.
+    if empty {
+        return Err(Error::EmptyInput);
+    }
     Err(Error::NoHeader)
 }
 
-fn parse_formula(src: &mut dyn io::BufRead, header: &Header) -> Result<sat::Formula, Error> {
-    // First, load all numeral tokens from the source.
-    let mut line = String::new();
-    let mut spec = Vec::<i32>::new();
+fn parse_formula(
+    src: &mut dyn io::BufRead,
+    header: &Header,
+    strict: bool,
+    comments: Option<&mut Vec<String>>,
+) -> Result<sat::Formula, Error> {
+    let mut formula = sat::Formula::new();
+    parse_clauses(src, header, comments, &Limits::default(), &mut |clause| {
+        formula.push(clause)
+    })?;
+    check_clause_count(&formula, header, strict)?;
+    Ok(formula)
+}
+
+fn check_clause_count(formula: &sat::Formula, header: &Header, strict: bool) -> Result<(), Error> {
+    if formula.len() != header.num_clauses && !is_unknown_count(header, strict) {
+        return Err(Error::ClauseCount);
+    }
+    Ok(())
+}
+
+/// Checks if the header leaves the number of clauses unknown, which a count of
+/// zero means in non-strict mode.
+pub fn is_unknown_count(header: &Header, strict: bool) -> bool {
+    !strict && header.num_clauses == 0
+}
+
+/// Parses clauses after the header. Fails with `Error::Limit` once the clauses
+/// read exceed `limits` in number or in bytes. The variable limit is not
+/// checked, and neither is the number of clauses against the header.
+fn parse_clauses(
+    src: &mut dyn io::BufRead,
+    header: &Header,
+    mut comments: Option<&mut Vec<String>>,
+    limits: &Limits,
+    emit: &mut dyn FnMut(sat::Clause),
+) -> Result<(), Error> {
+    // Parse numeral tokens as CNF clauses separated by a token '0' as they are
+    // read. A variable out of range is reported after reading the whole source
+    // so that a malformed token anywhere takes precedence.
+    let mut tokens = Tokens {
+        src,
+        at_line_start: true,
+        token: Vec::new(),
+    };
+    let mut num_clauses = 0;
+    let mut num_bytes = 0;
+    let mut clause = sat::Clause::new();
+    let mut variable_error = None;
+
+    let clause_bytes =
+        |len: usize| mem::size_of::<sat::Clause>() + len * mem::size_of::<sat::Literal>();
 
     loop {
-        line.clear();
-        match src.read_line(&mut line) {
-            Ok(0) => break,
-            Ok(_) => {}
-            Err(err) => return Err(Error::IO(err)),
-        }
+        let token = match tokens.next()? {
+            Some(Token::Comment(text)) => {
+                if let Some(comments) = comments.as_deref_mut() {
+                    comments.push(text.trim().to_string());
+                }
+                continue;
+            }
+            Some(Token::Number(token)) => token,
+            None => break,
+        };
 
-        if line.starts_with("c") {
+        let value = match token.parse::<i32>() {
+            Ok(value) => value,
+            Err(_) => return Err(Error::BadClause),
+        };
+
+        if variable_error.is_some() {
             continue;
         }
 
-        for token in line.split_whitespace() {
-            if let Ok(value) = token.parse::<i32>() {
-                spec.push(value);
-            } else {
-                return Err(Error::BadClause);
+        if value == 0 {
+            if Some(num_clauses) == limits.max_clauses {
+                // Leave the source at the start of the next line.
+                tokens.skip_line()?;
+                return Err(Error::Limit);
             }
+            num_bytes += clause_bytes(clause.len());
+            emit(clause.to_vec());
+            num_clauses += 1;
+            clause.clear();
+            continue;
         }
-    }
 
-    // Then, parse the sequence of numeral tokens as CNF clauses separated by
-    // a token '0'.
-    let mut formula = sat::Formula::new();
-    let mut clause = sat::Clause::new();
+        if limits
+            .max_bytes
+            .is_some_and(|max_bytes| num_bytes + clause_bytes(clause.len() + 1) > max_bytes)
+        {
+            return Err(Error::Limit);
+        }
 
-    for value in spec {
-        if value == 0 {
-            formula.push(clause.to_vec());
-            clause.clear();
+        if value.unsigned_abs() as usize > header.num_variables {
+            variable_error = Some(Error::VariableCount {
+                literal: value,
+                declared: header.num_variables,
+            });
             continue;
         }
 
-        if value.abs() as usize > header.num_variables {
-            return Err(Error::VariableCount);
+        clause.push(sat::Literal::from_dimacs(value));
+    }
+
+    if let Some(err) = variable_error {
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+/// Longest token accepted in clauses. No valid literal is longer, so this just
+/// bounds the memory for a malformed token.
+const MAX_TOKEN_LEN: usize = 64;
+
+/// Token in the clause section of DIMACS CNF.
+enum Token<'a> {
+    /// Text of a comment line after the leading `c`.
+    Comment(String),
+    /// Whitespace-delimited token, which should be a number.
+    Number(&'a str),
+}
+
+/// Reader of the tokens in the clause section. Tokens are read incrementally
+/// from the buffer of the source, so a long line, e.g., a whole formula on a
+/// single line, is not held in memory. A comment line is read as a whole.
+struct Tokens<'a> {
+    src: &'a mut dyn io::BufRead,
+    at_line_start: bool,
+    token: Vec<u8>,
+}
+
+impl Tokens<'_> {
+    /// Returns the next token, or None at the end of the source. Fails with
+    /// `Error::BadClause` on a token that is too long and with an IO error of
+    /// kind `InvalidData` on invalid UTF-8.
+    fn next(&mut self) -> Result<Option<Token<'_>>, Error> {
+        // Skip whitespace. A comment starts right after a newline.
+        loop {
+            let buf = self.src.fill_buf()?;
+            let n = buf.iter().take_while(|b| b.is_ascii_whitespace()).count();
+            if let Some(&last) = buf[..n].last() {
+                self.at_line_start = last == b'\n';
+            }
+            let done = n < buf.len() || buf.is_empty();
+            self.src.consume(n);
+            if done {
+                break;
+            }
+        }
+
+        let first = match self.src.fill_buf()?.first() {
+            Some(&first) => first,
+            None => return Ok(None),
+        };
+
+        if self.at_line_start && first == b'c' {
+            let mut line = Vec::new();
+            self.src.read_until(b'\n', &mut line)?;
+            let text = String::from_utf8(line).map_err(|_| invalid_utf8())?;
+            // The newline is consumed, so the next line starts right away.
+            self.at_line_start = true;
+            return Ok(Some(Token::Comment(text[1..].to_string())));
         }
 
-        // Convert one-based signed index to zero-based tagged index we use.
-        if value > 0 {
-            clause.push(sat::Var((value - 1) as usize));
-        } else {
-            clause.push(sat::Not((-value - 1) as usize));
+        self.at_line_start = false;
+        self.token.clear();
+        loop {
+            let buf = self.src.fill_buf()?;
+            let n = buf.iter().take_while(|b| !b.is_ascii_whitespace()).count();
+            if self.token.len() + n > MAX_TOKEN_LEN {
+                return Err(Error::BadClause);
+            }
+            self.token.extend_from_slice(&buf[..n]);
+            let done = n < buf.len() || buf.is_empty();
+            self.src.consume(n);
+            if done {
+                break;
+            }
+        }
+
+        match core::str::from_utf8(&self.token) {
+            Ok(token) => Ok(Some(Token::Number(token))),
+            Err(_) => Err(invalid_utf8().into()),
         }
     }
 
-    if formula.len() != header.num_clauses {
-        return Err(Error::ClauseCount);
+    /// Consumes the rest of the current line including the newline.
+    fn skip_line(&mut self) -> io::Result<()> {
+        loop {
+            let buf = self.src.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(());
+            }
+            match buf.iter().position(|&b| b == b'\n') {
+                Some(i) => {
+                    self.src.consume(i + 1);
+                    self.at_line_start = true;
+                    return Ok(());
+                }
+                None => {
+                    let n = buf.len();
+                    self.src.consume(n);
+                }
+            }
+        }
     }
+}
 
-    Ok(formula)
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::testing::random_3sat;
     use sat::Not;
     use sat::Var;
 
     #[test]
     fn test_load() {
         let mut src = "c example\np cnf 3 2\n1 -2 3 0\n-1 -3 0\n".as_bytes();
-        let result = load(&mut src);
+        let result = load(&mut src, true);
         let expect = vec![vec![Var(0), Not(1), Var(2)], vec![Not(0), Not(2)]];
         match result {
             Ok(actual) => assert_eq!(actual, expect),
-            Err(err) => panic!(format!("unexpected: {}", err)),
+            Err(err) => panic!("unexpected: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_write() {
+        let formula = vec![vec![Var(0), Not(2)], vec![], vec![Not(1)]];
+        let mut dst = Vec::new();
+        write(&mut dst, &formula).unwrap();
+        assert_eq!(
+            String::from_utf8(dst).unwrap(),
+            "p cnf 3 3\n1 -3 0\n0\n-2 0\n"
+        );
+
+        let mut dst = Vec::new();
+        write(&mut dst, &vec![]).unwrap();
+        assert_eq!(String::from_utf8(dst).unwrap(), "p cnf 0 0\n");
+    }
+
+    /// Generates a random formula with up to `size` clauses of up to `size`
+    /// literals, including empty clauses and repeated literals.
+    fn random_formula(rng: &mut crate::rng::Rng, size: u64) -> sat::Formula {
+        let mut below = |n: u64| (rng.next_u64() % (n + 1)) as usize;
+        let n_vars = below(size);
+        let n_clauses = below(size);
+        (0..n_clauses)
+            .map(|_| {
+                let len = if n_vars == 0 { 0 } else { below(size) };
+                (0..len)
+                    .map(|_| {
+                        let var = below(n_vars as u64 - 1);
+                        if below(1) == 0 {
+                            Var(var)
+                        } else {
+                            Not(var)
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Shrinks a formula on which `fails` holds by removing clauses and then
+    /// literals as long as it keeps failing.
+    fn shrink(mut formula: sat::Formula, fails: impl Fn(&sat::Formula) -> bool) -> sat::Formula {
+        let mut i = 0;
+        while i < formula.len() {
+            let clause = formula.remove(i);
+            if !fails(&formula) {
+                formula.insert(i, clause);
+                i += 1;
+            }
+        }
+
+        for i in 0..formula.len() {
+            let mut j = 0;
+            while j < formula[i].len() {
+                let lit = formula[i].remove(j);
+                if !fails(&formula) {
+                    formula[i].insert(j, lit);
+                    j += 1;
+                }
+            }
+        }
+        formula
+    }
+
+    /// Sorts the literals of each clause and the clauses.
+    fn normalize(formula: &sat::Formula) -> Vec<Vec<i32>> {
+        let mut clauses: Vec<Vec<i32>> = formula
+            .iter()
+            .map(|clause| {
+                let mut clause: Vec<i32> = clause.iter().map(|lit| lit.to_dimacs()).collect();
+                clause.sort_unstable();
+                clause
+            })
+            .collect();
+        clauses.sort();
+        clauses
+    }
+
+    #[test]
+    fn test_write_load_round_trip() {
+        let round_trips = |formula: &sat::Formula| {
+            let mut dst = Vec::new();
+            write(&mut dst, formula).unwrap();
+            match load(&mut dst.as_slice(), true) {
+                Ok(loaded) => normalize(&loaded) == normalize(formula),
+                Err(_) => false,
+            }
+        };
+
+        let mut rng = crate::rng::Rng::new(1);
+        for size in (0..500).map(|i| i / 50) {
+            let formula = random_formula(&mut rng, size);
+            if !round_trips(&formula) {
+                let minimal = shrink(formula, |formula| !round_trips(formula));
+                panic!("round trip fails: {:?}", minimal);
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_read_binary_round_trip() {
+        let mut rng = crate::rng::Rng::new(2);
+        for size in (0..200).map(|i| i / 20) {
+            let formula = random_formula(&mut rng, size);
+            let mut dst = Vec::new();
+            write_binary(&mut dst, &formula).unwrap();
+            match read_binary(&mut dst.as_slice()) {
+                Ok(loaded) => assert_eq!(loaded, formula),
+                Err(err) => panic!("unexpected: {}", err),
+            }
+        }
+
+        let formula = vec![vec![Var(0), Not(2)], vec![]];
+        let mut dst = Vec::new();
+        write_binary(&mut dst, &formula).unwrap();
+        assert_eq!(&dst[..8], b"DPLLSAT\0");
+        assert_eq!(dst.len(), 8 + 4 + 8 + 8 + 4 + 2 * 4 + 4);
+        assert_eq!(&dst[12..20], &3u64.to_le_bytes());
+
+        let mut bad_magic = dst.clone();
+        bad_magic[0] = b'X';
+        let mut bad_version = dst.clone();
+        bad_version[8] = 1;
+        // The code of Not(2) changed to that of Var(2^31 - 1).
+        let mut bad_code = dst.clone();
+        bad_code[36..40].copy_from_slice(&(u32::MAX - 1).to_le_bytes());
+        for data in &[bad_magic, bad_version, bad_code] {
+            match read_binary(&mut data.as_slice()) {
+                Err(Error::BadBinary) => {}
+                result => panic!("unexpected: {:?}", result),
+            }
+        }
+
+        match read_binary(&mut &dst[..dst.len() - 1]) {
+            Err(Error::IO(err)) => assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof),
+            result => panic!("unexpected: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_read_binary_with_limits() {
+        let formula = vec![vec![Var(0), Not(2)], vec![Var(1)]];
+        let mut dst = Vec::new();
+        write_binary(&mut dst, &formula).unwrap();
+
+        let unit = mem::size_of::<sat::Clause>() + mem::size_of::<sat::Literal>();
+        let exact = Limits {
+            max_variables: Some(3),
+            max_clauses: Some(2),
+            max_bytes: Some(2 * unit + mem::size_of::<sat::Literal>()),
+        };
+        match read_binary_with_limits(&mut dst.as_slice(), &exact) {
+            Ok(loaded) => assert_eq!(loaded, formula),
+            Err(err) => panic!("unexpected: {}", err),
+        }
+
+        let limits = [
+            Limits {
+                max_variables: Some(2),
+                ..exact
+            },
+            Limits {
+                max_clauses: Some(1),
+                ..exact
+            },
+            Limits {
+                max_bytes: Some(2 * unit),
+                ..exact
+            },
+        ];
+        for limits in &limits {
+            match read_binary_with_limits(&mut dst.as_slice(), limits) {
+                Err(Error::Limit) => {}
+                result => panic!("unexpected: {:?}", result),
+            }
+        }
+
+        // A huge declared number of variables is rejected before any clause.
+        let mut huge = dst[..12].to_vec();
+        huge.extend_from_slice(&u64::MAX.to_le_bytes());
+        huge.extend_from_slice(&dst[20..]);
+        match read_binary_with_limits(&mut huge.as_slice(), &exact) {
+            Err(Error::Limit) => {}
+            result => panic!("unexpected: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_load_single_long_line() {
+        let mut rng = crate::rng::Rng::new(3);
+        let formula: sat::Formula = random_3sat(&mut rng, 1000, 20000);
+
+        let clauses: Vec<String> = formula
+            .iter()
+            .map(|clause| sat::display_clause(clause).to_string())
+            .collect();
+        let source = format!("p cnf 1000 20000\n{}\n", clauses.join(" "));
+
+        // A small buffer makes tokens span buffer boundaries.
+        let mut src = io::BufReader::with_capacity(7, source.as_bytes());
+        match load(&mut src, true) {
+            Ok(loaded) => assert_eq!(loaded, formula),
+            Err(err) => panic!("unexpected: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_load_tokens() {
+        // Comments, CRLF and tabs as before.
+        let mut src = "p cnf 3 2\r\nc one\r\n1\t-2 0 c\n2\n c\n".as_bytes();
+        match load_with_comments(&mut src, true) {
+            Err(Error::BadClause) => {}
+            result => panic!("unexpected: {:?}", result),
+        }
+
+        let mut src = "p cnf 3 2\r\nc one\r\n1\t-2 0\nc two\n3\n  \n-1 0\n".as_bytes();
+        match load_with_comments(&mut src, true) {
+            Ok((formula, comments)) => {
+                assert_eq!(formula, vec![vec![Var(0), Not(1)], vec![Var(2), Not(0)]]);
+                assert_eq!(comments, vec!["one", "two"]);
+            }
+            Err(err) => panic!("unexpected: {}", err),
+        }
+
+        let long = format!("p cnf 1 1\n{} 0\n", "1".repeat(100));
+        match load(&mut long.as_bytes(), true) {
+            Err(Error::BadClause) => {}
+            result => panic!("unexpected: {:?}", result),
+        }
+
+        let mut src: &[u8] = b"p cnf 1 1\nc \xff\n1 0\n";
+        match load(&mut src, true) {
+            Err(Error::IO(err)) => assert_eq!(err.kind(), io::ErrorKind::InvalidData),
+            result => panic!("unexpected: {:?}", result),
         }
     }
 
+    #[test]
+    fn test_shrink() {
+        // Shrinks to a formula having a clause with literals 1 and -2.
+        let fails = |formula: &sat::Formula| {
+            formula
+                .iter()
+                .any(|clause| clause.contains(&Var(1)) && clause.contains(&Not(2)))
+        };
+        let formula = vec![
+            vec![Var(0), Not(2)],
+            vec![Not(0), Var(1), Var(3), Not(2)],
+            vec![Var(1), Not(2), Var(4)],
+        ];
+        assert_eq!(shrink(formula, fails), vec![vec![Var(1), Not(2)]]);
+    }
+
     #[test]
     fn test_parse_header_no_header() {
         let mut src = "1 2 3 4\n".as_bytes();
-        let result = parse_header(&mut src);
+        let result = parse_header(&mut src, true, None);
         match result {
             Ok(_) => panic!(),
             Err(err) => match err {
                 Error::NoHeader => {}
-                _ => panic!(format!("unexpected: {}", err)),
+                _ => panic!("unexpected: {}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_load_zero_variables() {
+        let mut src = "p cnf 0 0\n".as_bytes();
+        let formula = match load(&mut src, true) {
+            Ok(formula) => formula,
+            Err(err) => panic!("unexpected: {}", err),
+        };
+        assert_eq!(formula, sat::Formula::new());
+        assert_eq!(sat::check_sat(&formula), Some(vec![]));
+    }
+
+    #[test]
+    fn test_load_zero_variables_with_clause() {
+        let mut src = "p cnf 0 1\n1 0\n".as_bytes();
+        let result = load(&mut src, true);
+        match result {
+            Ok(_) => panic!(),
+            Err(err) => match err {
+                Error::VariableCount {
+                    literal: 1,
+                    declared: 0,
+                } => {}
+                _ => panic!("unexpected: {}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_load_with_comments() {
+        let source =
+            "c variable 1 = door_open\np cnf 3 2\nc\n1 -2 3 0\nc  variable 3 = lit \n-1 -3 0\n";
+        let result = load_with_comments(&mut source.as_bytes(), true);
+        let expect = vec!["variable 1 = door_open", "", "variable 3 = lit"];
+        match result {
+            Ok((formula, comments)) => {
+                assert_eq!(comments, expect);
+                assert_eq!(Some(formula), load(&mut source.as_bytes(), true).ok());
+            }
+            Err(err) => panic!("unexpected: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_load_with_header() {
+        let source = "c ind 1 3 0\np cnf 4 2\nc index comment\n1 -2 3 0\nc   ind 4 0\n-1 -4 0\n";
+        let result = load_with_header(&mut source.as_bytes(), true);
+        match result {
+            Ok((formula, header)) => {
+                assert_eq!(header.num_variables, 4);
+                assert_eq!(header.num_clauses, 2);
+                assert_eq!(header.independent_support, vec![0, 2, 3]);
+                assert_eq!(Some(formula), load(&mut source.as_bytes(), true).ok());
+            }
+            Err(err) => panic!("unexpected: {}", err),
+        }
+
+        let source = "c independent support not given\np cnf 1 1\nc comment\n1 0\n";
+        let result = load_with_header(&mut source.as_bytes(), true);
+        match result {
+            Ok((_, header)) => assert!(header.independent_support.is_empty()),
+            Err(err) => panic!("unexpected: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_load_with_header_bad_support() {
+        let sources = [
+            "p cnf 2 1\nc ind 1 2\n1 2 0\n",
+            "p cnf 2 1\nc ind 1 -2 0\n1 2 0\n",
+            "p cnf 2 1\nc ind 1 0 2 0\n1 2 0\n",
+        ];
+        for source in sources.iter() {
+            match load_with_header(&mut source.as_bytes(), true) {
+                Ok(_) => panic!(),
+                Err(err) => match err {
+                    Error::BadSupport => {}
+                    _ => panic!("unexpected: {}", err),
+                },
+            }
+        }
+
+        let source = "p cnf 2 1\nc ind 3 0\n1 2 0\n";
+        match load_with_header(&mut source.as_bytes(), true) {
+            Ok(_) => panic!(),
+            Err(err) => match err {
+                Error::VariableCount {
+                    literal: 3,
+                    declared: 2,
+                } => {}
+                _ => panic!("unexpected: {}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_load_with_limits() {
+        let limits = Limits {
+            max_variables: Some(3),
+            max_clauses: Some(2),
+            max_bytes: None,
+        };
+
+        let mut src = "p cnf 3 2\n1 -2 0\n2 3 0\n".as_bytes();
+        match load_with_limits(&mut src, true, &limits) {
+            Ok(formula) => assert_eq!(formula.len(), 2),
+            Err(err) => panic!("unexpected: {}", err),
+        }
+
+        let mut src = "p cnf 3 2\n1 -2 0\n2 3 0\n".as_bytes();
+        match load_with_limits(&mut src, true, &Limits::default()) {
+            Ok(formula) => assert_eq!(formula.len(), 2),
+            Err(err) => panic!("unexpected: {}", err),
+        }
+
+        // The body is not read if the header exceeds the limits.
+        let sources = ["p cnf 3 1000000\nbody\n", "p cnf 1000000 2\nbody\n"];
+        for source in sources.iter() {
+            let mut src = source.as_bytes();
+            match load_with_limits(&mut src, true, &limits) {
+                Ok(_) => panic!(),
+                Err(err) => match err {
+                    Error::Limit => assert_eq!(src, b"body\n"),
+                    _ => panic!("unexpected: {}", err),
+                },
+            }
+        }
+
+        // Reading stops at the first clause past the limit.
+        let mut src = "p cnf 3 2\n1 0\n2 0\n3 0\nrest\n".as_bytes();
+        match load_with_limits(&mut src, true, &limits) {
+            Ok(_) => panic!(),
+            Err(err) => match err {
+                Error::Limit => assert_eq!(src, b"rest\n"),
+                _ => panic!("unexpected: {}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_load_with_byte_limit() {
+        /// Source repeating a line forever.
+        struct Endless(&'static [u8], usize);
+
+        impl io::Read for Endless {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                for byte in buf.iter_mut() {
+                    *byte = self.0[self.1 % self.0.len()];
+                    self.1 += 1;
+                }
+                Ok(buf.len())
+            }
+        }
+
+        // The clauses of the formula below take this many bytes.
+        let unit = mem::size_of::<sat::Clause>() + mem::size_of::<sat::Literal>();
+        let limits = Limits {
+            max_bytes: Some(3 * unit + mem::size_of::<sat::Literal>()),
+            ..Limits::default()
+        };
+        let mut src = "p cnf 3 3\n1 0\n-2 0\n3 0\n".as_bytes();
+        match load_with_limits(&mut src, true, &limits) {
+            Ok(formula) => assert_eq!(formula.len(), 3),
+            Err(err) => panic!("unexpected: {}", err),
+        }
+
+        let mut src = "p cnf 3 3\n1 0\n-2 0\n3 -1 2 0\n".as_bytes();
+        match load_with_limits(&mut src, true, &limits) {
+            Ok(_) => panic!(),
+            Err(err) => assert!(matches!(err, Error::Limit), "unexpected: {}", err),
+        }
+
+        // Endless input, whose header declares no huge size, stops at a budget
+        // of a megabyte: many tiny clauses and one endless clause.
+        let limits = Limits {
+            max_bytes: Some(1 << 20),
+            ..Limits::default()
+        };
+        for line in [&b"1 -2 0\n"[..], &b"1 "[..]].iter() {
+            let header = b"p cnf 2 0\n";
+            let source = io::Read::chain(&header[..], Endless(line, 0));
+            let mut src = io::BufReader::new(source);
+            match load_with_limits(&mut src, false, &limits) {
+                Ok(_) => panic!(),
+                Err(err) => assert!(matches!(err, Error::Limit), "unexpected: {}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_load_lenient() {
+        // Too few and too many clauses.
+        let sources = [
+            "p cnf 3 4\n1 -2 0\n2 3 0\n-3 0\n",
+            "p cnf 3 2\n1 -2 0\n2 3 0\n-3 0\n",
+        ];
+        for source in sources.iter() {
+            match load(&mut source.as_bytes(), true) {
+                Ok(_) => panic!(),
+                Err(err) => match err {
+                    Error::ClauseCount => {}
+                    _ => panic!("unexpected: {}", err),
+                },
+            }
+
+            let formula = match load_lenient(&mut source.as_bytes(), true) {
+                Ok((formula, header)) => {
+                    assert_ne!(header.num_clauses, formula.len());
+                    formula
+                }
+                Err(err) => panic!("unexpected: {}", err),
+            };
+            assert_eq!(formula.len(), 3);
+            let vars = sat::check_sat(&formula).unwrap();
+            assert!(sat::verify(&formula, &vars));
+        }
+
+        // Other errors are still reported.
+        let mut src = "p cnf 1 5\n2 0\n".as_bytes();
+        match load_lenient(&mut src, true) {
+            Ok(_) => panic!(),
+            Err(err) => match err {
+                Error::VariableCount { .. } => {}
+                _ => panic!("unexpected: {}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_load_unknown_clause_count() {
+        let source = "p cnf 3 0\n1 -2 0\n2 3\n-1 0\n";
+
+        match load(&mut source.as_bytes(), true) {
+            Err(Error::ClauseCount) => {}
+            result => panic!("unexpected: {:?}", result),
+        }
+
+        let formula = match load(&mut source.as_bytes(), false) {
+            Ok(formula) => formula,
+            Err(err) => panic!("unexpected: {}", err),
+        };
+        assert_eq!(
+            formula,
+            vec![vec![Var(0), Not(1)], vec![Var(1), Var(2), Not(0)]]
+        );
+        let vars = sat::check_sat(&formula).unwrap();
+        assert!(sat::verify(&formula, &vars));
+
+        let mut count = 0;
+        let result = read_clauses(&mut source.as_bytes(), false, &mut |_| count += 1);
+        assert!(result.is_ok());
+        assert_eq!(count, 2);
+
+        match load_with_header(&mut source.as_bytes(), false) {
+            Ok((loaded, header)) => {
+                assert_eq!(loaded, formula);
+                assert!(is_unknown_count(&header, false));
+            }
+            Err(err) => panic!("unexpected: {}", err),
+        }
+
+        // Still the empty formula without clauses.
+        assert_eq!(
+            load(&mut "p cnf 3 0\n".as_bytes(), false).unwrap(),
+            sat::Formula::new()
+        );
+    }
+
+    #[test]
+    fn test_read_clauses() {
+        let mut src = "p cnf 3 2\n1 -2 3 0\n-1 -3 0\n".as_bytes();
+        let mut clauses = Vec::new();
+        let result = read_clauses(&mut src, true, &mut |clause| clauses.push(clause));
+        match result {
+            Ok(()) => assert_eq!(
+                clauses,
+                vec![vec![Var(0), Not(1), Var(2)], vec![Not(0), Not(2)]]
+            ),
+            Err(err) => panic!("unexpected: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_error_source() {
+        // Invalid UTF-8 makes the reader fail.
+        let mut src: &[u8] = b"p cnf 1 1\n\xff 0\n";
+        let err: Box<dyn error::Error> = match load(&mut src, true) {
+            Ok(_) => panic!(),
+            Err(err) => Box::new(err),
+        };
+        let source = err.source().unwrap();
+        assert_eq!(err.to_string(), source.to_string());
+        assert!(source.is::<io::Error>());
+
+        let err: Box<dyn error::Error> = Box::new(Error::BadClause);
+        assert_eq!(err.to_string(), "bad clause");
+        assert!(err.source().is_none());
+    }
+
+    #[test]
+    fn test_parse_header_empty_input() {
+        let mut src = "".as_bytes();
+        let result = parse_header(&mut src, true, None);
+        match result {
+            Ok(_) => panic!(),
+            Err(err) => match err {
+                Error::EmptyInput => {}
+                _ => panic!("unexpected: {}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_header_content_without_header() {
+        let mut src = "c comment\n\n".as_bytes();
+        let result = parse_header(&mut src, true, None);
+        match result {
+            Ok(_) => panic!(),
+            Err(err) => match err {
+                Error::NoHeader => {}
+                _ => panic!("unexpected: {}", err),
             },
         }
     }
@@ -183,54 +1253,95 @@ mod test {
     #[test]
     fn test_parse_header_valid_header() {
         let mut src = "p cnf 3 2\n".as_bytes();
-        let result = parse_header(&mut src);
+        let result = parse_header(&mut src, true, None);
         let expect = Header {
             num_variables: 3,
             num_clauses: 2,
+            independent_support: Vec::new(),
         };
         match result {
             Ok(actual) => assert_eq!(actual, expect),
-            Err(err) => panic!(format!("error: {}", err)),
+            Err(err) => panic!("error: {}", err),
         }
     }
 
     #[test]
     fn test_parse_header_comment() {
         let mut src = "c comment\np cnf 3 2\n".as_bytes();
-        let result = parse_header(&mut src);
+        let result = parse_header(&mut src, true, None);
         let expect = Header {
             num_variables: 3,
             num_clauses: 2,
+            independent_support: Vec::new(),
         };
         match result {
             Ok(actual) => assert_eq!(actual, expect),
-            Err(err) => panic!(format!("error: {}", err)),
+            Err(err) => panic!("error: {}", err),
         }
     }
 
     #[test]
     fn test_parse_header_empty_lines() {
         let mut src = "\n\np cnf 3 2\n".as_bytes();
-        let result = parse_header(&mut src);
+        let result = parse_header(&mut src, true, None);
+        let expect = Header {
+            num_variables: 3,
+            num_clauses: 2,
+            independent_support: Vec::new(),
+        };
+        match result {
+            Ok(actual) => assert_eq!(actual, expect),
+            Err(err) => panic!("error: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_parse_header_junk_before_header() {
+        let mut src = "%%\np cnf 3 2\n".as_bytes();
+        let result = parse_header(&mut src, true, None);
+        match result {
+            Ok(_) => panic!(),
+            Err(err) => match err {
+                Error::NoHeader => {}
+                _ => panic!("unexpected: {}", err),
+            },
+        }
+
+        let mut src = "%%\n1 2 0\np cnf 3 2\n".as_bytes();
+        let result = parse_header(&mut src, false, None);
         let expect = Header {
             num_variables: 3,
             num_clauses: 2,
+            independent_support: Vec::new(),
         };
         match result {
             Ok(actual) => assert_eq!(actual, expect),
-            Err(err) => panic!(format!("error: {}", err)),
+            Err(err) => panic!("error: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_parse_header_lenient_no_header() {
+        let mut src = "%%\n1 2 0\n".as_bytes();
+        let result = parse_header(&mut src, false, None);
+        match result {
+            Ok(_) => panic!(),
+            Err(err) => match err {
+                Error::NoHeader => {}
+                _ => panic!("unexpected: {}", err),
+            },
         }
     }
 
     #[test]
     fn test_parse_header_no_required_fields() {
         let mut src = "p cnf\n".as_bytes();
-        let result = parse_header(&mut src);
+        let result = parse_header(&mut src, true, None);
         match result {
             Ok(_) => panic!(),
             Err(err) => match err {
                 Error::BadHeader => {}
-                _ => panic!(format!("unexpected: {}", err)),
+                _ => panic!("unexpected: {}", err),
             },
         }
     }
@@ -238,12 +1349,12 @@ mod test {
     #[test]
     fn test_parse_header_extra_fields() {
         let mut src = "p cnf 3 2 1\n".as_bytes();
-        let result = parse_header(&mut src);
+        let result = parse_header(&mut src, true, None);
         match result {
             Ok(_) => panic!(),
             Err(err) => match err {
                 Error::BadHeader => {}
-                _ => panic!(format!("unexpected: {}", err)),
+                _ => panic!("unexpected: {}", err),
             },
         }
     }
@@ -251,25 +1362,61 @@ mod test {
     #[test]
     fn test_parse_header_not_cnf() {
         let mut src = "p dnf 3 2\n".as_bytes();
-        let result = parse_header(&mut src);
+        let result = parse_header(&mut src, true, None);
         match result {
             Ok(_) => panic!(),
             Err(err) => match err {
                 Error::BadHeader => {}
-                _ => panic!(format!("unexpected: {}", err)),
+                _ => panic!("unexpected: {}", err),
             },
         }
     }
 
+    #[test]
+    fn test_parse_header_missing_cnf() {
+        let expect = Header {
+            num_variables: 3,
+            num_clauses: 2,
+            independent_support: Vec::new(),
+        };
+
+        for &strict in &[true, false] {
+            let mut src = "p cnf 3 2\n".as_bytes();
+            match parse_header(&mut src, strict, None) {
+                Ok(actual) => assert_eq!(actual, expect),
+                Err(err) => panic!("error: {}", err),
+            }
+        }
+
+        let mut src = "p 3 2\n".as_bytes();
+        match parse_header(&mut src, false, None) {
+            Ok(actual) => assert_eq!(actual, expect),
+            Err(err) => panic!("error: {}", err),
+        }
+
+        for &(source, strict) in &[("p 3 2\n", true), ("p 3\n", false), ("p 3 2 1\n", false)] {
+            let mut src = source.as_bytes();
+            match parse_header(&mut src, strict, None) {
+                Ok(_) => panic!("unexpected success: {:?}", source),
+                Err(Error::BadHeader) => {}
+                Err(err) => panic!("unexpected: {}", err),
+            }
+        }
+
+        let mut src = "c loose\np 3 2\n1 -2 0\n3 0\n".as_bytes();
+        let formula = load(&mut src, false).unwrap();
+        assert_eq!(formula, vec![vec![Var(0), Not(1)], vec![Var(2)]]);
+    }
+
     #[test]
     fn test_parse_header_negative_variables() {
         let mut src = "p cnf -1 2\n".as_bytes();
-        let result = parse_header(&mut src);
+        let result = parse_header(&mut src, true, None);
         match result {
             Ok(_) => panic!(),
             Err(err) => match err {
                 Error::BadHeader => {}
-                _ => panic!(format!("unexpected: {}", err)),
+                _ => panic!("unexpected: {}", err),
             },
         }
     }
@@ -277,12 +1424,12 @@ mod test {
     #[test]
     fn test_parse_header_negative_clauses() {
         let mut src = "p cnf 1 -2\n".as_bytes();
-        let result = parse_header(&mut src);
+        let result = parse_header(&mut src, true, None);
         match result {
             Ok(_) => panic!(),
             Err(err) => match err {
                 Error::BadHeader => {}
-                _ => panic!(format!("unexpected: {}", err)),
+                _ => panic!("unexpected: {}", err),
             },
         }
     }
@@ -293,12 +1440,13 @@ mod test {
         let header = Header {
             num_variables: 0,
             num_clauses: 0,
+            independent_support: Vec::new(),
         };
-        let result = parse_formula(&mut src, &header);
+        let result = parse_formula(&mut src, &header, true, None);
         let expect = sat::Formula::new();
         match result {
             Ok(actual) => assert_eq!(actual, expect),
-            Err(err) => panic!(format!("unexpected: {}", err)),
+            Err(err) => panic!("unexpected: {}", err),
         }
     }
 
@@ -308,12 +1456,13 @@ mod test {
         let header = Header {
             num_variables: 5,
             num_clauses: 2,
+            independent_support: Vec::new(),
         };
-        let result = parse_formula(&mut src, &header);
+        let result = parse_formula(&mut src, &header, true, None);
         let expect = vec![vec![Var(0), Var(1)], vec![Not(2), Not(3), Not(4)]];
         match result {
             Ok(actual) => assert_eq!(actual, expect),
-            Err(err) => panic!(format!("unexpected: {}", err)),
+            Err(err) => panic!("unexpected: {}", err),
         }
     }
 
@@ -323,12 +1472,47 @@ mod test {
         let header = Header {
             num_variables: 2,
             num_clauses: 2,
+            independent_support: Vec::new(),
         };
-        let result = parse_formula(&mut src, &header);
+        let result = parse_formula(&mut src, &header, true, None);
         let expect = vec![vec![Var(0), Var(1)], vec![Not(0), Not(1)]];
         match result {
             Ok(actual) => assert_eq!(actual, expect),
-            Err(err) => panic!(format!("unexpected: {}", err)),
+            Err(err) => panic!("unexpected: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_parse_formula_clause_across_lines() {
+        let mut src = "1\n2 0 -1\nc comment\n-2\n0\n".as_bytes();
+        let header = Header {
+            num_variables: 2,
+            num_clauses: 2,
+            independent_support: Vec::new(),
+        };
+        let result = parse_formula(&mut src, &header, true, None);
+        let expect = vec![vec![Var(0), Var(1)], vec![Not(0), Not(1)]];
+        match result {
+            Ok(actual) => assert_eq!(actual, expect),
+            Err(err) => panic!("unexpected: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_parse_formula_bad_clause_after_bad_variable() {
+        let mut src = "1 9 0\n1 x 0\n".as_bytes();
+        let header = Header {
+            num_variables: 2,
+            num_clauses: 2,
+            independent_support: Vec::new(),
+        };
+        let result = parse_formula(&mut src, &header, true, None);
+        match result {
+            Ok(_) => panic!(),
+            Err(err) => match err {
+                Error::BadClause => {}
+                _ => panic!("unexpected: {}", err),
+            },
         }
     }
 
@@ -338,13 +1522,36 @@ mod test {
         let header = Header {
             num_variables: 3,
             num_clauses: 1,
+            independent_support: Vec::new(),
+        };
+        let result = parse_formula(&mut src, &header, true, None);
+        match result {
+            Ok(_) => panic!(),
+            Err(err) => match err {
+                Error::VariableCount { .. } => {}
+                _ => panic!("unexpected: {}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_formula_variable_count_message() {
+        let mut src = "1 -7 0\n".as_bytes();
+        let header = Header {
+            num_variables: 5,
+            num_clauses: 1,
+            independent_support: Vec::new(),
         };
-        let result = parse_formula(&mut src, &header);
+        let result = parse_formula(&mut src, &header, true, None);
         match result {
             Ok(_) => panic!(),
             Err(err) => match err {
-                Error::VariableCount => {}
-                _ => panic!(format!("unexpected: {}", err)),
+                Error::VariableCount { literal, declared } => {
+                    assert_eq!(literal, -7);
+                    assert_eq!(declared, 5);
+                    assert_eq!(format!("{}", err), "variable 7 exceeds declared count 5");
+                }
+                _ => panic!("unexpected: {}", err),
             },
         }
     }
@@ -355,13 +1562,14 @@ mod test {
         let header = Header {
             num_variables: 2,
             num_clauses: 2,
+            independent_support: Vec::new(),
         };
-        let result = parse_formula(&mut src, &header);
+        let result = parse_formula(&mut src, &header, true, None);
         match result {
             Ok(_) => panic!(),
             Err(err) => match err {
                 Error::ClauseCount => {}
-                _ => panic!(format!("unexpected: {}", err)),
+                _ => panic!("unexpected: {}", err),
             },
         }
     }
@@ -372,13 +1580,14 @@ mod test {
         let header = Header {
             num_variables: 2,
             num_clauses: 2,
+            independent_support: Vec::new(),
         };
-        let result = parse_formula(&mut src, &header);
+        let result = parse_formula(&mut src, &header, true, None);
         match result {
             Ok(_) => panic!(),
             Err(err) => match err {
                 Error::ClauseCount => {}
-                _ => panic!(format!("unexpected: {}", err)),
+                _ => panic!("unexpected: {}", err),
             },
         }
     }
@@ -0,0 +1,538 @@
+use super::sat::{Assignment, Clause, Formula, Literal, Not, Var};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Boolean expression over variables indexed from zero.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Var(usize),
+    Not(Box<Expr>),
+    /// Conjunction of the operands. True if there is no operand.
+    And(Vec<Expr>),
+    /// Disjunction of the operands. False if there is no operand.
+    Or(Vec<Expr>),
+    Xor(Box<Expr>, Box<Expr>),
+    Imply(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Returns the number of variables, i.e., one plus the largest variable
+    /// index in the expression.
+    pub fn num_variables(&self) -> usize {
+        match self {
+            Expr::Var(i) => i + 1,
+            Expr::Not(e) => e.num_variables(),
+            Expr::And(es) | Expr::Or(es) => es.iter().map(Expr::num_variables).max().unwrap_or(0),
+            Expr::Xor(a, b) | Expr::Imply(a, b) => a.num_variables().max(b.num_variables()),
+        }
+    }
+
+    /// Evaluates the expression under an assignment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the assignment does not cover all the variables.
+    pub fn eval(&self, vars: &Assignment) -> bool {
+        match self {
+            Expr::Var(i) => vars[*i],
+            Expr::Not(e) => !e.eval(vars),
+            Expr::And(es) => es.iter().all(|e| e.eval(vars)),
+            Expr::Or(es) => es.iter().any(|e| e.eval(vars)),
+            Expr::Xor(a, b) => a.eval(vars) != b.eval(vars),
+            Expr::Imply(a, b) => !a.eval(vars) || b.eval(vars),
+        }
+    }
+}
+
+/// Converts an expression to an equisatisfiable CNF formula by the Tseitin
+/// transformation.
+///
+/// Each subexpression other than a variable or a negation is given an
+/// auxiliary variable t, which is constrained to be equivalent to the
+/// subexpression. An n-ary conjunction t ↔ a ∧ b ∧ ..., for example, becomes
+///
+/// > (¬t ∨ a) ∧ (¬t ∨ b) ∧ ... ∧ (t ∨ ¬a ∨ ¬b ∨ ...) .
+///
+/// The formula is then the conjunction of these constraints and the literal
+/// of the whole expression. Its size is linear in the size of the expression.
+///
+/// Returns the formula and the number n of the variables in the expression.
+/// The variables of the expression keep their indices and the auxiliary
+/// variables are numbered from n, so the first n values of a model of the
+/// formula give a model of the expression.
+pub fn tseitin(expr: &Expr) -> (Formula, usize) {
+    let n_vars = expr.num_variables();
+    let mut next_var = n_vars;
+    let mut formula = Formula::new();
+
+    let root = tseitin_literal(expr, &mut next_var, &mut formula);
+    formula.push(vec![root]);
+
+    (formula, n_vars)
+}
+
+/// Adds the Tseitin constraints of an expression to `formula` and returns the
+/// literal equivalent to the expression.
+fn tseitin_literal(expr: &Expr, next_var: &mut usize, formula: &mut Formula) -> Literal {
+    let (a, b) = match expr {
+        Expr::Var(i) => return Var(*i),
+        Expr::Not(e) => return tseitin_literal(e, next_var, formula).negate(),
+        Expr::And(es) | Expr::Or(es) => {
+            let lits: Vec<Literal> = es
+                .iter()
+                .map(|e| tseitin_literal(e, next_var, formula))
+                .collect();
+            let t = Var(*next_var);
+            *next_var += 1;
+
+            // A disjunction is the negation of the conjunction of negations.
+            let (conj, lits): (Literal, Vec<Literal>) = match expr {
+                Expr::And(_) => (t, lits),
+                _ => (t.negate(), lits.iter().map(|lit| lit.negate()).collect()),
+            };
+            for &lit in &lits {
+                formula.push(vec![conj.negate(), lit]);
+            }
+            let mut clause = vec![conj];
+            clause.extend(lits.iter().map(|lit| lit.negate()));
+            formula.push(clause);
+
+            return t;
+        }
+        Expr::Xor(a, b) | Expr::Imply(a, b) => (
+            tseitin_literal(a, next_var, formula),
+            tseitin_literal(b, next_var, formula),
+        ),
+    };
+
+    let t = Var(*next_var);
+    *next_var += 1;
+
+    if let Expr::Xor(..) = expr {
+        formula.push(vec![t.negate(), a, b]);
+        formula.push(vec![t.negate(), a.negate(), b.negate()]);
+        formula.push(vec![t, a.negate(), b]);
+        formula.push(vec![t, a, b.negate()]);
+    } else {
+        formula.push(vec![t, a]);
+        formula.push(vec![t, b.negate()]);
+        formula.push(vec![t.negate(), a.negate(), b]);
+    }
+
+    t
+}
+
+/// Encodes the constraint that at most `k` of the variables `vars` are true.
+///
+/// # Sequential counter
+///
+/// The encoding introduces auxiliary variables s(i, j) for i < n - 1 and j < k,
+/// where n is the number of variables. s(i, j) is forced to be true if at least
+/// j + 1 of the first i + 1 variables are true. The constraint is then violated
+/// exactly when a variable is true while k variables before it are true:
+///
+/// > x(i) ⇒ s(i, 0) ,  s(i - 1, j) ⇒ s(i, j) ,
+/// > x(i) ∧ s(i - 1, j - 1) ⇒ s(i, j) ,  x(i) ⇒ ¬s(i - 1, k - 1) .
+///
+/// This takes O(nk) clauses instead of the C(n, k + 1) clauses of the naive
+/// encoding.
+///
+/// # Auxiliary variables
+///
+/// The (n - 1)k auxiliary variables are numbered from `next_var`, which is
+/// advanced past them. No auxiliary variable is introduced if k is zero or at
+/// least n.
+pub fn encode_at_most_k(vars: &[usize], k: usize, next_var: &mut usize) -> Vec<Clause> {
+    let lits: Vec<Literal> = vars.iter().map(|&i| Var(i)).collect();
+    at_most_k(&lits, k, next_var)
+}
+
+/// Encodes the constraint that at least `k` of the variables `vars` are true.
+///
+/// This is the constraint that at most n - k of the variables are false, so it
+/// introduces the auxiliary variables of `encode_at_most_k` with n - k in place
+/// of k. The result has an empty clause if k is greater than n.
+pub fn encode_at_least_k(vars: &[usize], k: usize, next_var: &mut usize) -> Vec<Clause> {
+    if k > vars.len() {
+        return vec![Clause::new()];
+    }
+    let lits: Vec<Literal> = vars.iter().map(|&i| Not(i)).collect();
+    at_most_k(&lits, vars.len() - k, next_var)
+}
+
+/// Encodes the constraint that exactly `k` of the variables `vars` are true.
+///
+/// This is the conjunction of `encode_at_most_k` and `encode_at_least_k`, and
+/// introduces the auxiliary variables of both.
+pub fn encode_exactly_k(vars: &[usize], k: usize, next_var: &mut usize) -> Vec<Clause> {
+    let mut clauses = encode_at_most_k(vars, k, next_var);
+    clauses.extend(encode_at_least_k(vars, k, next_var));
+    clauses
+}
+
+/// Formula under construction with an allocator of fresh variables.
+///
+/// Variables are handed out by `new_var` in sequence from zero, and the
+/// constraints added through the builder number their auxiliary variables
+/// from the same counter, so no index is tracked by hand.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FormulaBuilder {
+    formula: Formula,
+    n_vars: usize,
+}
+
+impl FormulaBuilder {
+    /// Creates a builder of an empty formula without variables.
+    pub fn new() -> FormulaBuilder {
+        FormulaBuilder::default()
+    }
+
+    /// Allocates a fresh variable and returns its index.
+    pub fn new_var(&mut self) -> usize {
+        self.n_vars += 1;
+        self.n_vars - 1
+    }
+
+    /// Returns the number of variables allocated so far, including the
+    /// auxiliary variables of the constraints. A model of the formula has a
+    /// value for each variable used in a clause, which may be fewer.
+    pub fn num_vars(&self) -> usize {
+        self.n_vars
+    }
+
+    /// Adds a clause to the formula.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the clause refers to a variable not allocated.
+    pub fn add_clause(&mut self, clause: Clause) {
+        assert!(
+            clause.iter().all(|lit| lit.variable() < self.n_vars),
+            "clause refers to an unallocated variable"
+        );
+        self.formula.push(clause);
+    }
+
+    /// Adds the constraint that an expression over the allocated variables is
+    /// true, by the Tseitin transformation. See `tseitin`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the expression refers to a variable not allocated.
+    pub fn add_expr(&mut self, expr: &Expr) {
+        assert!(
+            expr.num_variables() <= self.n_vars,
+            "expression refers to an unallocated variable"
+        );
+        let root = tseitin_literal(expr, &mut self.n_vars, &mut self.formula);
+        self.formula.push(vec![root]);
+    }
+
+    /// Adds the constraint that at most `k` of the variables are true. See
+    /// `encode_at_most_k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the constraint refers to a variable not allocated.
+    pub fn add_at_most_k(&mut self, vars: &[usize], k: usize) {
+        self.check_allocated(vars);
+        let clauses = encode_at_most_k(vars, k, &mut self.n_vars);
+        self.formula.extend(clauses);
+    }
+
+    /// Adds the constraint that at least `k` of the variables are true. See
+    /// `encode_at_least_k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the constraint refers to a variable not allocated.
+    pub fn add_at_least_k(&mut self, vars: &[usize], k: usize) {
+        self.check_allocated(vars);
+        let clauses = encode_at_least_k(vars, k, &mut self.n_vars);
+        self.formula.extend(clauses);
+    }
+
+    /// Adds the constraint that exactly `k` of the variables are true. See
+    /// `encode_exactly_k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the constraint refers to a variable not allocated.
+    pub fn add_exactly_k(&mut self, vars: &[usize], k: usize) {
+        self.check_allocated(vars);
+        let clauses = encode_exactly_k(vars, k, &mut self.n_vars);
+        self.formula.extend(clauses);
+    }
+
+    fn check_allocated(&self, vars: &[usize]) {
+        assert!(
+            vars.iter().all(|&var| var < self.n_vars),
+            "constraint refers to an unallocated variable"
+        );
+    }
+
+    /// Returns the formula built so far.
+    pub fn formula(&self) -> &Formula {
+        &self.formula
+    }
+
+    /// Returns the formula, consuming the builder.
+    pub fn into_formula(self) -> Formula {
+        self.formula
+    }
+}
+
+/// Sequential counter encoding of the constraint that at most `k` of the
+/// literals `lits` are true.
+fn at_most_k(lits: &[Literal], k: usize, next_var: &mut usize) -> Vec<Clause> {
+    let n = lits.len();
+    let mut clauses = Vec::new();
+
+    if k >= n {
+        return clauses;
+    }
+
+    if k == 0 {
+        for lit in lits {
+            clauses.push(vec![lit.negate()]);
+        }
+        return clauses;
+    }
+
+    let base = *next_var;
+    let s = |i: usize, j: usize| base + i * k + j;
+    *next_var += (n - 1) * k;
+
+    for (i, &x) in lits.iter().enumerate() {
+        if i == n - 1 {
+            clauses.push(vec![x.negate(), Not(s(i - 1, k - 1))]);
+            break;
+        }
+
+        clauses.push(vec![x.negate(), Var(s(i, 0))]);
+
+        if i == 0 {
+            for j in 1..k {
+                clauses.push(vec![Not(s(0, j))]);
+            }
+            continue;
+        }
+
+        for j in 0..k {
+            clauses.push(vec![Not(s(i - 1, j)), Var(s(i, j))]);
+        }
+        for j in 1..k {
+            clauses.push(vec![x.negate(), Not(s(i - 1, j - 1)), Var(s(i, j))]);
+        }
+        clauses.push(vec![x.negate(), Not(s(i - 1, k - 1))]);
+    }
+
+    clauses
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sat::{self, Formula};
+
+    /// Checks that `encode` admits exactly the assignments of n variables for
+    /// which `count_ok` holds on the number of true variables.
+    fn check_encoding<E, C>(n: usize, encode: E, count_ok: C)
+    where
+        E: Fn(&[usize], &mut usize) -> Vec<Clause>,
+        C: Fn(usize) -> bool,
+    {
+        let vars: Vec<usize> = (0..n).collect();
+        let mut next_var = n;
+        let clauses = encode(&vars, &mut next_var);
+
+        for bits in 0..1usize << n {
+            let mut formula: Formula = clauses.clone();
+            for i in 0..n {
+                formula.push(vec![if bits >> i & 1 == 1 { Var(i) } else { Not(i) }]);
+            }
+
+            let count = bits.count_ones() as usize;
+            match sat::check_sat(&formula) {
+                Some(model) => {
+                    assert!(count_ok(count));
+                    assert!(sat::verify(&formula, &model));
+                }
+                None => assert!(!count_ok(count)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_at_most_k() {
+        for n in 1..6 {
+            for k in 0..n + 2 {
+                check_encoding(n, |vars, next| encode_at_most_k(vars, k, next), |c| c <= k);
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_at_least_k() {
+        for n in 1..6 {
+            for k in 0..n + 2 {
+                check_encoding(n, |vars, next| encode_at_least_k(vars, k, next), |c| c >= k);
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_exactly_k() {
+        for n in 1..6 {
+            for k in 0..n + 2 {
+                check_encoding(n, |vars, next| encode_exactly_k(vars, k, next), |c| c == k);
+            }
+        }
+    }
+
+    fn and(es: Vec<Expr>) -> Expr {
+        Expr::And(es)
+    }
+
+    fn or(es: Vec<Expr>) -> Expr {
+        Expr::Or(es)
+    }
+
+    fn not(e: Expr) -> Expr {
+        Expr::Not(Box::new(e))
+    }
+
+    fn xor(a: Expr, b: Expr) -> Expr {
+        Expr::Xor(Box::new(a), Box::new(b))
+    }
+
+    fn imply(a: Expr, b: Expr) -> Expr {
+        Expr::Imply(Box::new(a), Box::new(b))
+    }
+
+    fn x(i: usize) -> Expr {
+        Expr::Var(i)
+    }
+
+    #[test]
+    fn test_tseitin_model() {
+        // (x0 ^ x1) & (x1 -> x2) & !(x2 & x0)
+        let expr = and(vec![
+            xor(x(0), x(1)),
+            imply(x(1), x(2)),
+            not(and(vec![x(2), x(0)])),
+        ]);
+        let (formula, n_vars) = tseitin(&expr);
+        assert_eq!(n_vars, 3);
+
+        let model = sat::check_sat(&formula).unwrap();
+        assert!(sat::verify(&formula, &model));
+        assert!(expr.eval(&model[..n_vars].to_vec()));
+
+        let (formula, _) = tseitin(&and(vec![x(0), not(x(0))]));
+        assert!(sat::check_sat(&formula).is_none());
+    }
+
+    #[test]
+    fn test_tseitin_equisatisfiable() {
+        let exprs = vec![
+            x(0),
+            not(x(1)),
+            and(vec![]),
+            or(vec![]),
+            or(vec![x(0), not(x(1)), and(vec![x(2), x(1)])]),
+            xor(x(0), xor(x(1), x(2))),
+            imply(or(vec![x(0), x(1)]), not(imply(x(2), x(0)))),
+            and(vec![or(vec![x(0), x(1)]), xor(not(x(0)), x(2)), x(1)]),
+        ];
+
+        for expr in &exprs {
+            let (formula, n_vars) = tseitin(expr);
+
+            // Fixing the original variables leaves a satisfiable formula if
+            // and only if the expression is true.
+            for bits in 0..1usize << n_vars {
+                let vars: Assignment = (0..n_vars).map(|i| bits >> i & 1 == 1).collect();
+                let mut fixed = formula.clone();
+                for (i, &truth) in vars.iter().enumerate() {
+                    fixed.push(vec![if truth { Var(i) } else { Not(i) }]);
+                }
+                assert_eq!(sat::check_sat(&fixed).is_some(), expr.eval(&vars));
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_auxiliary_variables() {
+        let mut next_var = 10;
+        let clauses = encode_at_most_k(&[0, 1, 2, 3], 2, &mut next_var);
+        assert_eq!(next_var, 10 + 3 * 2);
+
+        for lit in clauses.iter().flatten() {
+            let i = lit.variable();
+            assert!(i < 4 || (10..16).contains(&i));
+        }
+
+        let mut next_var = 4;
+        encode_at_most_k(&[0, 1, 2, 3], 0, &mut next_var);
+        encode_at_most_k(&[0, 1, 2, 3], 4, &mut next_var);
+        assert_eq!(next_var, 4);
+    }
+
+    #[test]
+    fn test_formula_builder() {
+        let mut builder = FormulaBuilder::new();
+        let vars: Vec<usize> = (0..4).map(|_| builder.new_var()).collect();
+        assert_eq!(vars, vec![0, 1, 2, 3]);
+        assert_eq!(builder.num_vars(), 4);
+
+        // Exactly two of the four, the first implies the second, and not the
+        // last.
+        builder.add_exactly_k(&vars, 2);
+        let aux = builder.num_vars();
+        assert!(aux > 4);
+        builder.add_expr(&Expr::Imply(
+            Box::new(Expr::Var(vars[0])),
+            Box::new(Expr::Var(vars[1])),
+        ));
+        assert!(builder.num_vars() > aux);
+        builder.add_clause(vec![Not(vars[3])]);
+
+        // Fresh variables continue after the auxiliary ones.
+        let extra = builder.new_var();
+        assert_eq!(extra, builder.num_vars() - 1);
+        builder.add_clause(vec![Var(extra), Not(vars[2])]);
+
+        let formula = builder.formula().clone();
+        assert!(formula
+            .iter()
+            .flatten()
+            .all(|lit| lit.variable() < builder.num_vars()));
+        let model = sat::check_sat(&formula).unwrap();
+        assert!(sat::verify(&formula, &model));
+        let count = vars.iter().filter(|&&i| model[i]).count();
+        assert_eq!(count, 2);
+        assert!(!model[vars[0]] || model[vars[1]]);
+        assert!(!model[vars[3]]);
+
+        builder.add_at_least_k(&vars, 3);
+        assert_eq!(sat::check_sat(&builder.into_formula()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "unallocated variable")]
+    fn test_formula_builder_unallocated() {
+        let mut builder = FormulaBuilder::new();
+        let var = builder.new_var();
+        builder.add_clause(vec![Var(var), Var(var + 1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "unallocated variable")]
+    fn test_formula_builder_unallocated_constraint() {
+        // The auxiliary variables would collide with the unallocated ones.
+        let mut builder = FormulaBuilder::new();
+        builder.add_at_most_k(&[0, 1, 2], 1);
+    }
+}
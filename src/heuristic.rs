@@ -0,0 +1,778 @@
+use super::sat::{
+    self, ClauseStatus, ClauseStore, FlatFormula, Formula, LazyClauses, Literal, Not, Var,
+};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Decision heuristic of the DPLL search in `sat::check_sat_with`.
+pub trait Heuristic {
+    /// Chooses a branching literal, which is tried to be true first.
+    ///
+    /// `formula` is simplified by the assignments made so far, so every
+    /// variable in it is unassigned. It has at least one clause and no empty
+    /// clause. `n_vars` is the number of variables of the original formula.
+    fn choose(&mut self, formula: &Formula, n_vars: usize) -> Literal;
+
+    /// Chooses a branching literal like `choose`, knowing where the clauses
+    /// come from: `formula[i]` is what remains of the clause numbered
+    /// `origins[i]` in the formula given to the search, or a clause added by
+    /// the search if the number is past its clauses.
+    ///
+    /// The search calls this instead of `choose` when it tracks the origins,
+    /// which it does unless `choose_by_counts` is used. Calls `choose` by
+    /// default.
+    fn choose_with_origins(
+        &mut self,
+        formula: &Formula,
+        origins: &[usize],
+        n_vars: usize,
+    ) -> Literal {
+        let _ = origins;
+        self.choose(formula, n_vars)
+    }
+
+    /// Chooses a branching literal like `choose`, but only from the number of
+    /// occurrences of each literal in the formula, indexed by `Literal::code`.
+    ///
+    /// The search keeps these counts up to date as the formula is simplified,
+    /// which saves scanning the formula at every decision. Returns None by
+    /// default, in which case the search falls back to `choose` for the rest
+    /// of the search. So, a heuristic must either always or never return a
+    /// literal.
+    fn choose_by_counts(&mut self, _counts: &[u64]) -> Option<Literal> {
+        None
+    }
+}
+
+/// Name of the default heuristic accepted by `by_name`.
+pub const DEFAULT_NAME: &str = "dominant";
+
+/// Returns the heuristic of a name: "dominant" for `DominantVariable`,
+/// "dominant-phase" for `DominantPhase`, "jeroslow-wang" for `JeroslowWang`,
+/// "least-frequent" for `LeastFrequent` or "look-ahead" for `LookAhead`.
+/// Returns None for an unknown name.
+pub fn by_name(name: &str) -> Option<Box<dyn Heuristic>> {
+    match name {
+        "dominant" => Some(Box::new(DominantVariable)),
+        "dominant-phase" => Some(Box::new(DominantPhase)),
+        "jeroslow-wang" => Some(Box::new(JeroslowWang)),
+        "least-frequent" => Some(Box::new(LeastFrequent)),
+        "look-ahead" => Some(Box::new(LookAhead::default())),
+        _ => None,
+    }
+}
+
+/// Chooses the variable occurring the most in the formula, which is tried to be
+/// true first. This is the default heuristic.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DominantVariable;
+
+impl Heuristic for DominantVariable {
+    fn choose(&mut self, formula: &Formula, n_vars: usize) -> Literal {
+        let (var, _) = find_dominant_variable(formula, n_vars, |_| 1);
+        Var(var)
+    }
+
+    fn choose_by_counts(&mut self, counts: &[u64]) -> Option<Literal> {
+        let (var, _) = find_dominant_by_counts(counts);
+        Some(Var(var))
+    }
+}
+
+/// Chooses the variable occurring the most in the formula like
+/// `DominantVariable`, but in the polarity occurring more often, which
+/// satisfies more clauses. This is not always faster, so it is not the
+/// default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DominantPhase;
+
+impl Heuristic for DominantPhase {
+    fn choose(&mut self, formula: &Formula, n_vars: usize) -> Literal {
+        let (var, phase) = find_dominant_variable(formula, n_vars, |_| 1);
+        if phase {
+            Var(var)
+        } else {
+            Not(var)
+        }
+    }
+
+    fn choose_by_counts(&mut self, counts: &[u64]) -> Option<Literal> {
+        let (var, phase) = find_dominant_by_counts(counts);
+        Some(if phase { Var(var) } else { Not(var) })
+    }
+}
+
+/// Chooses the variable with the largest total weight of the clauses it occurs
+/// in, in the polarity of the larger weight. This is `DominantPhase` with
+/// weighted clauses: variables in heavy clauses are decided first, which lets
+/// soft preferences steer the search. Every clause weighs one by default.
+///
+/// The weights are given for the clauses of the original formula, and the
+/// search tells which original clause each simplified clause comes from. When
+/// called by `choose` without the origins, the formula is taken to be the
+/// original one.
+#[derive(Clone, Debug, Default)]
+pub struct WeightedDominant {
+    weights: Vec<u64>,
+}
+
+impl WeightedDominant {
+    /// Creates the heuristic for a formula whose i-th clause has weight
+    /// `weights[i]`. Clauses beyond the weights weigh one, and so does a weight
+    /// of zero.
+    pub fn new(weights: &[u64]) -> WeightedDominant {
+        WeightedDominant {
+            weights: weights.to_vec(),
+        }
+    }
+
+    fn weight(&self, origin: usize) -> u64 {
+        self.weights.get(origin).copied().unwrap_or(1).max(1)
+    }
+}
+
+impl Heuristic for WeightedDominant {
+    fn choose(&mut self, formula: &Formula, n_vars: usize) -> Literal {
+        let origins: Vec<usize> = (0..formula.len()).collect();
+        self.choose_with_origins(formula, &origins, n_vars)
+    }
+
+    fn choose_with_origins(
+        &mut self,
+        formula: &Formula,
+        origins: &[usize],
+        n_vars: usize,
+    ) -> Literal {
+        let (var, phase) = find_dominant_variable(formula, n_vars, |i| self.weight(origins[i]));
+        if phase {
+            Var(var)
+        } else {
+            Not(var)
+        }
+    }
+}
+
+/// Chooses the variable occurring in the most recently added clause, the one
+/// occurring the most and then the lowest on a tie, in the polarity occurring
+/// more often. Recently added clauses encode the newest constraints, so
+/// deciding their variables first tends to resolve the new part of an
+/// incrementally grown formula quickly. Without added clauses, this is
+/// `DominantPhase`.
+///
+/// The recency of a variable is the position of the last clause containing it
+/// among the clauses added by `new` and `add_clause`. The heuristic does not
+/// rely on the order of the clauses it sees, which the search rearranges.
+#[derive(Clone, Debug, Default)]
+pub struct RecentBias {
+    recency: Vec<usize>,
+    n_clauses: usize,
+}
+
+impl RecentBias {
+    /// Creates the heuristic with the clauses of a formula added in order.
+    pub fn new(formula: &Formula) -> RecentBias {
+        let mut heuristic = RecentBias::default();
+        for clause in formula {
+            heuristic.add_clause(clause);
+        }
+        heuristic
+    }
+
+    /// Records a clause added to the formula as the most recent one.
+    pub fn add_clause(&mut self, clause: &[Literal]) {
+        self.n_clauses += 1;
+        for lit in clause {
+            let var = lit.variable();
+            if var >= self.recency.len() {
+                self.recency.resize(var + 1, 0);
+            }
+            self.recency[var] = self.n_clauses;
+        }
+    }
+}
+
+impl Heuristic for RecentBias {
+    fn choose(&mut self, formula: &Formula, n_vars: usize) -> Literal {
+        let mut counts = vec![0; 2 * n_vars];
+        for lit in formula.iter().flatten() {
+            counts[lit.code()] += 1;
+        }
+
+        let recency = |var: usize| self.recency.get(var).copied().unwrap_or(0);
+        let var = (0..n_vars)
+            .filter(|&i| counts[2 * i] + counts[2 * i + 1] > 0)
+            .max_by_key(|&i| {
+                let freq = counts[2 * i] + counts[2 * i + 1];
+                (recency(i), freq, core::cmp::Reverse(i))
+            })
+            .unwrap_or(0);
+
+        if counts[2 * var] >= counts[2 * var + 1] {
+            Var(var)
+        } else {
+            Not(var)
+        }
+    }
+}
+
+/// Jeroslow-Wang heuristic. Chooses the literal maximizing the score
+///
+/// > J(l) = Σ 2^(-|C|) ,
+///
+/// where the sum is over the clauses C containing the literal l. Occurrences
+/// in short clauses weigh more as these clauses are closer to becoming units.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JeroslowWang;
+
+impl Heuristic for JeroslowWang {
+    fn choose(&mut self, formula: &Formula, n_vars: usize) -> Literal {
+        let mut scores = vec![0.0; 2 * n_vars];
+
+        for clause in formula {
+            // f64::powi is not available without std. Halving is exact.
+            let weight = (0..clause.len()).fold(1.0, |weight, _| weight / 2.0);
+            for lit in clause {
+                scores[lit.code()] += weight;
+            }
+        }
+
+        let mut max = 0.0;
+        let mut argmax = 0;
+
+        for (code, &score) in scores.iter().enumerate() {
+            if score > max {
+                max = score;
+                argmax = code;
+            }
+        }
+
+        if argmax.is_multiple_of(2) {
+            Var(argmax / 2)
+        } else {
+            Not(argmax / 2)
+        }
+    }
+}
+
+/// Chooses the variable occurring the least in the formula, in the polarity
+/// occurring more often. The opposite of `DominantVariable`: a rare variable
+/// tends to sit in small clauses, which are quickly satisfied or falsified.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LeastFrequent;
+
+impl Heuristic for LeastFrequent {
+    fn choose(&mut self, formula: &Formula, n_vars: usize) -> Literal {
+        let mut positives = vec![0; n_vars];
+        let mut negatives = vec![0; n_vars];
+
+        for clause in formula {
+            for lit in clause {
+                match *lit {
+                    Var(i) => positives[i] += 1,
+                    Not(i) => negatives[i] += 1,
+                }
+            }
+        }
+
+        // Variables not occurring in the formula are assigned or irrelevant.
+        let var = (0..n_vars)
+            .filter(|&i| positives[i] + negatives[i] > 0)
+            .min_by_key(|&i| positives[i] + negatives[i])
+            .unwrap_or(0);
+
+        if positives[var] >= negatives[var] {
+            Var(var)
+        } else {
+            Not(var)
+        }
+    }
+}
+
+/// Look-ahead heuristic. Tentatively assigns each candidate variable in both
+/// phases, runs unit propagation, and chooses the variable whose phases
+/// satisfy the most clauses. The score of a variable is
+///
+/// > (s(x) + 1) (s(¬x) + 1) ,
+///
+/// where s(l) is the number of clauses satisfied after propagating l, so that
+/// both branches are balanced. The phase satisfying more clauses is tried
+/// first. If a phase leads to a conflict, the opposite literal is forced and
+/// chosen immediately.
+///
+/// Probing costs two propagations over the whole formula per candidate, so
+/// only the `max_candidates` most frequent variables are probed.
+#[derive(Clone, Copy, Debug)]
+pub struct LookAhead {
+    pub max_candidates: usize,
+}
+
+impl Default for LookAhead {
+    fn default() -> LookAhead {
+        LookAhead { max_candidates: 10 }
+    }
+}
+
+impl Heuristic for LookAhead {
+    fn choose(&mut self, formula: &Formula, n_vars: usize) -> Literal {
+        let mut counts = vec![0; n_vars];
+        for lit in formula.iter().flatten() {
+            counts[lit.variable()] += 1;
+        }
+
+        let mut candidates: Vec<usize> = (0..n_vars).filter(|&i| counts[i] > 0).collect();
+        candidates.sort_by_key(|&i| core::cmp::Reverse(counts[i]));
+        candidates.truncate(self.max_candidates.max(1));
+
+        let mut best = (0, Var(candidates[0]));
+        let flat = FlatFormula::from(formula);
+
+        for &var in &candidates {
+            let positive = probe(&flat, n_vars, Var(var));
+            let negative = probe(&flat, n_vars, Not(var));
+
+            let (pos, neg) = match (positive, negative) {
+                (Some(_), None) => return Var(var),
+                (None, Some(_)) => return Not(var),
+                (None, None) => return Var(var),
+                (Some(pos), Some(neg)) => (pos, neg),
+            };
+
+            let score = (pos + 1) * (neg + 1);
+            if score > best.0 {
+                let lit = if pos >= neg { Var(var) } else { Not(var) };
+                best = (score, lit);
+            }
+        }
+
+        best.1
+    }
+}
+
+/// Propagates literal `lit` on a formula without modifying it. Returns the
+/// number of clauses satisfied, or None if a clause is falsified.
+fn probe(formula: &FlatFormula, n_vars: usize, lit: Literal) -> Option<usize> {
+    let mut clauses = LazyClauses::from_flat(formula, n_vars);
+    let mut vars = vec![false; n_vars];
+    clauses.assign(lit.variable(), matches!(lit, Var(_)));
+    sat::propagate_units(&mut clauses, &mut vars);
+
+    if clauses.has_conflict() {
+        return None;
+    }
+    let satisfied = (0..formula.len())
+        .filter(|&i| clauses.status(i) == ClauseStatus::Satisfied)
+        .count();
+    Some(satisfied)
+}
+
+/// Finds the variable occurring the most in a formula, counting each
+/// occurrence in the i-th clause `weight(i)` times. Returns the variable and
+/// the polarity in which it occurs more often, preferring true on a tie.
+/// Satisfying the more frequent literal satisfies more clauses.
+fn find_dominant_variable(
+    formula: &Formula,
+    n_vars: usize,
+    weight: impl Fn(usize) -> u64,
+) -> (usize, bool) {
+    let mut counts = vec![0; 2 * n_vars];
+
+    for (clause_index, clause) in formula.iter().enumerate() {
+        let weight = weight(clause_index);
+        for lit in clause {
+            counts[lit.code()] += weight;
+        }
+    }
+
+    find_dominant_by_counts(&counts)
+}
+
+/// Finds the dominant variable like `find_dominant_variable` from the number
+/// of occurrences of each literal, indexed by `Literal::code`.
+fn find_dominant_by_counts(counts: &[u64]) -> (usize, bool) {
+    let mut max: u64 = 0;
+    let mut argmax: usize = 0;
+
+    for i in 0..counts.len() / 2 {
+        let freq = counts[2 * i] + counts[2 * i + 1];
+        if freq > max {
+            max = freq;
+            argmax = i;
+        }
+    }
+
+    (argmax, counts[2 * argmax] >= counts[2 * argmax + 1])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dimacs;
+    use crate::sat::{self, Assignment};
+    use crate::solver::Solver;
+    use crate::testing::random_3sat;
+
+    #[test]
+    fn test_find_dominant_variable() {
+        let formula = vec![
+            vec![Var(0), Var(1), Var(2)],
+            vec![Not(0), Var(1)],
+            vec![Not(1), Var(2)],
+            vec![Var(0), Not(1), Not(2)],
+        ];
+        assert_eq!(find_dominant_variable(&formula, 3, |_| 1), (1, true));
+
+        let formula = vec![
+            vec![Not(0), Var(1)],
+            vec![Not(0), Not(1)],
+            vec![Var(0), Not(2)],
+            vec![Not(0), Var(2)],
+        ];
+        assert_eq!(find_dominant_variable(&formula, 3, |_| 1), (0, false));
+
+        // Weighting the third clause flips the polarity.
+        assert_eq!(
+            find_dominant_variable(&formula, 3, |i| [1, 1, 5, 1][i]),
+            (0, true)
+        );
+    }
+
+    #[test]
+    fn test_weighted_dominant() {
+        let formula = vec![
+            vec![Not(0), Var(1)],
+            vec![Not(0), Not(1)],
+            vec![Not(2), Var(3), Var(4)],
+            vec![Not(0), Var(2)],
+        ];
+        let choose = |weights: &[u64]| WeightedDominant::new(weights).choose(&formula, 5);
+        assert_eq!(choose(&[]), Not(0));
+        assert_eq!(choose(&[1, 1, 0, 1]), Not(0));
+        assert_eq!(choose(&[1, 1, 5]), Not(2));
+
+        // The weight follows the origin of a simplified clause, wherever the
+        // search moved it.
+        let simplified = vec![
+            vec![Not(0), Var(2)],
+            vec![Not(2), Var(3)],
+            vec![Not(0), Var(1)],
+        ];
+        let mut heuristic = WeightedDominant::new(&[1, 1, 5]);
+        assert_eq!(
+            heuristic.choose_with_origins(&simplified, &[3, 2, 0], 5),
+            Not(2)
+        );
+        assert_eq!(
+            heuristic.choose_with_origins(&simplified, &[3, 1, 0], 5),
+            Not(0)
+        );
+    }
+
+    #[test]
+    fn test_weighted_dominant_search() {
+        /// Records the decisions of the heuristic.
+        struct Recording(WeightedDominant, Vec<Literal>);
+
+        impl Heuristic for Recording {
+            fn choose(&mut self, formula: &Formula, n_vars: usize) -> Literal {
+                let lit = self.0.choose(formula, n_vars);
+                self.1.push(lit);
+                lit
+            }
+
+            fn choose_with_origins(
+                &mut self,
+                formula: &Formula,
+                origins: &[usize],
+                n_vars: usize,
+            ) -> Literal {
+                let lit = self.0.choose_with_origins(formula, origins, n_vars);
+                self.1.push(lit);
+                lit
+            }
+        }
+
+        // Deciding 5 shortens the heavy second clause, whose weight then makes
+        // 3 the next decision although 0, 1 and 7 occur more.
+        let formula = vec![
+            vec![Var(5), Var(6)],
+            vec![Not(5), Var(3), Var(4)],
+            vec![Var(0), Var(1), Var(7)],
+            vec![Var(0), Not(1), Var(7)],
+            vec![Not(0), Var(1), Not(7)],
+            vec![Not(0), Not(1), Not(7)],
+        ];
+        let weights = [20, 20];
+
+        for &mode in &[sat::SimplifyMode::Eager, sat::SimplifyMode::Lazy] {
+            let mut heuristic = Recording(WeightedDominant::new(&weights), Vec::new());
+            let vars = sat::check_sat_with_mode(&formula, &mut heuristic, mode).unwrap();
+            assert!(sat::verify(&formula, &vars));
+            assert_eq!(heuristic.1[..2], [Var(5), Var(3)]);
+        }
+    }
+
+    #[test]
+    fn test_recent_bias() {
+        let mut formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Var(0), Not(2)],
+            vec![Not(0), Var(1), Var(2)],
+            vec![Var(0), Not(1)],
+        ];
+        let mut heuristic = RecentBias::new(&formula);
+        assert_eq!(heuristic.choose(&formula, 5), Var(0));
+        assert_eq!(
+            RecentBias::default().choose(&formula, 5),
+            DominantPhase.choose(&formula, 5)
+        );
+
+        // A tie in frequency goes to the lower variable, as in `DominantPhase`.
+        let tied = vec![vec![Var(0), Var(1)], vec![Not(0), Not(1), Var(2)]];
+        assert_eq!(DominantPhase.choose(&tied, 3), Var(0));
+        assert_eq!(RecentBias::default().choose(&tied, 3), Var(0));
+        assert_eq!(RecentBias::new(&tied).choose(&tied, 3), Var(0));
+
+        // Variable 1 of the new clause ties with 0 in frequency.
+        let clause = vec![Not(3), Var(4), Not(1)];
+        heuristic.add_clause(&clause);
+        formula.push(clause);
+        assert_eq!(DominantVariable.choose(&formula, 5), Var(0));
+        assert_eq!(heuristic.choose(&formula, 5), Var(1));
+
+        // The choice does not depend on the order of the clauses.
+        formula.swap(0, 4);
+        assert_eq!(heuristic.choose(&formula, 5), Var(1));
+
+        // Only variable 4 is left of the new clause, which is the most recent.
+        let simplified = vec![
+            vec![Var(0), Not(2)],
+            vec![Not(0), Var(2)],
+            vec![Var(4), Var(2)],
+        ];
+        assert_eq!(heuristic.choose(&simplified, 5), Var(4));
+    }
+
+    #[test]
+    fn test_recent_bias_verdicts() {
+        let source = include_str!("../examples/negative_3.cnf");
+        let mut formula = match dimacs::load(&mut source.as_bytes(), true) {
+            Ok(formula) => formula,
+            Err(err) => panic!("unexpected: {}", err),
+        };
+        let mut heuristic = RecentBias::new(&formula);
+        let vars = sat::check_sat_with(&formula, &mut heuristic).unwrap();
+        assert!(sat::verify(&formula, &vars));
+
+        // Forbid the model found, and then all the models of two variables.
+        for clause in [
+            vars.iter()
+                .enumerate()
+                .map(|(i, &truth)| if truth { Not(i) } else { Var(i) })
+                .collect(),
+            vec![Var(0), Var(1)],
+            vec![Var(0), Not(1)],
+            vec![Not(0), Var(1)],
+            vec![Not(0), Not(1)],
+        ]
+        .iter()
+        {
+            heuristic.add_clause(clause);
+            formula.push(clause.clone());
+            let expect = Solver::new(&formula).solve().is_some();
+            let actual = sat::check_sat_with(&formula, &mut heuristic);
+            assert_eq!(actual.is_some(), expect);
+            if let Some(vars) = actual {
+                assert!(sat::verify(&formula, &vars));
+            }
+        }
+    }
+
+    #[test]
+    fn test_weighted_dominant_verdicts() {
+        let formula = dimacs::load(
+            &mut include_str!("../examples/negative_3.cnf").as_bytes(),
+            true,
+        )
+        .unwrap();
+        let mut formulas = vec![formula];
+
+        // Random 3-SAT around the threshold ratio, both SAT and UNSAT.
+        let mut rng = crate::rng::Rng::new(5);
+        for _ in 0..30 {
+            let formula: Formula = random_3sat(&mut rng, 20, 85);
+            formulas.push(formula);
+        }
+
+        for formula in &formulas {
+            let weights: Vec<u64> = (0..formula.len() as u64).map(|i| i % 7).collect();
+            let mut heuristic = WeightedDominant::new(&weights);
+
+            let expect = sat::check_sat(formula).is_some();
+            let actual = sat::check_sat_with(formula, &mut heuristic);
+            assert_eq!(actual.is_some(), expect);
+            if let Some(vars) = actual {
+                assert!(sat::verify(formula, &vars));
+            }
+        }
+    }
+
+    #[test]
+    fn test_by_name() {
+        assert!(by_name(DEFAULT_NAME).is_some());
+        assert!(by_name("jeroslow-wang").is_some());
+        assert!(by_name("least-frequent").is_some());
+        assert!(by_name("look-ahead").is_some());
+        assert!(by_name("unknown").is_none());
+    }
+
+    #[test]
+    fn test_jeroslow_wang() {
+        // Variable 0 occurs the most, but !1 occurs in the short clauses.
+        let formula = vec![
+            vec![Var(0), Var(2), Var(3)],
+            vec![Var(0), Var(4), Var(5)],
+            vec![Var(0), Var(6), Var(7)],
+            vec![Var(0), Var(8), Var(9)],
+            vec![Not(1), Var(2)],
+            vec![Not(1), Var(4)],
+            vec![Not(1), Var(6)],
+        ];
+        assert_eq!(DominantVariable.choose(&formula, 10), Var(0));
+        assert_eq!(JeroslowWang.choose(&formula, 10), Not(1));
+    }
+
+    #[test]
+    fn test_jeroslow_wang_verdicts() {
+        let sources = [
+            include_str!("../examples/negative_3.cnf"),
+            include_str!("../examples/qg3-08.cnf"),
+        ];
+
+        for source in sources.iter() {
+            let formula = match dimacs::load(&mut source.as_bytes(), true) {
+                Ok(formula) => formula,
+                Err(err) => panic!("unexpected: {}", err),
+            };
+            let expect = sat::check_sat(&formula).is_some();
+            let actual = sat::check_sat_with(&formula, &mut JeroslowWang);
+            assert_eq!(actual.is_some(), expect);
+            if let Some(vars) = actual {
+                assert!(sat::verify(&formula, &vars));
+            }
+        }
+
+        // Not Horn nor 2-SAT, so the heuristic is used. UNSAT as a whole.
+        let mut formula = Vec::new();
+        for bits in 0..8 {
+            let clause = (0..3)
+                .map(|i| if bits >> i & 1 == 1 { Var(i) } else { Not(i) })
+                .collect();
+            formula.push(clause);
+        }
+        assert!(sat::check_sat_with(&formula, &mut JeroslowWang).is_none());
+
+        formula.pop();
+        let vars: Assignment = sat::check_sat_with(&formula, &mut JeroslowWang).unwrap();
+        assert!(sat::verify(&formula, &vars));
+    }
+
+    #[test]
+    fn test_least_frequent() {
+        // Variable 3 occurs once, and variable 4 not at all.
+        let formula = vec![
+            vec![Var(0), Var(1), Not(3)],
+            vec![Not(0), Var(1), Var(2)],
+            vec![Var(0), Not(1), Not(2)],
+            vec![Not(0), Var(2)],
+        ];
+        assert_eq!(LeastFrequent.choose(&formula, 5), Not(3));
+
+        // Polarity follows the more frequent literal.
+        let formula = vec![vec![Not(0), Var(1)], vec![Not(0), Not(1), Var(1)]];
+        assert_eq!(LeastFrequent.choose(&formula, 2), Not(0));
+    }
+
+    #[test]
+    fn test_least_frequent_verdicts() {
+        let sources = [
+            include_str!("../examples/negative_3.cnf"),
+            include_str!("../examples/qg3-08.cnf"),
+        ];
+
+        for source in sources.iter() {
+            let formula = match dimacs::load(&mut source.as_bytes(), true) {
+                Ok(formula) => formula,
+                Err(err) => panic!("unexpected: {}", err),
+            };
+            let expect = sat::check_sat(&formula).is_some();
+            let actual = sat::check_sat_with(&formula, &mut LeastFrequent);
+            assert_eq!(actual.is_some(), expect);
+            if let Some(vars) = actual {
+                assert!(sat::verify(&formula, &vars));
+            }
+        }
+
+        let formula = vec![
+            vec![Var(0), Var(1), Var(2)],
+            vec![Var(0), Var(1), Not(2)],
+            vec![Var(0), Not(1), Var(2)],
+            vec![Var(0), Not(1), Not(2)],
+            vec![Not(0), Var(1)],
+            vec![Not(0), Not(1)],
+        ];
+        assert!(sat::check_sat_with(&formula, &mut LeastFrequent).is_none());
+    }
+
+    #[test]
+    fn test_look_ahead() {
+        // Variable 0 occurs the most, but 1 propagates to satisfy more.
+        let formula = vec![
+            vec![Var(0), Var(2), Var(3)],
+            vec![Var(0), Var(4), Var(5)],
+            vec![Not(0), Var(6), Var(7)],
+            vec![Not(0), Var(8), Var(9)],
+            vec![Not(1), Var(2)],
+            vec![Not(1), Var(4)],
+            vec![Var(1), Var(6)],
+        ];
+        assert_eq!(DominantVariable.choose(&formula, 10), Var(0));
+        assert_eq!(LookAhead::default().choose(&formula, 10), Var(1));
+
+        // Variable 0 false falsifies the last clause, so 0 is forced.
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Var(0), Not(1), Var(2)],
+            vec![Var(0), Not(2)],
+            vec![Not(0), Var(1), Var(2)],
+        ];
+        assert_eq!(LookAhead::default().choose(&formula, 3), Var(0));
+
+        let mut limited = LookAhead { max_candidates: 1 };
+        assert_eq!(limited.choose(&formula, 3).variable(), 0);
+    }
+
+    #[test]
+    fn test_look_ahead_verdicts() {
+        let source = include_str!("../examples/negative_3.cnf");
+        let formula = match dimacs::load(&mut source.as_bytes(), true) {
+            Ok(formula) => formula,
+            Err(err) => panic!("unexpected: {}", err),
+        };
+        let vars = sat::check_sat_with(&formula, &mut LookAhead::default()).unwrap();
+        assert!(sat::verify(&formula, &vars));
+
+        let mut formula = Vec::new();
+        for bits in 0..16 {
+            let clause = (0..4)
+                .map(|i| if bits >> i & 1 == 1 { Var(i) } else { Not(i) })
+                .collect();
+            formula.push(clause);
+        }
+        assert!(sat::check_sat_with(&formula, &mut LookAhead::default()).is_none());
+
+        formula.swap_remove(5);
+        let vars = sat::check_sat_with(&formula, &mut LookAhead::default()).unwrap();
+        assert!(sat::verify(&formula, &vars));
+    }
+}
@@ -0,0 +1,80 @@
+//! The solver core (`sat`, `solver` and the other modules working on formulas)
+//! only needs `alloc`, so the crate can be built for `no_std` environments by
+//! disabling the default `std` feature. Parsing DIMACS and SMT-LIB, the
+//! command-line program and parallel solving require `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod cli;
+#[cfg(feature = "std")]
+pub mod dimacs;
+pub mod encode;
+pub mod heuristic;
+pub mod local_search;
+pub mod preprocess;
+pub mod sat;
+#[cfg(feature = "std")]
+pub mod smtlib;
+pub mod solver;
+pub mod streaming;
+
+mod bits;
+mod rng;
+#[cfg(test)]
+mod testing;
+
+use alloc::vec::Vec;
+use sat::{Literal, Not, Var};
+
+/// Solves a CNF formula given as clauses of one-based signed indices as in
+/// DIMACS: a positive number `i` means the i-th variable and a negative number
+/// `-i` means its negation.
+///
+/// Returns a satisfying assignment in the same signed form, covering every
+/// variable up to the largest one used, or None if the formula is
+/// unsatisfiable.
+///
+/// # Panics
+///
+/// Panics if a clause contains zero.
+pub fn solve(clauses: &[&[i32]]) -> Option<Vec<i32>> {
+    let formula: sat::Formula = clauses
+        .iter()
+        .map(|clause| {
+            clause
+                .iter()
+                .map(|&value| Literal::from_dimacs(value))
+                .collect()
+        })
+        .collect();
+
+    let vars = solver::Solver::new(&formula).solve()?;
+
+    let model = vars
+        .iter()
+        .enumerate()
+        .map(|(i, &truth)| if truth { Var(i) } else { Not(i) }.to_dimacs())
+        .collect();
+
+    Some(model)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_solve() {
+        assert_eq!(solve(&[&[1, 2], &[-1, 2], &[-2]]), None);
+        assert_eq!(
+            solve(&[&[1, 2], &[-1, 2], &[-2, 3]]).map(|m| m[1..].to_vec()),
+            Some(vec![2, 3])
+        );
+        assert_eq!(solve(&[&[-1], &[1, -2]]), Some(vec![-1, -2]));
+        assert_eq!(solve(&[]), Some(vec![]));
+    }
+}
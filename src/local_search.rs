@@ -0,0 +1,227 @@
+use super::rng::Rng;
+use super::sat::{num_variables, Assignment, Clause, Formula, Not, Var};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Number of flips per variable after which `walksat` restarts from a new
+/// random assignment.
+const RESTART_FLIPS_PER_VARIABLE: u64 = 50;
+
+/// Searches for a model of a formula by WalkSAT local search.
+///
+/// # Algorithm
+///
+/// Starting from a random assignment, each step picks a random unsatisfied
+/// clause and flips one of its variables. The break count of a variable is the
+/// number of clauses that become unsatisfied by flipping it. A variable with
+/// zero break count is flipped if any. Otherwise, with probability `noise` a
+/// random variable of the clause is flipped, and the one with the least break
+/// count is flipped otherwise. The search restarts from a new random
+/// assignment every 50n flips, where n is the number of variables.
+///
+/// # Result
+///
+/// Returns a model if one is found within `max_flips` flips in total, or None
+/// otherwise. None does not mean that the formula is unsatisfiable; local
+/// search never proves it. The random choices are determined by `seed`.
+pub fn walksat(formula: &Formula, max_flips: u64, noise: f64, seed: u64) -> Option<Assignment> {
+    if formula.iter().any(|clause| clause.is_empty()) {
+        return None;
+    }
+
+    let n_vars = num_variables(formula);
+
+    // Duplicate literals and tautologies would confuse the break counts.
+    let clauses: Vec<Clause> = formula
+        .iter()
+        .filter(|clause| !clause.iter().any(|lit| clause.contains(&lit.negate())))
+        .map(|clause| {
+            let mut unique = Clause::new();
+            for &lit in clause {
+                if !unique.contains(&lit) {
+                    unique.push(lit);
+                }
+            }
+            unique
+        })
+        .collect();
+
+    let mut search = Walk::new(clauses, n_vars);
+    let mut rng = Rng::new(seed);
+    let restart_flips = RESTART_FLIPS_PER_VARIABLE * n_vars.max(1) as u64;
+    let mut flips = 0;
+
+    loop {
+        for i in 0..n_vars {
+            search.vars[i] = rng.next_bool();
+        }
+        search.recount();
+
+        for _ in 0..restart_flips {
+            if search.unsat.is_empty() {
+                return Some(search.vars);
+            }
+            if flips == max_flips {
+                return None;
+            }
+            flips += 1;
+
+            let index = search.unsat[(rng.next_u64() % search.unsat.len() as u64) as usize];
+            let clause = &search.clauses[index];
+
+            let (mut best, mut best_breaks) = (clause[0].variable(), usize::MAX);
+            for lit in clause {
+                let breaks = search.break_count(lit.variable());
+                if breaks < best_breaks {
+                    best = lit.variable();
+                    best_breaks = breaks;
+                }
+            }
+
+            // Uniform in [0, 1) with the 53 bits of precision of f64.
+            let coin = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+            let var = if best_breaks > 0 && coin < noise {
+                clause[(rng.next_u64() % clause.len() as u64) as usize].variable()
+            } else {
+                best
+            };
+
+            search.flip(var);
+        }
+    }
+}
+
+/// State of `walksat`: an assignment and the clauses it leaves unsatisfied.
+struct Walk {
+    clauses: Vec<Clause>,
+    vars: Assignment,
+    // Indices of the clauses containing each literal, indexed by code.
+    occurrences: Vec<Vec<usize>>,
+    // Number of true literals in each clause.
+    n_true: Vec<usize>,
+    // Unsatisfied clauses and their positions in the list (usize::MAX if
+    // satisfied), so that a clause is removed in constant time.
+    unsat: Vec<usize>,
+    position: Vec<usize>,
+}
+
+impl Walk {
+    fn new(clauses: Vec<Clause>, n_vars: usize) -> Walk {
+        let mut occurrences = vec![Vec::new(); 2 * n_vars];
+        for (index, clause) in clauses.iter().enumerate() {
+            for lit in clause {
+                occurrences[lit.code()].push(index);
+            }
+        }
+        let n_clauses = clauses.len();
+        Walk {
+            clauses,
+            vars: vec![false; n_vars],
+            occurrences,
+            n_true: vec![0; n_clauses],
+            unsat: Vec::new(),
+            position: vec![usize::MAX; n_clauses],
+        }
+    }
+
+    /// Recomputes the counts and the unsatisfied clauses from scratch.
+    fn recount(&mut self) {
+        self.unsat.clear();
+        for index in 0..self.clauses.len() {
+            let vars = &self.vars;
+            let n_true = self.clauses[index]
+                .iter()
+                .filter(|lit| match **lit {
+                    Var(i) => vars[i],
+                    Not(i) => !vars[i],
+                })
+                .count();
+            self.n_true[index] = n_true;
+            self.position[index] = usize::MAX;
+            if n_true == 0 {
+                self.mark_unsat(index);
+            }
+        }
+    }
+
+    /// Returns the number of clauses that flipping `var` makes unsatisfied.
+    fn break_count(&self, var: usize) -> usize {
+        let lit = if self.vars[var] { Var(var) } else { Not(var) };
+        self.occurrences[lit.code()]
+            .iter()
+            .filter(|&&index| self.n_true[index] == 1)
+            .count()
+    }
+
+    fn flip(&mut self, var: usize) {
+        let was_true = if self.vars[var] { Var(var) } else { Not(var) };
+        self.vars[var] = !self.vars[var];
+
+        for i in 0..self.occurrences[was_true.negate().code()].len() {
+            let index = self.occurrences[was_true.negate().code()][i];
+            self.n_true[index] += 1;
+            if self.n_true[index] == 1 {
+                self.mark_sat(index);
+            }
+        }
+        for i in 0..self.occurrences[was_true.code()].len() {
+            let index = self.occurrences[was_true.code()][i];
+            self.n_true[index] -= 1;
+            if self.n_true[index] == 0 {
+                self.mark_unsat(index);
+            }
+        }
+    }
+
+    fn mark_unsat(&mut self, index: usize) {
+        self.position[index] = self.unsat.len();
+        self.unsat.push(index);
+    }
+
+    fn mark_sat(&mut self, index: usize) {
+        let pos = self.position[index];
+        self.unsat.swap_remove(pos);
+        if let Some(&moved) = self.unsat.get(pos) {
+            self.position[moved] = pos;
+        }
+        self.position[index] = usize::MAX;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dimacs;
+    use crate::sat;
+
+    #[test]
+    fn test_walksat() {
+        let source = include_str!("../examples/qg3-08.cnf");
+        let formula = dimacs::load(&mut source.as_bytes(), true).unwrap();
+        let vars = walksat(&formula, 10_000_000, 0.5, 1).unwrap();
+        assert!(sat::verify(&formula, &vars));
+
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Not(0), Var(1)],
+            vec![Var(0), Not(0)],
+            vec![Not(1), Var(2), Var(2)],
+        ];
+        let vars = walksat(&formula, 1000, 0.5, 2).unwrap();
+        assert!(sat::verify(&formula, &vars));
+
+        assert_eq!(walksat(&vec![], 1000, 0.5, 3), Some(vec![]));
+    }
+
+    #[test]
+    fn test_walksat_gives_up() {
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Var(0), Not(1)],
+            vec![Not(0), Var(1)],
+            vec![Not(0), Not(1)],
+        ];
+        assert_eq!(walksat(&formula, 1000, 0.5, 1), None);
+        assert_eq!(walksat(&vec![vec![Var(0)], vec![]], 1000, 0.5, 1), None);
+    }
+}
@@ -1,57 +1,19 @@
+use dpll_sat::cli;
+use std::env;
 use std::io;
 
-mod dimacs;
-mod sat;
-
 fn main() {
-    let formula = match dimacs::load(&mut io::stdin().lock()) {
-        Ok(formula) => formula,
-        Err(err) => {
-            eprintln!("error: {}", err);
-            std::process::exit(1);
-        }
-    };
-
-    if let Some(vars) = sat::check_sat(&formula) {
-        println!("sat");
-        println!("{}", format_assignment(&vars));
-    } else {
-        println!("unsat");
-        std::process::exit(1);
-    }
-}
-
-fn format_assignment(vars: &sat::Assignment) -> String {
-    let mut message = String::new();
-    for (i, &truth) in vars.iter().enumerate() {
-        if i > 0 {
-            message.push(' ');
-        }
-        if !truth {
-            message.push('-');
-        }
-        message.push_str(&format!("{}", i + 1));
-    }
-    message
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn test_format_assignment_empty() {
-        let vars = sat::Assignment::new();
-        let actual = format_assignment(&vars);
-        let expect = "";
-        assert_eq!(actual, expect);
-    }
+    #[cfg(all(feature = "signal", unix))]
+    let interrupt = cli::install_sigint_handler();
+    #[cfg(not(all(feature = "signal", unix)))]
+    let interrupt = &std::sync::atomic::AtomicBool::new(false);
 
-    #[test]
-    fn test_format_assignment_one_based_signed() {
-        let vars = vec![true, false, true, false];
-        let actual = format_assignment(&vars);
-        let expect = "1 -2 3 -4";
-        assert_eq!(actual, expect);
-    }
+    let code = cli::run_with_interrupt(
+        env::args().skip(1),
+        &mut io::stdin().lock(),
+        &mut io::stdout(),
+        &mut io::stderr(),
+        interrupt,
+    );
+    std::process::exit(code);
 }
@@ -0,0 +1,936 @@
+use super::rng::Rng;
+use super::sat::{self, Assignment, Clause, Formula, Literal, Not, Var};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Simplifies the binary implication chains of a formula.
+///
+/// A binary clause a ∨ b is a pair of implications ¬a → b and ¬b → a. The
+/// binary clauses of a formula thus form an implication graph, and the pass
+/// works on the graph in two steps:
+///
+/// 1. Hyper-binary resolution. If the literals implied by a literal l falsify
+///    all but one literal x of a longer clause, then l implies x and the binary
+///    clause ¬l ∨ x is added. If they falsify a whole clause or contain both a
+///    literal and its negation, l can never be true and the unit clause ¬l is
+///    added. This is done in a single round over all literals. A longer clause
+///    containing both literals of a binary clause is then removed.
+///
+/// 2. Transitive reduction. A binary clause is removed if its implication is
+///    still implied through a path of the remaining binary clauses, e.g., the
+///    shortcut a → c of a chain a → b → c. Duplicate binary clauses go as well.
+///
+/// Every added clause is entailed by the formula and every removed clause is
+/// entailed by the rest, so the result has exactly the same models as the
+/// original formula. Clauses other than the removed ones keep their order and
+/// added clauses are appended.
+pub fn reduce_implications(formula: &Formula) -> Formula {
+    let n_vars = sat::num_variables(formula);
+
+    let mut graph = ImplicationGraph::new(n_vars);
+    for clause in formula {
+        if let Some((a, b)) = as_binary(clause) {
+            graph.insert(a, b);
+        }
+    }
+
+    let mut result = formula.clone();
+    result.extend(resolve_hyper_binary(formula, &mut graph, n_vars));
+
+    let mut removed: Vec<bool> = result.iter().map(|clause| graph.subsumes(clause)).collect();
+
+    // Removing a clause only when the rest still implies it keeps the
+    // reachability between literals, even if the graph has cycles.
+    for (i, clause) in result.iter().enumerate() {
+        if let Some((a, b)) = as_binary(clause) {
+            graph.remove(a, b);
+            if graph.reaches(a.negate(), b) {
+                removed[i] = true;
+            } else {
+                graph.insert(a, b);
+            }
+        }
+    }
+
+    result
+        .into_iter()
+        .zip(removed)
+        .filter(|(_, removed)| !removed)
+        .map(|(clause, _)| clause)
+        .collect()
+}
+
+/// Finds the pairs of interchangeable variables: swapping the two variables
+/// everywhere in the formula gives back the same set of clauses. Returns the
+/// pairs (i, j) with i < j in lexicographic order.
+///
+/// Only such single swaps are detected, not symmetries permuting several
+/// variables at once. Candidates are narrowed down to the variables having the
+/// same lengths and polarities of occurrences, and each candidate swap is then
+/// checked against the whole formula.
+pub fn find_symmetric_pairs(formula: &Formula) -> Vec<(usize, usize)> {
+    let n_vars = sat::num_variables(formula);
+
+    let clauses = canonical_clauses(formula.iter().cloned());
+
+    let mut signatures = vec![Vec::new(); n_vars];
+    for clause in &clauses {
+        for &code in clause {
+            signatures[code / 2].push((clause.len(), code % 2));
+        }
+    }
+    for signature in signatures.iter_mut() {
+        signature.sort_unstable();
+    }
+
+    let mut pairs = Vec::new();
+    for i in 0..n_vars {
+        for j in i + 1..n_vars {
+            if signatures[i].is_empty() || signatures[i] != signatures[j] {
+                continue;
+            }
+
+            let swap = |lit: Literal| match lit {
+                Var(k) | Not(k) if k != i && k != j => lit,
+                Var(k) => Var(i + j - k),
+                Not(k) => Not(i + j - k),
+            };
+            let swapped = formula
+                .iter()
+                .map(|clause| clause.iter().map(|&lit| swap(lit)).collect());
+
+            if canonical_clauses(swapped) == clauses {
+                pairs.push((i, j));
+            }
+        }
+    }
+
+    pairs
+}
+
+/// Adds lex-leader symmetry-breaking clauses for the interchangeable variables
+/// found by `find_symmetric_pairs`.
+///
+/// Swapping i < j maps every model to another model, and of the two, the
+/// lexicographically smaller one satisfies x_i ≤ x_j, i.e., ¬x_i ∨ x_j. The
+/// clause thus keeps the formula satisfiable if it was, but the models are not
+/// preserved. Interchangeable variables form groups in which any two can be
+/// swapped, so the clauses only chain each variable to the next one of its
+/// group. They are appended to a copy of the formula.
+pub fn break_symmetries(formula: &Formula) -> Formula {
+    let mut result = formula.clone();
+    let mut last = None;
+
+    for (i, j) in find_symmetric_pairs(formula) {
+        if last != Some(i) {
+            result.push(vec![Not(i), Var(j)]);
+            last = Some(i);
+        }
+    }
+
+    result
+}
+
+/// Returns the clauses as sorted lists of distinct literal codes, sorted and
+/// deduplicated, so that formulas with the same set of clauses compare equal.
+fn canonical_clauses(clauses: impl Iterator<Item = Clause>) -> Vec<Vec<usize>> {
+    let mut result: Vec<Vec<usize>> = clauses
+        .map(|clause| {
+            let mut codes: Vec<usize> = clause.iter().map(|lit| lit.code()).collect();
+            codes.sort_unstable();
+            codes.dedup();
+            codes
+        })
+        .collect();
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+/// Order of the literals within a clause set by `sort_literals`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LiteralOrder {
+    /// Ascending variable index, with the positive literal first.
+    Variable,
+    /// Descending number of occurrences of the literal in the formula, ties
+    /// broken by variable index. Frequent literals come first.
+    Frequency,
+}
+
+/// Sorts the literals within each clause. Clauses are sets of literals, so
+/// this changes no model. Their order in the formula is kept.
+///
+/// A fixed order makes the printed formulas deterministic, and putting
+/// frequent literals first keeps the literals touched by the search together
+/// at the front of the clauses.
+pub fn sort_literals(formula: &mut Formula, order: LiteralOrder) {
+    match order {
+        LiteralOrder::Variable => {
+            for clause in formula.iter_mut() {
+                clause.sort_unstable_by_key(|lit| lit.code());
+            }
+        }
+        LiteralOrder::Frequency => {
+            let mut counts = Vec::new();
+            for lit in formula.iter().flatten() {
+                if counts.len() <= lit.code() {
+                    counts.resize(lit.code() + 1, 0);
+                }
+                counts[lit.code()] += 1;
+            }
+            for clause in formula.iter_mut() {
+                clause.sort_unstable_by_key(|lit| {
+                    (core::cmp::Reverse(counts[lit.code()]), lit.code())
+                });
+            }
+        }
+    }
+}
+
+/// Clauses removed from a formula by an elimination pass, from which a model
+/// of the original formula is reconstructed.
+///
+/// Each clause is recorded with a witness literal. Flipping the witness to
+/// true satisfies the clause without falsifying any clause remaining at the
+/// time of its removal, so the clauses are fixed in the reverse order of
+/// their removal.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EliminationStack {
+    pub clauses: Vec<(Literal, Clause)>,
+}
+
+impl EliminationStack {
+    /// Extends a model of the reduced formula to a model of the original
+    /// formula. The model is grown to cover the variables of the removed
+    /// clauses, which get false unless flipped.
+    pub fn extend_model(&self, vars: &mut Assignment) {
+        for (witness, clause) in self.clauses.iter().rev() {
+            for lit in clause {
+                if vars.len() <= lit.variable() {
+                    vars.resize(lit.variable() + 1, false);
+                }
+            }
+            if !sat::clause_satisfied(clause, vars) {
+                vars[witness.variable()] = matches!(witness, Var(_));
+            }
+        }
+    }
+}
+
+/// Removes the blocked clauses of a formula and returns them for model
+/// reconstruction.
+///
+/// A clause C is blocked on its literal l if every resolvent of C on l with a
+/// clause containing ¬l is a tautology. Removing a blocked clause keeps the
+/// formula satisfiable if it was, with l as the witness for reconstruction.
+/// Removing a clause may block others, so the pass repeats until no clause is
+/// blocked. The remaining clauses keep their order.
+pub fn blocked_clause_elimination(formula: &mut Formula) -> EliminationStack {
+    let n_codes = 2 * sat::num_variables(formula);
+
+    let mut occurrences = vec![Vec::new(); n_codes];
+    for (index, clause) in formula.iter().enumerate() {
+        for lit in clause {
+            occurrences[lit.code()].push(index);
+        }
+    }
+
+    let mut removed = vec![false; formula.len()];
+    let mut stack = EliminationStack::default();
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for index in 0..formula.len() {
+            if removed[index] {
+                continue;
+            }
+
+            let clause = &formula[index];
+            let blocking = clause.iter().copied().find(|lit| {
+                occurrences[lit.negate().code()]
+                    .iter()
+                    .filter(|&&other| !removed[other])
+                    .all(|&other| sat::resolve(clause, &formula[other], lit.variable()).is_none())
+            });
+
+            if let Some(witness) = blocking {
+                removed[index] = true;
+                stack.clauses.push((witness, clause.clone()));
+                changed = true;
+            }
+        }
+    }
+
+    let mut index = 0;
+    formula.retain(|_| {
+        index += 1;
+        !removed[index - 1]
+    });
+    stack
+}
+
+/// Eliminates variables by resolution and returns the removed clauses for
+/// model reconstruction.
+///
+/// A variable v is eliminated by replacing the clauses containing it with all
+/// the non-tautological resolvents on v of a clause containing v and one
+/// containing ¬v, which keeps the formula satisfiable if it was. This is done
+/// only if there are at most `grow_limit` more resolvents than replaced
+/// clauses, since the resolvents can be quadratically many. The variables are
+/// tried once each, in the ascending order of the number of resolvents.
+///
+/// The remaining clauses keep their order and the resolvents are appended.
+/// The removed clauses have v as the witness for reconstruction, and a
+/// tautology containing both v and ¬v is just dropped.
+pub fn bounded_variable_elimination(formula: &mut Formula, grow_limit: usize) -> EliminationStack {
+    let n_vars = sat::num_variables(formula);
+
+    let mut counts = vec![(0, 0); n_vars];
+    for clause in formula.iter() {
+        for lit in clause {
+            match *lit {
+                Var(i) => counts[i].0 += 1,
+                Not(i) => counts[i].1 += 1,
+            }
+        }
+    }
+    let mut order: Vec<usize> = (0..n_vars).collect();
+    order.sort_by_key(|&var| counts[var].0 * counts[var].1);
+
+    let mut stack = EliminationStack::default();
+
+    for var in order {
+        let (mut pos, mut neg, mut both) = (Vec::new(), Vec::new(), Vec::new());
+        for (index, clause) in formula.iter().enumerate() {
+            match (clause.contains(&Var(var)), clause.contains(&Not(var))) {
+                (true, true) => both.push(index),
+                (true, false) => pos.push(index),
+                (false, true) => neg.push(index),
+                (false, false) => {}
+            }
+        }
+        if pos.is_empty() && neg.is_empty() && both.is_empty() {
+            continue;
+        }
+
+        let limit = pos.len() + neg.len() + both.len() + grow_limit;
+        let mut resolvents = Formula::new();
+        'resolve: for &p in &pos {
+            for &n in &neg {
+                if let Some(resolvent) = sat::resolve(&formula[p], &formula[n], var) {
+                    resolvents.push(resolvent);
+                    if resolvents.len() > limit {
+                        break 'resolve;
+                    }
+                }
+            }
+        }
+        if resolvents.len() > limit {
+            continue;
+        }
+
+        for &index in pos.iter() {
+            stack.clauses.push((Var(var), formula[index].clone()));
+        }
+        for &index in neg.iter() {
+            stack.clauses.push((Not(var), formula[index].clone()));
+        }
+
+        formula.retain(|clause| !clause.iter().any(|lit| lit.variable() == var));
+        formula.extend(resolvents);
+    }
+
+    stack
+}
+
+/// Derives binary and unit clauses by hyper-binary resolution. Derived binary
+/// clauses are added to `graph` as well.
+fn resolve_hyper_binary(formula: &Formula, graph: &mut ImplicationGraph, n_vars: usize) -> Formula {
+    let mut derived = Formula::new();
+
+    for code in 0..2 * n_vars {
+        if graph.edges[code].is_empty() {
+            continue;
+        }
+
+        let lit = literal_of(code);
+        let implied = graph.reach(lit);
+
+        if (0..n_vars).any(|i| implied[2 * i] && implied[2 * i + 1]) {
+            derived.push(vec![lit.negate()]);
+            continue;
+        }
+
+        for clause in formula.iter().filter(|clause| clause.len() > 2) {
+            if clause.iter().any(|x| implied[x.code()]) {
+                continue;
+            }
+
+            let mut open = clause.iter().filter(|x| !implied[x.negate().code()]);
+            match (open.next(), open.next()) {
+                (None, _) => {
+                    derived.push(vec![lit.negate()]);
+                    break;
+                }
+                (Some(&x), None) if !graph.edges[code].contains(&x) => {
+                    graph.insert(lit.negate(), x);
+                    derived.push(vec![lit.negate(), x]);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    derived
+}
+
+/// Returns the two literals of a clause if it is a binary clause of distinct
+/// variables.
+fn as_binary(clause: &Clause) -> Option<(Literal, Literal)> {
+    match clause.as_slice() {
+        &[a, b] if a.variable() != b.variable() => Some((a, b)),
+        _ => None,
+    }
+}
+
+/// Inverse of `Literal::code`.
+fn literal_of(code: usize) -> Literal {
+    if code.is_multiple_of(2) {
+        Literal::Var(code / 2)
+    } else {
+        Literal::Not(code / 2)
+    }
+}
+
+/// Directed graph of the implications between literals given by binary
+/// clauses. Vertices are indexed by literal codes.
+struct ImplicationGraph {
+    edges: Vec<Vec<Literal>>,
+}
+
+impl ImplicationGraph {
+    fn new(n_vars: usize) -> ImplicationGraph {
+        ImplicationGraph {
+            edges: vec![Vec::new(); 2 * n_vars],
+        }
+    }
+
+    /// Adds the implications of the binary clause a ∨ b.
+    fn insert(&mut self, a: Literal, b: Literal) {
+        self.edges[a.negate().code()].push(b);
+        self.edges[b.negate().code()].push(a);
+    }
+
+    /// Removes the implications of one copy of the binary clause a ∨ b.
+    fn remove(&mut self, a: Literal, b: Literal) {
+        for &(from, to) in &[(a.negate(), b), (b.negate(), a)] {
+            let edges = &mut self.edges[from.code()];
+            if let Some(k) = edges.iter().position(|&x| x == to) {
+                edges.swap_remove(k);
+            }
+        }
+    }
+
+    /// Returns the set of literals implied by `lit`, including itself, as a
+    /// vector indexed by literal codes.
+    fn reach(&self, lit: Literal) -> Vec<bool> {
+        let mut visited = vec![false; self.edges.len()];
+        let mut stack = vec![lit];
+        visited[lit.code()] = true;
+
+        while let Some(lit) = stack.pop() {
+            for &next in &self.edges[lit.code()] {
+                if !visited[next.code()] {
+                    visited[next.code()] = true;
+                    stack.push(next);
+                }
+            }
+        }
+
+        visited
+    }
+
+    /// Checks if a clause longer than two literals contains both literals of
+    /// a binary clause in the graph.
+    fn subsumes(&self, clause: &Clause) -> bool {
+        clause.len() > 2
+            && clause.iter().enumerate().any(|(i, a)| {
+                clause[i + 1..]
+                    .iter()
+                    .any(|b| self.edges[a.negate().code()].contains(b))
+            })
+    }
+
+    /// Checks if `to` is implied by `from`.
+    fn reaches(&self, from: Literal, to: Literal) -> bool {
+        self.reach(from)[to.code()]
+    }
+}
+
+/// Renaming of the variables made by `shuffle`, from which a model of the
+/// original formula is restored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Shuffle {
+    /// New variable of each original variable.
+    pub renaming: Vec<usize>,
+}
+
+impl Shuffle {
+    /// Maps a model of the shuffled formula back to the original variables.
+    /// The model covers the variables up to the largest one used in the
+    /// original formula. Variables not covered by `vars` get false.
+    pub fn restore(&self, vars: &Assignment) -> Assignment {
+        self.renaming
+            .iter()
+            .map(|&var| vars.get(var).copied().unwrap_or(false))
+            .collect()
+    }
+}
+
+/// Permutes the clauses of a formula and renames its variables at random,
+/// keeping the literals of each clause in order. The result is determined by
+/// `seed`. This is meant for measuring how sensitive a solver is to the order
+/// of the input, e.g., the variance of a heuristic.
+///
+/// The shuffled formula is satisfiable if and only if the original one is,
+/// and the returned renaming maps its models back to the original.
+pub fn shuffle(formula: &mut Formula, seed: u64) -> Shuffle {
+    let n_vars = sat::num_variables(formula);
+
+    let mut rng = Rng::new(seed);
+    let mut permute = |len: usize| -> Vec<usize> {
+        // Fisher-Yates shuffle.
+        let mut items: Vec<usize> = (0..len).collect();
+        for i in (1..len).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+        items
+    };
+
+    let order = permute(formula.len());
+    let renaming = permute(n_vars);
+
+    let mut clauses: Vec<Option<Clause>> = formula.drain(..).map(Some).collect();
+    for index in order {
+        let mut clause = clauses[index].take().unwrap();
+        for lit in clause.iter_mut() {
+            *lit = match *lit {
+                Var(i) => Var(renaming[i]),
+                Not(i) => Not(renaming[i]),
+            };
+        }
+        formula.push(clause);
+    }
+
+    Shuffle { renaming }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sat;
+    use crate::solver::Solver;
+    use crate::testing::random_3sat;
+
+    /// Checks that two formulas over `n_vars` variables have the same models.
+    fn assert_equivalent(f: &Formula, g: &Formula, n_vars: usize) {
+        for bits in 0..1usize << n_vars {
+            let vars: sat::Assignment = (0..n_vars).map(|i| bits >> i & 1 == 1).collect();
+            assert_eq!(sat::verify(f, &vars), sat::verify(g, &vars));
+        }
+    }
+
+    #[test]
+    fn test_reduce_implications_chain() {
+        // 0 -> 1 -> 2 -> 3 with shortcuts 0 -> 2, 0 -> 3 and 1 -> 3.
+        let formula = vec![
+            vec![Not(0), Var(1)],
+            vec![Not(1), Var(2)],
+            vec![Not(2), Var(3)],
+            vec![Not(0), Var(2)],
+            vec![Not(0), Var(3)],
+            vec![Not(1), Var(3)],
+            vec![Var(0), Var(3), Var(4)],
+        ];
+        let reduced = reduce_implications(&formula);
+        let expect = vec![
+            vec![Not(0), Var(1)],
+            vec![Not(1), Var(2)],
+            vec![Not(2), Var(3)],
+            // Hyper-binary resolution of !3 -> !0 and the ternary clause.
+            vec![Var(3), Var(4)],
+        ];
+        assert_eq!(reduced, expect);
+        assert_equivalent(&formula, &reduced, 5);
+        assert!(sat::equisatisfiable(&formula, &reduced));
+    }
+
+    #[test]
+    fn test_reduce_implications_cycle() {
+        // 0 -> 1 -> 2 -> 0 and a duplicate of the first clause.
+        let formula = vec![
+            vec![Not(0), Var(1)],
+            vec![Not(1), Var(2)],
+            vec![Not(2), Var(0)],
+            vec![Var(1), Not(0)],
+        ];
+        let reduced = reduce_implications(&formula);
+        assert_eq!(reduced.len(), 3);
+        assert_equivalent(&formula, &reduced, 3);
+    }
+
+    #[test]
+    fn test_reduce_implications_hyper_binary() {
+        // 0 -> 1, 0 -> 2 and 1 & 2 -> 3 give 0 -> 3.
+        let formula = vec![
+            vec![Not(0), Var(1)],
+            vec![Not(0), Var(2)],
+            vec![Not(1), Not(2), Var(3)],
+        ];
+        let reduced = reduce_implications(&formula);
+        assert!(reduced.contains(&vec![Not(0), Var(3)]));
+        assert_equivalent(&formula, &reduced, 4);
+
+        // 0 -> 1, 0 -> 2 and !(1 & 2) make 0 false.
+        let formula = vec![
+            vec![Not(0), Var(1)],
+            vec![Not(0), Var(2)],
+            vec![Not(1), Not(2), Not(0)],
+        ];
+        let reduced = reduce_implications(&formula);
+        assert!(reduced.contains(&vec![Not(0)]));
+        assert_equivalent(&formula, &reduced, 3);
+
+        // 0 -> 1 -> !0 makes 0 false as well.
+        let formula = vec![vec![Not(0), Var(1)], vec![Not(1), Not(0)]];
+        let reduced = reduce_implications(&formula);
+        assert!(reduced.contains(&vec![Not(0)]));
+        assert_equivalent(&formula, &reduced, 2);
+    }
+
+    /// Generates the formula asserting that at least k + 1 and at most k of
+    /// the n variables are true, which is unsatisfiable and fully symmetric.
+    fn at_least_and_at_most(n: usize, k: usize) -> Formula {
+        let mut formula = Formula::new();
+        for bits in 0..1usize << n {
+            let subset: Vec<usize> = (0..n).filter(|i| bits >> i & 1 == 1).collect();
+            if subset.len() == k + 1 {
+                formula.push(subset.iter().map(|&i| Not(i)).collect());
+            }
+            if subset.len() == n - k {
+                formula.push(subset.iter().map(|&i| Var(i)).collect());
+            }
+        }
+        formula
+    }
+
+    #[test]
+    fn test_find_symmetric_pairs() {
+        // 0 and 1 are interchangeable. 2 looks like them by its occurrences
+        // but is not.
+        let formula = vec![
+            vec![Var(0), Var(1), Var(2)],
+            vec![Not(0), Var(3)],
+            vec![Not(1), Var(3)],
+            vec![Not(2), Var(4)],
+            vec![Var(3), Var(4)],
+        ];
+        assert_eq!(find_symmetric_pairs(&formula), vec![(0, 1)]);
+
+        // Swapping 0 and 1 needs 2 and 3 to be swapped at the same time, which
+        // is not detected.
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Not(0), Var(2)],
+            vec![Not(1), Var(3)],
+        ];
+        assert_eq!(find_symmetric_pairs(&formula), vec![]);
+
+        // All pairs of a fully symmetric formula.
+        let formula = at_least_and_at_most(4, 1);
+        assert_eq!(
+            find_symmetric_pairs(&formula),
+            vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]
+        );
+    }
+
+    #[test]
+    fn test_break_symmetries() {
+        // Satisfiable formulas stay satisfiable.
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Not(0), Not(1)],
+            vec![Var(2), Var(3), Var(4)],
+        ];
+        let broken = break_symmetries(&formula);
+        assert_eq!(
+            &broken[3..],
+            &[
+                vec![Not(0), Var(1)],
+                vec![Not(2), Var(3)],
+                vec![Not(3), Var(4)]
+            ]
+        );
+        assert!(sat::equisatisfiable(&formula, &broken));
+
+        // The search on an unsatisfiable formula gets shorter.
+        let formula = at_least_and_at_most(7, 3);
+        let broken = break_symmetries(&formula);
+        assert_eq!(broken.len(), formula.len() + 6);
+
+        let decisions = |formula: &Formula| {
+            let mut solver = Solver::new(formula);
+            assert_eq!(solver.solve(), None);
+            solver.stats().decisions
+        };
+        assert!(decisions(&broken) < decisions(&formula));
+    }
+
+    #[test]
+    fn test_sort_literals() {
+        let formula = vec![
+            vec![Not(2), Var(0), Var(1)],
+            vec![Var(2), Not(0)],
+            vec![Not(1), Not(2), Var(1)],
+        ];
+
+        let mut sorted = formula.clone();
+        sort_literals(&mut sorted, LiteralOrder::Variable);
+        assert_eq!(
+            sorted,
+            vec![
+                vec![Var(0), Var(1), Not(2)],
+                vec![Not(0), Var(2)],
+                vec![Var(1), Not(1), Not(2)],
+            ]
+        );
+
+        // Not(2) occurs twice and Var(1) twice; the rest once.
+        let mut sorted = formula.clone();
+        sort_literals(&mut sorted, LiteralOrder::Frequency);
+        assert_eq!(
+            sorted,
+            vec![
+                vec![Var(1), Not(2), Var(0)],
+                vec![Not(0), Var(2)],
+                vec![Var(1), Not(2), Not(1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_literals_verdicts() {
+        let mut rng = crate::rng::Rng::new(11);
+        let mut formulas: Vec<Formula> = (0..20).map(|_| random_3sat(&mut rng, 10, 40)).collect();
+        formulas.push(at_least_and_at_most(5, 2));
+
+        for formula in formulas.iter() {
+            let expect = sat::check_sat(formula).is_some();
+
+            for &order in &[LiteralOrder::Variable, LiteralOrder::Frequency] {
+                let mut sorted = formula.clone();
+                sort_literals(&mut sorted, order);
+
+                let vars = sat::check_sat(&sorted);
+                assert_eq!(vars.is_some(), expect);
+                if let Some(vars) = vars {
+                    assert!(sat::verify(formula, &vars));
+                }
+
+                let vars = Solver::new(&sorted).solve();
+                assert_eq!(vars.is_some(), expect);
+                if let Some(vars) = vars {
+                    assert!(sat::verify(formula, &vars));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_blocked_clause_elimination() {
+        // The four clauses over 0 and 1 are not blocked. Clause 5 is blocked
+        // on 3, which then blocks clause 4 on 2.
+        let core = vec![
+            vec![Var(0), Var(1)],
+            vec![Not(0), Var(1)],
+            vec![Var(0), Not(1)],
+            vec![Not(0), Not(1)],
+        ];
+        let mut formula = core.clone();
+        formula.push(vec![Var(0), Var(2)]);
+        formula.push(vec![Not(2), Var(3)]);
+
+        let stack = blocked_clause_elimination(&mut formula);
+        assert_eq!(formula, core);
+        assert_eq!(
+            stack.clauses,
+            vec![
+                (Var(3), vec![Not(2), Var(3)]),
+                (Var(2), vec![Var(0), Var(2)])
+            ]
+        );
+
+        // Everything is eliminated, and the all-false model of the empty
+        // formula needs a fix.
+        let original = vec![vec![Var(0), Var(1)], vec![Not(0), Not(1)]];
+        let mut formula = original.clone();
+        let stack = blocked_clause_elimination(&mut formula);
+        assert!(formula.is_empty());
+
+        let mut vars = Assignment::new();
+        stack.extend_model(&mut vars);
+        assert_eq!(vars, vec![true, false]);
+        assert!(sat::verify(&original, &vars));
+    }
+
+    #[test]
+    fn test_blocked_clause_elimination_models() {
+        let mut rng = crate::rng::Rng::new(7);
+        for _ in 0..30 {
+            let original: Formula = random_3sat(&mut rng, 8, 25);
+
+            let mut formula = original.clone();
+            let stack = blocked_clause_elimination(&mut formula);
+            assert_eq!(formula.len() + stack.clauses.len(), original.len());
+
+            match (sat::check_sat(&original), sat::check_sat(&formula)) {
+                (Some(_), Some(mut vars)) => {
+                    stack.extend_model(&mut vars);
+                    assert!(sat::verify(&original, &vars));
+                }
+                (None, None) => {}
+                _ => panic!("verdict changed"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_bounded_variable_elimination() {
+        // 0 occurs in three clauses and gives two resolvents. It is tried
+        // first as it has the fewest pairs to resolve.
+        let original = vec![
+            vec![Var(0), Var(1)],
+            vec![Not(0), Var(2)],
+            vec![Not(0), Var(3)],
+            vec![Not(1), Not(2), Var(4)],
+            vec![Not(1), Not(3), Not(4)],
+            vec![Var(1), Var(2), Var(3)],
+            vec![Var(2), Not(3), Not(4)],
+            vec![Not(2), Var(3), Var(4)],
+        ];
+        let mut formula = original.clone();
+        let stack = bounded_variable_elimination(&mut formula, 0);
+
+        assert!(formula.len() < original.len());
+        assert!(!formula.iter().flatten().any(|lit| lit.variable() == 0));
+        assert_eq!(
+            &stack.clauses[..3],
+            &[
+                (Var(0), vec![Var(0), Var(1)]),
+                (Not(0), vec![Not(0), Var(2)]),
+                (Not(0), vec![Not(0), Var(3)])
+            ]
+        );
+
+        let mut vars = sat::check_sat(&formula).unwrap();
+        stack.extend_model(&mut vars);
+        assert!(sat::verify(&original, &vars));
+
+        // Eliminating every variable leaves the empty clause if unsatisfiable.
+        let mut formula = vec![vec![Var(0)], vec![Not(0), Var(1)], vec![Not(1)]];
+        bounded_variable_elimination(&mut formula, 0);
+        assert_eq!(formula, vec![vec![]]);
+    }
+
+    #[test]
+    fn test_bounded_variable_elimination_models() {
+        let mut rng = crate::rng::Rng::new(9);
+        for round in 0..30 {
+            let original: Formula = random_3sat(&mut rng, 8, 30);
+
+            let mut formula = original.clone();
+            let stack = bounded_variable_elimination(&mut formula, round % 3);
+
+            match (sat::check_sat(&original), sat::check_sat(&formula)) {
+                (Some(_), Some(mut vars)) => {
+                    stack.extend_model(&mut vars);
+                    assert!(sat::verify(&original, &vars));
+                }
+                (None, None) => {}
+                _ => panic!("verdict changed"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let original = vec![
+            vec![Var(0), Not(1)],
+            vec![Var(1), Var(2), Var(3)],
+            vec![Not(0), Not(3)],
+            vec![Var(3)],
+        ];
+
+        let mut formula = original.clone();
+        let shuffle = shuffle(&mut formula, 1);
+        let mut renaming = shuffle.renaming.clone();
+        renaming.sort_unstable();
+        assert_eq!(renaming, vec![0, 1, 2, 3]);
+
+        // The same clauses under the renaming, in some order.
+        assert_eq!(formula.len(), original.len());
+        for clause in &original {
+            let renamed: Clause = clause
+                .iter()
+                .map(|lit| match *lit {
+                    Var(i) => Var(shuffle.renaming[i]),
+                    Not(i) => Not(shuffle.renaming[i]),
+                })
+                .collect();
+            assert!(formula.contains(&renamed));
+        }
+
+        // Deterministic by the seed.
+        let mut again = original.clone();
+        assert_eq!(super::shuffle(&mut again, 1), shuffle);
+        assert_eq!(again, formula);
+    }
+
+    #[test]
+    fn test_shuffle_verdicts() {
+        let mut rng = crate::rng::Rng::new(6);
+        let mut verdicts = [0, 0];
+        for round in 0..50 {
+            let original: Formula = random_3sat(&mut rng, 10, 45);
+
+            let mut formula = original.clone();
+            let shuffle = shuffle(&mut formula, round);
+
+            match (sat::check_sat(&original), sat::check_sat(&formula)) {
+                (Some(_), Some(vars)) => {
+                    let vars = shuffle.restore(&vars);
+                    assert!(sat::verify(&original, &vars));
+                    verdicts[1] += 1;
+                }
+                (None, None) => verdicts[0] += 1,
+                _ => panic!("verdict changed"),
+            }
+        }
+        assert!(verdicts[0] > 0 && verdicts[1] > 0);
+    }
+
+    #[test]
+    fn test_reduce_implications_unsat() {
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Var(0), Not(1)],
+            vec![Not(0), Var(1)],
+            vec![Not(0), Not(1)],
+        ];
+        let reduced = reduce_implications(&formula);
+        assert!(sat::check_sat(&reduced).is_none());
+    }
+}
@@ -1,3 +1,15 @@
+use super::heuristic::{DominantVariable, Heuristic};
+use super::solver::Outcome;
+use alloc::borrow::Cow;
+use alloc::collections::{BinaryHeap, VecDeque};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
 /// Variable assignment for a SAT problem. The i-th element designates the truth
 /// value of the i-th variable.
 pub type Assignment = Vec<bool>;
@@ -19,256 +31,2728 @@ pub enum Literal {
 pub use Literal::Not;
 pub use Literal::Var;
 
-/// Solves a satisfiability problem given as a CNF formula.
-///
-/// Returns a variable assignment if the formula is satisfiable, or None if the
-/// formula is unsatisfiable.
-pub fn check_sat(formula: &Formula) -> Option<Assignment> {
-    let mut n_vars = 0;
+impl Literal {
+    /// Returns the index of the variable of the literal.
+    pub fn variable(self) -> usize {
+        match self {
+            Var(i) => i,
+            Not(i) => i,
+        }
+    }
 
-    for clause in formula {
-        for lit in clause {
-            let i = match lit {
-                &Var(i) => i,
-                &Not(i) => i,
-            };
-            if i >= n_vars {
-                n_vars = i + 1;
-            }
+    /// Returns the literal of the opposite polarity.
+    pub fn negate(self) -> Literal {
+        match self {
+            Var(i) => Not(i),
+            Not(i) => Var(i),
         }
     }
 
-    let mut vars = vec![false; n_vars];
+    /// Returns a dense code of the literal usable as an array index: 2i for
+    /// `Var(i)` and 2i + 1 for `Not(i)`.
+    pub fn code(self) -> usize {
+        match self {
+            Var(i) => 2 * i,
+            Not(i) => 2 * i + 1,
+        }
+    }
 
-    if dpll(&formula, &mut vars) {
-        Some(vars)
-    } else {
-        None
+    /// Converts a one-based signed index used in DIMACS to a literal. A
+    /// positive number `i` means the i-th variable and a negative number `-i`
+    /// means its negation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is zero.
+    pub fn from_dimacs(value: i32) -> Literal {
+        assert!(value != 0, "zero is not a literal");
+
+        let i = value.unsigned_abs() as usize - 1;
+        if value > 0 {
+            Var(i)
+        } else {
+            Not(i)
+        }
+    }
+
+    /// Converts the literal to a one-based signed index used in DIMACS.
+    pub fn to_dimacs(self) -> i32 {
+        match self {
+            Var(i) => (i + 1) as i32,
+            Not(i) => -((i + 1) as i32),
+        }
     }
 }
 
-fn dpll(formula: &Formula, mut vars: &mut Assignment) -> bool {
-    let mut formula = formula.to_vec();
+/// Formats the literal as the one-based signed index used in DIMACS. Width and
+/// alignment flags apply as for integers.
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_dimacs(), f)
+    }
+}
+
+/// Wrapper formatting a clause as a DIMACS line without the newline: signed
+/// indices separated by spaces and terminated by `0`. See `display_clause`.
+#[derive(Clone, Copy, Debug)]
+pub struct ClauseDisplay<'a>(pub &'a [Literal]);
+
+impl fmt::Display for ClauseDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for lit in self.0 {
+            write!(f, "{} ", lit)?;
+        }
+        write!(f, "0")
+    }
+}
+
+/// Returns a wrapper that displays a clause in DIMACS form, e.g., `1 -2 0`.
+pub fn display_clause(clause: &[Literal]) -> ClauseDisplay<'_> {
+    ClauseDisplay(clause)
+}
 
-    unit_propagate(&mut formula, &mut vars);
+/// CNF formula stored in two flat arrays: the literals of all the clauses in
+/// order, and the range of each clause in the literals.
+///
+/// A `Formula` allocates every clause separately, so going through the
+/// clauses jumps around the heap. The flat layout keeps them contiguous,
+/// which suits formulas that are scanned often but never modified, like the
+/// clauses of `LazyClauses`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FlatFormula {
+    literals: Vec<Literal>,
+    ranges: Vec<Range<usize>>,
+}
+
+impl FlatFormula {
+    /// Returns the number of clauses.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
 
-    if formula.is_empty() {
-        return true;
+    /// Checks if the formula has no clause.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
     }
 
-    if formula.iter().any(|clause| clause.is_empty()) {
-        return false;
+    /// Returns the literals of the clause at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn clause(&self, index: usize) -> &[Literal] {
+        &self.literals[self.ranges[index].clone()]
     }
 
-    // Simple splitting rule: Assign a truth value to the most used variable
-    // in the formula.
-    let var = find_dominant_variable(&formula, vars.len());
+    /// Returns an iterator over the clauses in order.
+    pub fn iter(&self) -> impl Iterator<Item = &[Literal]> + '_ {
+        self.ranges
+            .iter()
+            .map(move |range| &self.literals[range.clone()])
+    }
 
-    formula.push(vec![Var(var)]);
-    if dpll(&formula, &mut vars) {
-        return true;
+    /// Returns the literals of all the clauses in order.
+    pub fn literals(&self) -> &[Literal] {
+        &self.literals
     }
 
-    formula.pop();
-    formula.push(vec![Not(var)]);
-    dpll(&formula, &mut vars)
+    /// Converts back to a formula of separate clauses.
+    pub fn to_formula(&self) -> Formula {
+        self.iter().map(|clause| clause.to_vec()).collect()
+    }
 }
 
-/// Resolves unit clauses in a CNF formula.
-///
-/// # Unit propagation
-///
-/// A unit clause is a clause consisting of a single literal:
-///
-/// > formula = ... ∧ x ∧ ...
-///
-/// Such a clause in a CNF formula induces an assignment `x = true` so that the
-/// formula must become true. Unit propagation finds out such assignments and
-/// simplifies the formula until all unit clauses are consumed.
-///
-fn unit_propagate(mut formula: &mut Formula, vars: &mut Assignment) {
-    while let Some(clause) = formula.iter().find(|clause| clause.len() == 1) {
-        let (var, truth) = match clause[0] {
-            Var(i) => (i, true),
-            Not(i) => (i, false),
-        };
-        vars[var] = truth;
-        simplify(&mut formula, var, truth);
+impl From<&Formula> for FlatFormula {
+    fn from(formula: &Formula) -> FlatFormula {
+        let mut literals = Vec::with_capacity(formula.iter().map(Vec::len).sum());
+        let mut ranges = Vec::with_capacity(formula.len());
+        for clause in formula {
+            let start = literals.len();
+            literals.extend_from_slice(clause);
+            ranges.push(start..literals.len());
+        }
+        FlatFormula { literals, ranges }
     }
 }
 
-/// Simplifies a CNF formula using given variable assignment.
-///
-/// # Simplification
-///
-/// A variable assignment resolves literals `Var(var)` and `Not(var)` in the
-/// formula to true or false. Note that a false literal (say x) does not
-/// contribute to the condition of a clause:
-///
-/// > C ∨ x = C  if x = false
-///
-/// so, a simplification will remove all false literals from the formula. On the
-/// other hand, a true literal (say y) makes the clause containing y true:
+/// Solves a satisfiability problem given as a CNF formula.
 ///
-/// > C ∨ y = true  if y = true
+/// Returns a variable assignment if the formula is satisfiable, or None if the
+/// formula is unsatisfiable.
+pub fn check_sat(formula: &Formula) -> Option<Assignment> {
+    check_sat_with(formula, &mut DominantVariable)
+}
+
+/// Solves a satisfiability problem like `check_sat`, returning the model as a
+/// map from each variable appearing in the formula to its value. Variables
+/// without a literal in the formula are left out, which keeps the model of a
+/// formula of few but large indices small.
+#[cfg(feature = "std")]
+pub fn check_sat_map(formula: &Formula) -> Option<HashMap<usize, bool>> {
+    let vars = check_sat(formula)?;
+    let model = formula
+        .iter()
+        .flatten()
+        .map(|lit| (lit.variable(), vars[lit.variable()]))
+        .collect();
+    Some(model)
+}
+
+/// Solves a satisfiability problem like `check_sat`, choosing the branching
+/// literals of the DPLL search with `heuristic`. Horn and 2-SAT formulas are
+/// solved without branching, so the heuristic is not used for them.
+pub fn check_sat_with(formula: &Formula, heuristic: &mut dyn Heuristic) -> Option<Assignment> {
+    check_sat_in(formula, &mut Search::new(heuristic))
+}
+
+/// How the DPLL search of `check_sat_with_mode` simplifies the formula at a
+/// node of the search tree. See `ClauseStore` for the two storages.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SimplifyMode {
+    /// Copies the formula and removes the satisfied clauses and the false
+    /// literals from the copy, which reorders the clauses. This is the mode of
+    /// `check_sat` and the default.
+    #[default]
+    Eager,
+    /// Keeps the clauses of the formula at their indices and tags them as
+    /// satisfied or falsified under the assignment of the node. The remaining
+    /// clauses are collected for the heuristic by scanning the formula.
+    Lazy,
+}
+
+/// Solves a satisfiability problem like `check_sat_with`, simplifying the
+/// formula during the search as chosen by `mode`. Both modes propagate the
+/// same units and give the same verdict, but the models may differ as the
+/// heuristic sees the clauses in different orders.
+pub fn check_sat_with_mode(
+    formula: &Formula,
+    heuristic: &mut dyn Heuristic,
+    mode: SimplifyMode,
+) -> Option<Assignment> {
+    let mut search = Search::new(heuristic);
+    search.mode = mode;
+    check_sat_in(formula, &mut search)
+}
+
+/// Solves a satisfiability problem like `check_sat_with_mode` in the lazy
+/// mode, propagating the units of the clauses of higher priority first. The
+/// i-th priority is that of the i-th clause, and clauses of equal priority
+/// propagate in the order of the lazy mode. The order of propagation decides
+/// which conflict is found first but not the verdict. Horn and 2-SAT formulas
+/// are solved without unit propagation in the search, so the priorities are
+/// not used for them.
 ///
-/// i.e., the clause becomes true. So, a simplification will remove all clauses
-/// containing one or more true literals.
+/// # Panics
 ///
-/// # Empty clause
+/// Panics if the number of priorities differs from the number of clauses.
+pub fn check_sat_with_priorities(
+    formula: &Formula,
+    heuristic: &mut dyn Heuristic,
+    priorities: &[u32],
+) -> Option<Assignment> {
+    assert_eq!(priorities.len(), formula.len(), "priority count differs");
+    let mut search = Search::new(heuristic);
+    search.mode = SimplifyMode::Lazy;
+    search.priorities = Some(priorities);
+    check_sat_in(formula, &mut search)
+}
+
+/// Solves a satisfiability problem like `check_sat_with`, giving up when
+/// `interrupt` becomes true. The flag is checked at every decision of the
+/// DPLL search, and an interrupted search returns `Outcome::Unknown`. Horn and
+/// 2-SAT formulas are solved without decisions, so they are never interrupted.
+pub fn check_sat_until(
+    formula: &Formula,
+    heuristic: &mut dyn Heuristic,
+    interrupt: &AtomicBool,
+) -> Outcome {
+    check_sat_until_with_stats(formula, heuristic, interrupt).0
+}
+
+/// Progress of the DPLL search of `check_sat_until_with_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SearchStats {
+    /// Number of decisions made.
+    pub decisions: u64,
+    /// Number of literals assigned by unit propagation, not counting the
+    /// decisions.
+    pub propagations: u64,
+    /// Number of branches closed by a falsified clause.
+    pub conflicts: u64,
+    /// Largest number of decisions on a branch of the search tree.
+    pub max_depth: usize,
+}
+
+/// Solves a satisfiability problem like `check_sat_until`, also returning the
+/// progress of the search. An interrupted search reports how far it got.
+pub fn check_sat_until_with_stats(
+    formula: &Formula,
+    heuristic: &mut dyn Heuristic,
+    interrupt: &AtomicBool,
+) -> (Outcome, SearchStats) {
+    let mut search = Search::new(heuristic);
+    search.interrupt = Some(interrupt);
+
+    let outcome = match check_sat_in(formula, &mut search) {
+        Some(vars) => Outcome::Sat(vars),
+        None if search.interrupted => Outcome::Unknown,
+        None => Outcome::Unsat,
+    };
+    (outcome, search.stats)
+}
+
+/// Maximum number of variables accepted by `check_sat_dp`.
+pub const DP_MAX_VARIABLES: usize = 64;
+
+/// Decides the satisfiability of a formula by the original Davis-Putnam
+/// procedure, which eliminates variables by resolution instead of branching.
 ///
-/// The simplification may leave empty clause(s) in the formula. An empty clause
-/// must have originated from a unit clause consisting of a false literal x:
+/// Each step picks the variable with the fewest pairs of clauses to resolve
+/// and replaces the clauses containing it with all their non-tautological
+/// resolvents on it. The formula is unsatisfiable if the empty clause is
+/// derived and satisfiable if no clause is left. No model is derived.
 ///
-/// > formula = ... ∧ x ∧ ... ,  x = false .
+/// The number of clauses can grow exponentially with the variables, so this is
+/// only practical for small formulas.
 ///
-/// Therefore, the formula must be unsatisfiable in that case.
+/// # Panics
 ///
-fn simplify(formula: &mut Formula, var: usize, truth: bool) {
-    let truthy_lit = if truth { Var(var) } else { Not(var) };
-    let falsey_lit = if truth { Not(var) } else { Var(var) };
-
-    // This function is the hottest part of the solver. So, we loop over
-    // clauses and literals manually and remove determined ones in-place.
-    let mut clause_index: usize = 0;
-    while clause_index < formula.len() {
-        let clause = &mut formula[clause_index];
+/// Panics if the formula uses more than `DP_MAX_VARIABLES` variables.
+pub fn check_sat_dp(formula: &Formula) -> bool {
+    // The used variables are renumbered in order, so that the counts below
+    // take as many entries as the variables and not the largest index.
+    let mut used: Vec<usize> = formula.iter().flatten().map(|lit| lit.variable()).collect();
+    used.sort_unstable();
+    used.dedup();
+    assert!(
+        used.len() <= DP_MAX_VARIABLES,
+        "too many variables for check_sat_dp"
+    );
+    let n_vars = used.len();
+    let renumber = |lit: Literal| {
+        let var = used.binary_search(&lit.variable()).unwrap();
+        match lit {
+            Var(_) => Var(var),
+            Not(_) => Not(var),
+        }
+    };
 
-        // Remove unconditionally true clause.
-        if clause.contains(&truthy_lit) {
-            let n = formula.len();
-            formula.swap(clause_index, n - 1);
-            formula.pop();
+    // Drop tautologies and duplicate literals as resolvents have none.
+    let mut clauses = Formula::new();
+    for clause in formula {
+        if clause.iter().any(|lit| clause.contains(&lit.negate())) {
             continue;
         }
+        let mut literals = Clause::new();
+        for &lit in clause {
+            let lit = renumber(lit);
+            if !literals.contains(&lit) {
+                literals.push(lit);
+            }
+        }
+        clauses.push(literals);
+    }
 
-        // Remove falsified literal.
-        let mut literal_index = 0;
-        while literal_index < clause.len() {
-            if clause[literal_index] == falsey_lit {
-                let n = clause.len();
-                clause.swap(literal_index, n - 1);
-                clause.pop();
-                continue;
+    loop {
+        if clauses.iter().any(|clause| clause.is_empty()) {
+            return false;
+        }
+
+        let mut counts = vec![(0, 0); n_vars];
+        for lit in clauses.iter().flatten() {
+            match *lit {
+                Var(i) => counts[i].0 += 1,
+                Not(i) => counts[i].1 += 1,
+            }
+        }
+
+        let pivot = match (0..n_vars)
+            .filter(|&i| counts[i] != (0, 0))
+            .min_by_key(|&i| counts[i].0 * counts[i].1)
+        {
+            Some(pivot) => pivot,
+            None => return true,
+        };
+
+        let (with, rest): (Formula, Formula) = clauses
+            .into_iter()
+            .partition(|clause| clause.iter().any(|lit| lit.variable() == pivot));
+        clauses = rest;
+
+        for (i, a) in with.iter().enumerate() {
+            for b in &with[i + 1..] {
+                if let Some(resolvent) = resolve(a, b, pivot) {
+                    let duplicate = clauses.iter().any(|clause| {
+                        clause.len() == resolvent.len()
+                            && clause.iter().all(|lit| resolvent.contains(lit))
+                    });
+                    if !duplicate {
+                        clauses.push(resolvent);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn check_sat_in(formula: &Formula, search: &mut Search) -> Option<Assignment> {
+    let n_vars = num_variables(formula);
+    let mut vars = vec![false; n_vars];
+
+    let sat = if is_trivially_sat(formula) {
+        true
+    } else if is_horn(formula) {
+        solve_horn(formula, &mut vars)
+    } else if is_2sat(formula) {
+        solve_2sat(formula, &mut vars)
+    } else {
+        match search.mode {
+            SimplifyMode::Eager => {
+                search.counts = Some(LiteralCounts::new(formula, n_vars));
+                search.origins = Some((0..formula.len()).collect());
+                dpll(formula, &mut vars, search)
+            }
+            SimplifyMode::Lazy => {
+                let flat = FlatFormula::from(formula);
+                dpll_lazy(&flat, &vec![None; n_vars], &mut vars, search)
             }
-            literal_index += 1;
         }
+    };
+
+    if sat {
+        debug_assert_eq!(vars.len(), n_vars, "model does not cover the variables");
+        Some(vars)
+    } else {
+        None
+    }
+}
+
+/// Returns the number of variables of a formula, i.e., one plus the largest
+/// variable index in its literals. This is zero for a formula without a
+/// literal, and the length of the models given by `check_sat`.
+pub fn num_variables(formula: &Formula) -> usize {
+    formula
+        .iter()
+        .flatten()
+        .map(|lit| lit.variable() + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Checks if a CNF formula is trivially satisfiable, i.e., every clause is a
+/// tautology containing a literal and its negation. This includes the empty
+/// formula. Any assignment satisfies such a formula, so `check_sat` returns the
+/// all-false model for it without search.
+pub fn is_trivially_sat(formula: &Formula) -> bool {
+    formula
+        .iter()
+        .all(|clause| clause.iter().any(|lit| clause.contains(&lit.negate())))
+}
 
-        clause_index += 1;
+/// Checks if a CNF formula is a Horn formula, i.e., every clause contains at
+/// most one positive literal.
+pub fn is_horn(formula: &Formula) -> bool {
+    formula
+        .iter()
+        .all(|clause| clause.iter().filter(|lit| matches!(lit, Var(_))).count() <= 1)
+}
+
+/// Checks if a CNF formula is a 2-SAT formula, i.e., every clause contains at
+/// most two literals.
+pub fn is_2sat(formula: &Formula) -> bool {
+    formula.iter().all(|clause| clause.len() <= 2)
+}
+
+/// Checks if a variable assignment satisfies a clause, i.e., makes one of its
+/// literals true. An empty clause is never satisfied, and a literal of a
+/// variable beyond the assignment is not true.
+pub fn clause_satisfied(clause: &Clause, vars: &Assignment) -> bool {
+    clause.iter().any(|&lit| {
+        vars.get(lit.variable())
+            .is_some_and(|&truth| truth == matches!(lit, Var(_)))
+    })
+}
+
+/// Checks if a variable assignment satisfies a CNF formula.
+///
+/// Returns false if the assignment is too short to cover the variables used
+/// in the formula.
+pub fn verify(formula: &Formula, vars: &Assignment) -> bool {
+    formula.iter().all(|clause| clause_satisfied(clause, vars))
+}
+
+/// Checks if two formulas are equisatisfiable, i.e., both are satisfiable or
+/// both are unsatisfiable. Only the verdicts are compared, not the models.
+///
+/// A simplification that keeps the verdict but not necessarily the models,
+/// such as eliminating a variable, must preserve equisatisfiability.
+pub fn equisatisfiable(a: &Formula, b: &Formula) -> bool {
+    check_sat(a).is_some() == check_sat(b).is_some()
+}
+
+/// Checks if a formula entails a clause, i.e., every model of the formula
+/// satisfies the clause.
+///
+/// The query is negated into unit clauses, one for the negation of each
+/// literal, and the formula is checked to be unsatisfiable together with
+/// them. So, an unsatisfiable formula entails anything, and a tautology is
+/// entailed by any formula. A valid clause, one entailed by the empty formula,
+/// is exactly a tautology.
+pub fn entails(formula: &Formula, query: &Clause) -> bool {
+    let mut refutation = formula.to_vec();
+    for lit in query {
+        refutation.push(vec![lit.negate()]);
     }
+    check_sat(&refutation).is_none()
 }
 
-/// Finds the most used variable in a formula.
-fn find_dominant_variable(formula: &Formula, n_vars: usize) -> usize {
-    let mut freqs = vec![0; n_vars];
+/// Splits a formula into connected components, i.e., groups of clauses such
+/// that no two groups share a variable. The clauses keep their variables and
+/// their relative order. Components are ordered by their first clause, and an
+/// empty clause forms a component by itself.
+pub fn connected_components(formula: &Formula) -> Vec<Formula> {
+    let n_vars = num_variables(formula);
 
+    // Union-find over the variables, joining the variables of each clause.
+    let mut parents: Vec<usize> = (0..n_vars).collect();
     for clause in formula {
-        for lit in clause {
-            let i = match lit {
-                &Var(i) => i,
-                &Not(i) => i,
-            };
-            freqs[i] += 1;
+        for pair in clause.windows(2) {
+            let a = find_root(&mut parents, pair[0].variable());
+            let b = find_root(&mut parents, pair[1].variable());
+            parents[a] = b;
         }
     }
 
-    let mut max: i32 = 0;
-    let mut argmax: usize = 0;
+    let mut index_of_root = vec![usize::MAX; n_vars];
+    let mut components: Vec<Formula> = Vec::new();
+    for clause in formula {
+        let index = match clause.first() {
+            Some(lit) => {
+                let root = find_root(&mut parents, lit.variable());
+                if index_of_root[root] == usize::MAX {
+                    index_of_root[root] = components.len();
+                    components.push(Formula::new());
+                }
+                index_of_root[root]
+            }
+            None => {
+                components.push(Formula::new());
+                components.len() - 1
+            }
+        };
+        components[index].push(clause.clone());
+    }
+    components
+}
+
+/// Returns the root of the tree of `var` in a union-find forest, halving the
+/// path on the way.
+fn find_root(parents: &mut [usize], mut var: usize) -> usize {
+    while parents[var] != var {
+        parents[var] = parents[parents[var]];
+        var = parents[var];
+    }
+    var
+}
+
+/// Solves a satisfiability problem like `check_sat`, solving each connected
+/// component of the formula separately. The formula is unsatisfiable if any
+/// component is, and otherwise the models of the components are merged.
+///
+/// The search on a formula of independent parts would otherwise interleave
+/// their decisions, so a conflict in one part backtracks over the others.
+pub fn solve_by_components(formula: &Formula) -> Option<Assignment> {
+    let n_vars = num_variables(formula);
+    let mut vars = vec![false; n_vars];
 
-    for (i, &freq) in freqs.iter().enumerate() {
-        if freq > max {
-            max = freq;
-            argmax = i;
+    for component in connected_components(formula) {
+        let model = check_sat(&component)?;
+        for lit in component.iter().flatten() {
+            vars[lit.variable()] = model[lit.variable()];
         }
     }
+    Some(vars)
+}
 
-    return argmax;
+/// Asserts that `vars` is a model of `formula` as returned by `check_sat`,
+/// i.e., it satisfies the formula and has exactly one value for each variable
+/// up to the largest one used in the formula. Variables declared in a DIMACS
+/// header but not used in any clause are not covered.
+///
+/// # Panics
+///
+/// Panics if the assignment has a wrong length or does not satisfy the
+/// formula.
+#[track_caller]
+pub fn assert_valid_model(formula: &Formula, vars: &Assignment) {
+    let n_vars = num_variables(formula);
+    assert_eq!(
+        vars.len(),
+        n_vars,
+        "model length differs from variable count"
+    );
+    assert!(verify(formula, vars), "model does not satisfy the formula");
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+/// Removes the clauses satisfied by a partial assignment, in which None means
+/// an unassigned variable. The other clauses keep their order.
+///
+/// The removed clauses impose no constraint on the remaining variables, so the
+/// formula stays satisfiable under the assignment if and only if it was. The
+/// search in `check_sat` does not need this as it drops satisfied clauses as
+/// it assigns variables.
+pub fn remove_satisfied(formula: &mut Formula, vars: &[Option<bool>]) {
+    formula.retain(|clause| {
+        !clause.iter().any(|lit| match *lit {
+            Var(i) => vars.get(i) == Some(&Some(true)),
+            Not(i) => vars.get(i) == Some(&Some(false)),
+        })
+    });
+}
 
-    #[test]
-    fn test_check_sat() {
-        // Empty formula
-        {
-            let formula = vec![];
-            let sat = check_sat(&formula);
+/// Returns the indices of the clauses that are neither satisfied nor falsified
+/// by a partial assignment, i.e., those having an unassigned literal but no
+/// true one. None, as well as a variable beyond the assignment, means
+/// unassigned.
+pub fn unsatisfied_clauses(formula: &Formula, partial: &[Option<bool>]) -> Vec<usize> {
+    let value = |lit: &Literal| match *lit {
+        Var(i) => partial.get(i).copied().flatten(),
+        Not(i) => partial.get(i).copied().flatten().map(|truth| !truth),
+    };
 
-            assert!(sat == Some(vec![]));
+    formula
+        .iter()
+        .enumerate()
+        .filter(|(_, clause)| {
+            !clause.iter().any(|lit| value(lit) == Some(true))
+                && clause.iter().any(|lit| value(lit).is_none())
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Reduces a model to a partial assignment whose unassigned variables are
+/// don't-cares: every clause stays satisfied by the assigned variables alone,
+/// so any values of the don't-cares give a model.
+///
+/// Variables are tried one at a time and unassigned if no clause depends on
+/// them any more. The result is minimal in that no assigned variable can be
+/// unassigned, but which minimal assignment is found depends on the order.
+/// The variables in `priority` are tried first, in that order, so they are
+/// preferably left unassigned, and the others follow in ascending order.
+///
+/// # Panics
+///
+/// Panics if `vars` is not a model of the formula.
+pub fn dont_cares(formula: &Formula, vars: &Assignment, priority: &[usize]) -> Vec<Option<bool>> {
+    let is_true = |lit: &Literal| match *lit {
+        Var(i) => vars[i],
+        Not(i) => !vars[i],
+    };
+
+    let mut n_true: Vec<usize> = formula
+        .iter()
+        .map(|clause| clause.iter().filter(|lit| is_true(lit)).count())
+        .collect();
+    assert!(n_true.iter().all(|&n| n > 0), "not a model");
+
+    // Clauses in which each variable has a true literal.
+    let mut supported = vec![Vec::new(); vars.len()];
+    for (index, clause) in formula.iter().enumerate() {
+        for lit in clause.iter().filter(|lit| is_true(lit)) {
+            supported[lit.variable()].push(index);
         }
+    }
 
-        // Satisfiable example
-        {
-            let formula = vec![
-                vec![Var(0), Var(0), Var(1)],
-                vec![Not(0), Not(1), Not(1)],
-                vec![Not(0), Var(1), Var(1)],
-            ];
-            let sat = check_sat(&formula);
+    let mut partial: Vec<Option<bool>> = vars.iter().map(|&truth| Some(truth)).collect();
+    let mut tried = vec![false; vars.len()];
+    let order = priority.iter().copied().chain(0..vars.len());
 
-            assert!(sat == Some(vec![false, true]));
+    for var in order {
+        if var >= vars.len() || tried[var] {
+            continue;
         }
+        tried[var] = true;
 
-        // Unsatisfiable example
+        // A clause with duplicate true literals of the variable counts them
+        // all, so it needs more than that many.
+        let needed = |index: usize| supported[var].iter().filter(|&&i| i == index).count();
+        if supported[var]
+            .iter()
+            .all(|&index| n_true[index] > needed(index))
         {
-            let formula = vec![
-                vec![Var(0), Var(1)],
-                vec![Not(0), Not(1)],
-                vec![Var(1), Var(2)],
-                vec![Not(1), Not(2)],
-                vec![Var(2), Var(0)],
-                vec![Not(2), Not(0)],
-            ];
-            let sat = check_sat(&formula);
+            for &index in &supported[var] {
+                n_true[index] -= 1;
+            }
+            partial[var] = None;
+        }
+    }
 
-            assert!(sat == None);
+    partial
+}
+
+/// Counts the clauses of each length in a formula. Returns (length, count)
+/// pairs in ascending order of length; lengths with no clause are omitted.
+pub fn clause_length_histogram(formula: &Formula) -> Vec<(usize, usize)> {
+    let mut counts = Vec::new();
+    for clause in formula {
+        if counts.len() <= clause.len() {
+            counts.resize(clause.len() + 1, 0);
         }
+        counts[clause.len()] += 1;
     }
 
-    #[test]
-    fn test_unit_propagate() {
-        let mut formula = vec![
-            vec![Var(1)],                 // (unit clause)
-            vec![Not(2)],                 // (unit clause)
-            vec![Var(1), Var(2)],         // => true
-            vec![Not(1), Var(2), Var(3)], // => 3 (unit clause)
-            vec![Var(0), Not(3), Var(4)], // => 0 | 4
-        ];
-        let mut vars = vec![false; 5];
+    counts
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, count)| count > 0)
+        .collect()
+}
 
-        unit_propagate(&mut formula, &mut vars);
+/// Resolves two clauses on the pivot variable. One clause must contain the
+/// pivot positively and the other negatively; the resolvent is the OR-sum of
+/// the remaining literals, which is entailed by the two clauses.
+///
+/// Returns None if the clauses do not clash on the pivot or the resolvent is
+/// tautological, i.e., contains a literal and its negation. Duplicate literals
+/// are kept only at their first occurrence, `a` first.
+pub fn resolve(a: &Clause, b: &Clause, pivot: usize) -> Option<Clause> {
+    let clashes = |a: &Clause, b: &Clause| a.contains(&Var(pivot)) && b.contains(&Not(pivot));
+    if !clashes(a, b) && !clashes(b, a) {
+        return None;
+    }
 
-        assert!(formula == vec![vec![Var(0), Var(4)]]);
-        assert!(vars == vec![false, true, false, true, false]);
+    let mut resolvent = Clause::new();
+    for &lit in a.iter().chain(b.iter()) {
+        if lit.variable() == pivot || resolvent.contains(&lit) {
+            continue;
+        }
+        if resolvent.contains(&lit.negate()) {
+            return None;
+        }
+        resolvent.push(lit);
     }
+    Some(resolvent)
+}
 
-    #[test]
-    fn test_simplify() {
-        // Raw and negated literals are resolved differently.
-        {
-            let mut formula = vec![vec![Var(1), Var(2)], vec![Not(1), Var(3)]];
-            simplify(&mut formula, 1, true);
-            assert!(formula == vec![vec![Var(3)]]);
+/// State shared by the recursions of `dpll`.
+struct Search<'a> {
+    heuristic: &'a mut dyn Heuristic,
+    pool: ClausePool,
+    interrupt: Option<&'a AtomicBool>,
+    interrupted: bool,
+    depth: usize,
+    stats: SearchStats,
+    counts: Option<LiteralCounts>,
+    // Indices of the clauses of the formula at the current node of `dpll` in
+    // the original formula. Dropped once the heuristic uses the counts.
+    origins: Option<Vec<usize>>,
+    mode: SimplifyMode,
+    priorities: Option<&'a [u32]>,
+}
+
+impl<'a> Search<'a> {
+    fn new(heuristic: &'a mut dyn Heuristic) -> Search<'a> {
+        Search {
+            heuristic,
+            pool: ClausePool::new(),
+            interrupt: None,
+            interrupted: false,
+            depth: 0,
+            stats: SearchStats::default(),
+            counts: None,
+            origins: None,
+            mode: SimplifyMode::Eager,
+            priorities: None,
         }
+    }
 
-        // Falsey unit clause becomes an empty clause.
-        {
-            let mut formula = vec![vec![Var(1)], vec![Var(2)]];
-            simplify(&mut formula, 1, false);
-            assert!(formula == vec![vec![], vec![Var(2)]]);
+    /// Chooses a branching literal from the literal counts if the heuristic
+    /// supports them, or from the formula otherwise. The origins of the
+    /// clauses are `origins` if given, or those tracked by `dpll`.
+    fn choose(&mut self, formula: &Formula, origins: Option<&[usize]>, n_vars: usize) -> Literal {
+        if let Some(counts) = &self.counts {
+            if let Some(lit) = self.heuristic.choose_by_counts(&counts.counts) {
+                self.origins = None;
+                return lit;
+            }
+            self.counts = None;
+        }
+        match origins.or(self.origins.as_deref()) {
+            Some(origins) => self.heuristic.choose_with_origins(formula, origins, n_vars),
+            None => self.heuristic.choose(formula, n_vars),
         }
     }
 
-    #[test]
-    fn test_find_dominant_variable() {
+    /// Adds `delta` to the count of a literal in a clause added or changed by
+    /// the search itself.
+    fn count(&mut self, lit: Literal, delta: i64) {
+        if let Some(counts) = &mut self.counts {
+            let count = &mut counts.counts[lit.code()];
+            *count = (*count as i64 + delta) as u64;
+        }
+    }
+
+    /// Checks the interrupt flag. Once interrupted, every branch fails so that
+    /// the recursion unwinds.
+    fn is_interrupted(&mut self) -> bool {
+        if let Some(flag) = self.interrupt {
+            self.interrupted |= flag.load(Ordering::Relaxed);
+        }
+        self.interrupted
+    }
+}
+
+fn dpll(formula: &Formula, vars: &mut Assignment, search: &mut Search) -> bool {
+    let mut formula = search.pool.copy(formula);
+
+    let n_vars = vars.len();
+    let saved = search.counts.as_ref().map(|counts| counts.counts.clone());
+    let saved_origins = search.origins.clone();
+    let mut clauses = EagerClauses::new(&mut formula, n_vars);
+    clauses.counts = search.counts.as_mut();
+    clauses.origins = search.origins.as_mut();
+    let propagated = propagate_units(&mut clauses, vars);
+    search.pool.recycle(clauses.into_removed());
+
+    // Below the root, the decision is the first unit propagated.
+    search.stats.propagations += propagated.saturating_sub((search.depth > 0) as usize) as u64;
+
+    let sat = if formula.is_empty() {
+        true
+    } else if formula.iter().any(|clause| clause.is_empty()) {
+        search.stats.conflicts += 1;
+        false
+    } else if search.is_interrupted() {
+        false
+    } else {
+        let lit = search.choose(&formula, None, vars.len());
+        search.stats.decisions += 1;
+        search.depth += 1;
+        search.stats.max_depth = search.stats.max_depth.max(search.depth);
+
+        let mut unit = search.pool.take();
+        unit.push(lit);
+        formula.push(unit);
+        search.count(lit, 1);
+        if let Some(origins) = &mut search.origins {
+            origins.push(usize::MAX);
+        }
+
+        let last = formula.len() - 1;
+        let sat = dpll(&formula, vars, search) || {
+            formula[last][0] = lit.negate();
+            search.count(lit, -1);
+            search.count(lit.negate(), 1);
+            dpll(&formula, vars, search)
+        };
+        search.depth -= 1;
+        sat
+    };
+
+    // This also takes back the count of the decision unit.
+    if let (Some(counts), Some(saved)) = (&mut search.counts, saved) {
+        counts.counts = saved;
+    }
+    if let (Some(origins), Some(saved)) = (&mut search.origins, saved_origins) {
+        *origins = saved;
+    }
+    search.pool.recycle(formula);
+    sat
+}
+
+/// Searches like `dpll` without modifying the formula. A node starts from the
+/// assignment `values` of its parent with the decision, propagates it over the
+/// clauses of the formula with `LazyClauses`, and passes the heuristic the
+/// unresolved clauses reduced to their unassigned literals.
+fn dpll_lazy(
+    formula: &FlatFormula,
+    values: &[Option<bool>],
+    vars: &mut Assignment,
+    search: &mut Search,
+) -> bool {
+    let n_vars = vars.len();
+    let mut clauses = LazyClauses::from_flat(formula, n_vars);
+    if let Some(priorities) = search.priorities {
+        clauses = clauses.with_priorities(priorities);
+    }
+    for (var, value) in values.iter().enumerate() {
+        if let Some(truth) = *value {
+            clauses.assign(var, truth);
+        }
+    }
+    search.stats.propagations += propagate_units(&mut clauses, vars) as u64;
+
+    let mut residual = Formula::new();
+    let mut origins = Vec::new();
+    for (index, clause) in formula.iter().enumerate() {
+        if clauses.status(index) != ClauseStatus::Satisfied {
+            let unassigned = clause
+                .iter()
+                .filter(|lit| clauses.value(lit.variable()).is_none());
+            residual.push(unassigned.copied().collect());
+            origins.push(index);
+        }
+    }
+
+    if residual.is_empty() {
+        for (var, truth) in vars.iter_mut().enumerate() {
+            if let Some(value) = clauses.value(var) {
+                *truth = value;
+            }
+        }
+        true
+    } else if residual.iter().any(|clause| clause.is_empty()) {
+        search.stats.conflicts += 1;
+        false
+    } else if search.is_interrupted() {
+        false
+    } else {
+        let lit = search.choose(&residual, Some(&origins), n_vars);
+        search.stats.decisions += 1;
+        search.depth += 1;
+        search.stats.max_depth = search.stats.max_depth.max(search.depth);
+
+        let mut child = clauses.values;
+        child[lit.variable()] = Some(matches!(lit, Var(_)));
+        let sat = dpll_lazy(formula, &child, vars, search) || {
+            child[lit.variable()] = Some(!matches!(lit, Var(_)));
+            dpll_lazy(formula, &child, vars, search)
+        };
+        search.depth -= 1;
+        sat
+    }
+}
+
+/// Number of occurrences of each literal in the formula at the current node of
+/// `dpll`, indexed by `Literal::code`.
+///
+/// The counts are decremented as the formula is simplified instead of being
+/// recounted from the clauses at every node. A node saves the counts of its
+/// parent and restores them before returning, which takes time proportional
+/// to the variables rather than the clauses.
+struct LiteralCounts {
+    counts: Vec<u64>,
+}
+
+impl LiteralCounts {
+    fn new(formula: &Formula, n_vars: usize) -> LiteralCounts {
+        let mut counts = vec![0; 2 * n_vars];
+        for lit in formula.iter().flatten() {
+            counts[lit.code()] += 1;
+        }
+        LiteralCounts { counts }
+    }
+}
+
+/// Reservoir of clause buffers for reuse.
+///
+/// Every recursion of `dpll` copies the formula and drops the copy on return,
+/// which makes a pair of allocation and deallocation per clause. The pool
+/// keeps the buffers of dropped clauses instead and hands them out for new
+/// clauses, so the search allocates only when the pool runs dry.
+struct ClausePool {
+    buffers: Vec<Clause>,
+}
+
+impl ClausePool {
+    fn new() -> ClausePool {
+        ClausePool {
+            buffers: Vec::new(),
+        }
+    }
+
+    /// Returns an empty clause, reusing a buffer if available.
+    fn take(&mut self) -> Clause {
+        self.buffers.pop().unwrap_or_default()
+    }
+
+    /// Copies a formula into buffers drawn from the pool.
+    fn copy(&mut self, formula: &Formula) -> Formula {
+        formula
+            .iter()
+            .map(|clause| {
+                let mut copy = self.take();
+                copy.extend_from_slice(clause);
+                copy
+            })
+            .collect()
+    }
+
+    /// Returns the buffers of the clauses of a formula to the pool.
+    fn recycle(&mut self, formula: Formula) {
+        for mut clause in formula {
+            clause.clear();
+            self.buffers.push(clause);
+        }
+    }
+}
+
+/// Solves a Horn formula without branching.
+///
+/// Unit propagation determines all variables that must be true. Every clause
+/// left after the propagation contains a negative literal since it has at most
+/// one positive literal and is not a unit. So, the formula is satisfied by
+/// assigning false to all the remaining variables, unless the propagation
+/// produced an empty clause. `vars` must be initialized to false.
+fn solve_horn(formula: &Formula, vars: &mut Assignment) -> bool {
+    let mut formula = formula.to_vec();
+
+    unit_propagate(&mut formula, vars);
+
+    !formula.iter().any(|clause| clause.is_empty())
+}
+
+/// Solves a 2-SAT formula in linear time.
+///
+/// # Implication graph
+///
+/// A binary clause is equivalent to a pair of implications:
+///
+/// > x ∨ y = (¬x ⇒ y) ∧ (¬y ⇒ x)
+///
+/// and a unit clause x is equivalent to ¬x ⇒ x. The formula is unsatisfiable
+/// if and only if some variable and its negation are in the same strongly
+/// connected component of the graph of these implications. Otherwise, a model
+/// is obtained by making each literal true if its component comes later in the
+/// topological order than the component of its negation.
+///
+fn solve_2sat(formula: &Formula, vars: &mut Assignment) -> bool {
+    let mut graph = vec![Vec::new(); 2 * vars.len()];
+
+    for clause in formula {
+        match *clause.as_slice() {
+            [] => return false,
+            [x] => graph[x.negate().code()].push(x.code()),
+            [x, y] => {
+                graph[x.negate().code()].push(y.code());
+                graph[y.negate().code()].push(x.code());
+            }
+            _ => panic!("not a 2-SAT formula"),
+        }
+    }
+
+    let components = find_strongly_connected_components(&graph);
+
+    for (i, truth) in vars.iter_mut().enumerate() {
+        let (pos, neg) = (components[2 * i], components[2 * i + 1]);
+        if pos == neg {
+            return false;
+        }
+        // Components are numbered in reverse topological order.
+        *truth = pos < neg;
+    }
+
+    true
+}
+
+/// Finds the strongly connected components of a directed graph given as
+/// adjacency lists, using Tarjan's algorithm.
+///
+/// Returns the component index of each node. Components are numbered in the
+/// reverse topological order of the condensed graph.
+fn find_strongly_connected_components(graph: &[Vec<usize>]) -> Vec<usize> {
+    const UNVISITED: usize = usize::MAX;
+
+    let n = graph.len();
+    let mut index = vec![UNVISITED; n];
+    let mut lowlink = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut components = vec![0; n];
+    let mut n_visited = 0;
+    let mut n_components = 0;
+
+    // The depth-first search is done with an explicit stack of (node, index of
+    // the next edge to follow) so that deep graphs do not overflow the stack.
+    let mut path: Vec<(usize, usize)> = Vec::new();
+
+    for root in 0..n {
+        if index[root] != UNVISITED {
+            continue;
+        }
+
+        index[root] = n_visited;
+        lowlink[root] = n_visited;
+        n_visited += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        path.push((root, 0));
+
+        while let Some(&(v, edge)) = path.last() {
+            if edge < graph[v].len() {
+                path.last_mut().unwrap().1 += 1;
+
+                let w = graph[v][edge];
+                if index[w] == UNVISITED {
+                    index[w] = n_visited;
+                    lowlink[w] = n_visited;
+                    n_visited += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    path.push((w, 0));
+                } else if on_stack[w] {
+                    lowlink[v] = lowlink[v].min(index[w]);
+                }
+                continue;
+            }
+
+            path.pop();
+
+            if let Some(&(u, _)) = path.last() {
+                lowlink[u] = lowlink[u].min(lowlink[v]);
+            }
+
+            if lowlink[v] == index[v] {
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    components[w] = n_components;
+                    if w == v {
+                        break;
+                    }
+                }
+                n_components += 1;
+            }
+        }
+    }
+
+    components
+}
+
+/// Resolves unit clauses in a CNF formula.
+///
+/// # Unit propagation
+///
+/// A unit clause is a clause consisting of a single literal:
+///
+/// > formula = ... ∧ x ∧ ...
+///
+/// Such a clause in a CNF formula induces an assignment `x = true` so that the
+/// formula must become true. Unit propagation finds out such assignments and
+/// simplifies the formula until all unit clauses are consumed.
+///
+fn unit_propagate(formula: &mut Formula, vars: &mut Assignment) {
+    let n_vars = vars.len();
+    propagate_units(&mut EagerClauses::new(formula, n_vars), vars);
+}
+
+/// Runs unit propagation on a clause storage, recording the assignments in
+/// `vars`. Returns the number of literals assigned.
+pub fn propagate_units(clauses: &mut dyn ClauseStore, vars: &mut Assignment) -> usize {
+    let mut n_assigned = 0;
+    while let Some(lit) = clauses.find_unit() {
+        let (var, truth) = match lit {
+            Var(i) => (i, true),
+            Not(i) => (i, false),
+        };
+        vars[var] = truth;
+        clauses.assign(var, truth);
+        n_assigned += 1;
+    }
+    n_assigned
+}
+
+/// Storage of the clauses of a formula under a growing partial assignment, on
+/// which unit propagation works.
+///
+/// # Storage modes
+///
+/// `EagerClauses` simplifies the formula in place: satisfied clauses and false
+/// literals are physically removed, so a unit clause is just a clause of one
+/// literal. `LazyClauses` does not modify the clauses at all. It keeps the
+/// values of the variables and counts the true and false literals of each
+/// clause instead, which tell whether the clause is satisfied, falsified or a
+/// unit. The original clauses thus remain available, e.g., for conflict
+/// analysis, at the cost of the counters.
+pub trait ClauseStore {
+    /// Returns the unassigned literal of a unit clause, if any.
+    fn find_unit(&mut self) -> Option<Literal>;
+
+    /// Assigns a truth value to an unassigned variable.
+    fn assign(&mut self, var: usize, truth: bool);
+}
+
+/// Clause storage simplifying a formula in place. See `simplify`.
+///
+/// The literals of the unit clauses are queued as the clauses shrink to one
+/// literal, so a unit is found without scanning the formula. A queued literal
+/// goes stale when its variable gets assigned, which is the only way its
+/// clause can be removed or falsified.
+pub struct EagerClauses<'a> {
+    formula: &'a mut Formula,
+    occurrences: Occurrences,
+    removed: Formula,
+    units: VecDeque<Literal>,
+    assigned: Vec<bool>,
+    counts: Option<&'a mut LiteralCounts>,
+    origins: Option<&'a mut Vec<usize>>,
+}
+
+impl<'a> EagerClauses<'a> {
+    /// Creates a storage simplifying `formula` over `n_vars` variables.
+    pub fn new(formula: &'a mut Formula, n_vars: usize) -> EagerClauses<'a> {
+        let occurrences = Occurrences::new(formula.iter(), n_vars);
+        let units = formula
+            .iter()
+            .filter(|clause| clause.len() == 1)
+            .map(|clause| clause[0])
+            .collect();
+        EagerClauses {
+            formula,
+            occurrences,
+            removed: Formula::new(),
+            units,
+            assigned: vec![false; n_vars],
+            counts: None,
+            origins: None,
+        }
+    }
+
+    /// Returns the clauses removed from the formula as satisfied.
+    pub fn into_removed(self) -> Formula {
+        self.removed
+    }
+}
+
+impl ClauseStore for EagerClauses<'_> {
+    fn find_unit(&mut self) -> Option<Literal> {
+        while let Some(lit) = self.units.pop_front() {
+            if !self.assigned[lit.variable()] {
+                return Some(lit);
+            }
+        }
+        None
+    }
+
+    fn assign(&mut self, var: usize, truth: bool) {
+        self.assigned[var] = true;
+        let n_removed = self.removed.len();
+        simplify(
+            self.formula,
+            &mut self.occurrences,
+            &mut self.removed,
+            &mut self.units,
+            self.origins.as_deref_mut(),
+            var,
+            truth,
+        );
+
+        // The removed clauses take their literals away, and the remaining
+        // occurrences of the falsified literal are all removed.
+        if let Some(counts) = self.counts.as_deref_mut() {
+            for lit in self.removed[n_removed..].iter().flatten() {
+                counts.counts[lit.code()] -= 1;
+            }
+            let falsey_lit = if truth { Not(var) } else { Var(var) };
+            counts.counts[falsey_lit.code()] = 0;
+        }
+    }
+}
+
+/// Clause storage leaving the clauses intact and tracking their status under
+/// the assignment with counters. The clauses are stored as a `FlatFormula`.
+pub struct LazyClauses<'a> {
+    formula: Cow<'a, FlatFormula>,
+    occurrences: Occurrences,
+    values: Vec<Option<bool>>,
+    n_true: Vec<usize>,
+    n_false: Vec<usize>,
+    // Clauses that may have become units, ranked by priority if given.
+    pending: Vec<usize>,
+    priorities: Option<&'a [u32]>,
+    ranked: BinaryHeap<(u32, usize)>,
+}
+
+/// Status of a clause under a partial assignment.
+#[derive(Debug, PartialEq)]
+pub enum ClauseStatus {
+    Satisfied,
+    Falsified,
+    Unit(Literal),
+    Unresolved,
+}
+
+impl<'a> LazyClauses<'a> {
+    /// Creates a storage of the clauses of `formula` over `n_vars` variables,
+    /// all of which are unassigned. The clauses are copied into a flat
+    /// formula.
+    pub fn new(formula: &Formula, n_vars: usize) -> LazyClauses<'a> {
+        LazyClauses::with_formula(Cow::Owned(FlatFormula::from(formula)), n_vars)
+    }
+
+    /// Creates a storage like `new` sharing the clauses of a flat formula.
+    pub fn from_flat(formula: &'a FlatFormula, n_vars: usize) -> LazyClauses<'a> {
+        LazyClauses::with_formula(Cow::Borrowed(formula), n_vars)
+    }
+
+    fn with_formula(formula: Cow<'a, FlatFormula>, n_vars: usize) -> LazyClauses<'a> {
+        LazyClauses {
+            occurrences: Occurrences::new(formula.iter(), n_vars),
+            values: vec![None; n_vars],
+            n_true: vec![0; formula.len()],
+            n_false: vec![0; formula.len()],
+            pending: (0..formula.len()).rev().collect(),
+            priorities: None,
+            ranked: BinaryHeap::new(),
+            formula,
+        }
+    }
+
+    /// Makes `find_unit` return the unit of the clause of the highest
+    /// priority among the units. The i-th priority is that of the i-th clause.
+    /// Without priorities, the unit found is the one of the clause that most
+    /// recently lost a literal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of priorities differs from the number of clauses.
+    pub fn with_priorities(mut self, priorities: &'a [u32]) -> LazyClauses<'a> {
+        assert_eq!(
+            priorities.len(),
+            self.formula.len(),
+            "priority count differs"
+        );
+        for index in self.pending.drain(..) {
+            self.ranked.push((priorities[index], index));
+        }
+        self.priorities = Some(priorities);
+        self
+    }
+
+    /// Returns the status of the clause at `index`.
+    pub fn status(&self, index: usize) -> ClauseStatus {
+        let clause = self.formula.clause(index);
+        if self.n_true[index] > 0 {
+            return ClauseStatus::Satisfied;
+        }
+        match clause.len() - self.n_false[index] {
+            0 => ClauseStatus::Falsified,
+            1 => {
+                let lit = clause
+                    .iter()
+                    .find(|lit| self.values[lit.variable()].is_none());
+                ClauseStatus::Unit(*lit.unwrap())
+            }
+            _ => ClauseStatus::Unresolved,
+        }
+    }
+
+    /// Returns the value of a variable, or None if it is unassigned.
+    pub fn value(&self, var: usize) -> Option<bool> {
+        self.values[var]
+    }
+
+    /// Checks if some clause is falsified by the assignment.
+    pub fn has_conflict(&self) -> bool {
+        (0..self.formula.len()).any(|i| self.status(i) == ClauseStatus::Falsified)
+    }
+}
+
+impl ClauseStore for LazyClauses<'_> {
+    fn find_unit(&mut self) -> Option<Literal> {
+        if self.priorities.is_some() {
+            while let Some(&(_, index)) = self.ranked.peek() {
+                if let ClauseStatus::Unit(lit) = self.status(index) {
+                    return Some(lit);
+                }
+                self.ranked.pop();
+            }
+            return None;
+        }
+
+        while let Some(&index) = self.pending.last() {
+            if let ClauseStatus::Unit(lit) = self.status(index) {
+                return Some(lit);
+            }
+            self.pending.pop();
+        }
+        None
+    }
+
+    fn assign(&mut self, var: usize, truth: bool) {
+        let truthy_lit = if truth { Var(var) } else { Not(var) };
+        let falsey_lit = truthy_lit.negate();
+        self.values[var] = Some(truth);
+
+        for &index in &self.occurrences.lists[truthy_lit.code()] {
+            self.n_true[index] += 1;
+        }
+        for &index in &self.occurrences.lists[falsey_lit.code()] {
+            self.n_false[index] += 1;
+            match self.priorities {
+                Some(priorities) => self.ranked.push((priorities[index], index)),
+                None => self.pending.push(index),
+            }
+        }
+    }
+}
+
+/// Indices of the clauses containing each literal of a formula.
+struct Occurrences {
+    lists: Vec<Vec<usize>>,
+}
+
+impl Occurrences {
+    /// Lists the occurrences in the clauses, indexed in the order given.
+    fn new<C: AsRef<[Literal]>>(
+        clauses: impl IntoIterator<Item = C>,
+        n_vars: usize,
+    ) -> Occurrences {
+        let mut lists = vec![Vec::new(); 2 * n_vars];
+        for (index, clause) in clauses.into_iter().enumerate() {
+            for lit in clause.as_ref() {
+                lists[lit.code()].push(index);
+            }
+        }
+        Occurrences { lists }
+    }
+
+    /// Removes and returns the indices of the clauses containing `lit`.
+    fn take(&mut self, lit: Literal) -> Vec<usize> {
+        core::mem::take(&mut self.lists[lit.code()])
+    }
+
+    /// Removes an occurrence of `lit` in the clause at `index`.
+    fn remove(&mut self, lit: Literal, index: usize) {
+        let list = &mut self.lists[lit.code()];
+        if let Some(pos) = list.iter().position(|&i| i == index) {
+            list.swap_remove(pos);
+        }
+    }
+
+    /// Records that an occurrence of `lit` moved from clause `from` to `to`.
+    fn rename(&mut self, lit: Literal, from: usize, to: usize) {
+        let list = &mut self.lists[lit.code()];
+        if let Some(pos) = list.iter().position(|&i| i == from) {
+            list[pos] = to;
+        }
+    }
+}
+
+/// Simplifies a CNF formula using given variable assignment.
+///
+/// # Simplification
+///
+/// A variable assignment resolves literals `Var(var)` and `Not(var)` in the
+/// formula to true or false. Note that a false literal (say x) does not
+/// contribute to the condition of a clause:
+///
+/// > C ∨ x = C  if x = false
+///
+/// so, a simplification will remove all false literals from the formula. On the
+/// other hand, a true literal (say y) makes the clause containing y true:
+///
+/// > C ∨ y = true  if y = true
+///
+/// i.e., the clause becomes true. So, a simplification will remove all clauses
+/// containing one or more true literals.
+///
+/// # Empty clause
+///
+/// The simplification may leave empty clause(s) in the formula. An empty clause
+/// must have originated from a unit clause consisting of a false literal x:
+///
+/// > formula = ... ∧ x ∧ ... ,  x = false .
+///
+/// Therefore, the formula must be unsatisfiable in that case.
+///
+/// # Occurrence lists
+///
+/// Only the clauses containing `Var(var)` or `Not(var)` are visited, which are
+/// looked up in the occurrence lists. The lists are updated as clauses are
+/// removed and moved. The removed clauses are appended to `removed`, and the
+/// literals of the clauses shrinking to unit clauses are pushed to `units`.
+/// If given, `origins` is a list parallel to the clauses, which is rearranged
+/// along with them.
+///
+fn simplify(
+    formula: &mut Formula,
+    occurrences: &mut Occurrences,
+    removed: &mut Formula,
+    units: &mut VecDeque<Literal>,
+    mut origins: Option<&mut Vec<usize>>,
+    var: usize,
+    truth: bool,
+) {
+    let truthy_lit = if truth { Var(var) } else { Not(var) };
+    let falsey_lit = if truth { Not(var) } else { Var(var) };
+
+    // Remove unconditionally true clauses. A clause is removed by moving the
+    // last clause into its place, so removing from the back keeps the indices
+    // of the remaining ones valid.
+    let mut satisfied = occurrences.take(truthy_lit);
+    satisfied.sort_unstable();
+    satisfied.dedup();
+
+    for &clause_index in satisfied.iter().rev() {
+        for &lit in &formula[clause_index] {
+            if lit != truthy_lit {
+                occurrences.remove(lit, clause_index);
+            }
+        }
+
+        let last = formula.len() - 1;
+        if clause_index != last {
+            for &lit in &formula[last] {
+                occurrences.rename(lit, last, clause_index);
+            }
+        }
+        removed.push(formula.swap_remove(clause_index));
+        if let Some(origins) = origins.as_deref_mut() {
+            origins.swap_remove(clause_index);
+        }
+    }
+
+    // Remove falsified literals.
+    for clause_index in occurrences.take(falsey_lit) {
+        let clause = &mut formula[clause_index];
+        let original_len = clause.len();
+        let mut literal_index = 0;
+        while literal_index < clause.len() {
+            if clause[literal_index] == falsey_lit {
+                let n = clause.len();
+                clause.swap(literal_index, n - 1);
+                clause.pop();
+                continue;
+            }
+            literal_index += 1;
+        }
+        if clause.len() == 1 && original_len > 1 {
+            units.push_back(clause[0]);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::partialeq_to_none)]
+mod test {
+    use super::*;
+    use crate::dimacs;
+    use crate::heuristic::DominantPhase;
+    use crate::testing::random_3sat;
+
+    #[test]
+    fn test_literal_display() {
+        assert_eq!(Var(0).to_string(), "1");
+        assert_eq!(Not(1).to_string(), "-2");
+        assert_eq!(format!("{:>4}", Var(9)), "  10");
+    }
+
+    #[test]
+    fn test_display_clause() {
+        let clause = vec![Var(0), Not(1), Var(11)];
+        assert_eq!(display_clause(&clause).to_string(), "1 -2 12 0");
+        assert_eq!(display_clause(&[Not(4)]).to_string(), "-5 0");
+        assert_eq!(display_clause(&[]).to_string(), "0");
+    }
+
+    #[test]
+    fn test_check_sat() {
+        // Empty formula
+        {
+            let formula = vec![];
+            let sat = check_sat(&formula);
+
+            assert!(sat == Some(vec![]));
+        }
+
+        // Satisfiable example
+        {
+            let formula = vec![
+                vec![Var(0), Var(0), Var(1)],
+                vec![Not(0), Not(1), Not(1)],
+                vec![Not(0), Var(1), Var(1)],
+            ];
+            let sat = check_sat(&formula);
+
+            assert!(sat == Some(vec![false, true]));
+        }
+
+        // Unsatisfiable example
+        {
+            let formula = vec![
+                vec![Var(0), Var(1)],
+                vec![Not(0), Not(1)],
+                vec![Var(1), Var(2)],
+                vec![Not(1), Not(2)],
+                vec![Var(2), Var(0)],
+                vec![Not(2), Not(0)],
+            ];
+            let sat = check_sat(&formula);
+
+            assert!(sat == None);
+        }
+    }
+
+    #[test]
+    fn test_check_sat_model_length() {
+        // Horn, 2-SAT and general formulas using fewer variables than declared,
+        // with gaps in the used variables.
+        let sources = [
+            "p cnf 6 2\n-1 -3 0\n3 0\n",
+            "p cnf 6 3\n1 -5 0\n5 2 0\n-1 -2 0\n",
+            "p cnf 8 3\n1 2 5 0\n-1 -2 0\n-5 -1 0\n",
+        ];
+        let lengths = [3, 5, 5];
+
+        for (source, &length) in sources.iter().zip(lengths.iter()) {
+            let formula = match dimacs::load(&mut source.as_bytes(), true) {
+                Ok(formula) => formula,
+                Err(err) => panic!("unexpected: {}", err),
+            };
+            let vars = check_sat(&formula).unwrap();
+            assert_eq!(vars.len(), length);
+            assert_valid_model(&formula, &vars);
+        }
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let formula = vec![
+            vec![Var(0), Not(2)],
+            vec![Var(1)],
+            vec![Var(3), Var(4)],
+            vec![],
+            vec![Not(2), Var(4)],
+            vec![Not(1), Var(5)],
+        ];
+        assert_eq!(
+            connected_components(&formula),
+            vec![
+                vec![
+                    vec![Var(0), Not(2)],
+                    vec![Var(3), Var(4)],
+                    vec![Not(2), Var(4)]
+                ],
+                vec![vec![Var(1)], vec![Not(1), Var(5)]],
+                vec![vec![]],
+            ]
+        );
+        assert_eq!(connected_components(&vec![]), Vec::<Formula>::new());
+    }
+
+    #[test]
+    fn test_solve_by_components() {
+        // Two satisfiable components, the first not solved by propagation.
+        let mut formula = vec![
+            vec![Var(0), Var(1), Var(2)],
+            vec![Not(0), Not(1)],
+            vec![Var(4), Not(5)],
+            vec![Not(1), Not(2)],
+            vec![Var(5)],
+            vec![Not(0), Not(2)],
+        ];
+        assert_eq!(connected_components(&formula).len(), 2);
+        let vars = solve_by_components(&formula).unwrap();
+        assert_valid_model(&formula, &vars);
+
+        // The second component is unsatisfiable.
+        formula.push(vec![Not(4)]);
+        assert_eq!(solve_by_components(&formula), None);
+        assert_eq!(check_sat(&formula), None);
+
+        assert_eq!(solve_by_components(&vec![]), Some(vec![]));
+    }
+
+    #[test]
+    fn test_equisatisfiable() {
+        let formula = vec![
+            vec![Var(0), Var(1), Var(2)],
+            vec![Not(0), Var(1), Not(1)],
+            vec![Not(1), Not(2)],
+            vec![Var(2), Not(0)],
+        ];
+        let mut reduced = formula.clone();
+        reduced.retain(|clause| !clause.iter().any(|lit| clause.contains(&lit.negate())));
+        assert_eq!(reduced.len(), 3);
+        assert!(equisatisfiable(&formula, &reduced));
+
+        let unsat = vec![vec![Var(0)], vec![Not(0)]];
+        assert!(!equisatisfiable(&formula, &unsat));
+        assert!(equisatisfiable(&unsat, &vec![vec![]]));
+        assert!(equisatisfiable(&vec![], &vec![vec![Var(5)]]));
+    }
+
+    #[test]
+    fn test_entails() {
+        // 0 => 1, 1 => 2, and 0 or 3.
+        let formula = vec![
+            vec![Not(0), Var(1)],
+            vec![Not(1), Var(2)],
+            vec![Var(0), Var(3)],
+        ];
+        assert!(entails(&formula, &vec![Not(0), Var(2)]));
+        assert!(entails(&formula, &vec![Var(2), Var(3)]));
+        assert!(entails(&formula, &vec![Var(5), Not(5)]));
+        assert!(!entails(&formula, &vec![Var(2)]));
+        assert!(!entails(&formula, &vec![Var(0), Not(2)]));
+        assert!(!entails(&formula, &vec![]));
+
+        // Validity is entailment by the empty formula.
+        assert!(entails(&vec![], &vec![Var(0), Not(0)]));
+        assert!(!entails(&vec![], &vec![Var(0), Var(1)]));
+
+        let unsat = vec![vec![Var(0)], vec![Not(0)]];
+        assert!(entails(&unsat, &vec![]));
+        assert!(entails(&unsat, &vec![Var(7)]));
+    }
+
+    #[test]
+    fn test_assert_valid_model() {
+        let formula = vec![vec![Var(0), Var(2)], vec![Not(2)]];
+        assert_valid_model(&formula, &vec![true, false, false]);
+        assert_valid_model(&vec![], &vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "model length differs")]
+    fn test_assert_valid_model_length() {
+        let formula = vec![vec![Var(0), Var(2)], vec![Not(2)]];
+        assert_valid_model(&formula, &vec![true, false, false, true]);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not satisfy")]
+    fn test_assert_valid_model_unsatisfied() {
+        let formula = vec![vec![Var(0), Var(2)], vec![Not(2)]];
+        assert_valid_model(&formula, &vec![false, false, false]);
+    }
+
+    #[test]
+    fn test_literal_variable() {
+        assert_eq!(Var(3).variable(), 3);
+        assert_eq!(Not(3).variable(), 3);
+    }
+
+    #[test]
+    fn test_literal_negate() {
+        assert_eq!(Var(3).negate(), Not(3));
+        assert_eq!(Not(3).negate(), Var(3));
+    }
+
+    #[test]
+    fn test_literal_code() {
+        assert_eq!(Var(3).code(), 6);
+        assert_eq!(Not(3).code(), 7);
+    }
+
+    #[test]
+    fn test_literal_dimacs() {
+        assert_eq!(Literal::from_dimacs(1), Var(0));
+        assert_eq!(Literal::from_dimacs(-2), Not(1));
+        assert_eq!(Var(0).to_dimacs(), 1);
+        assert_eq!(Not(1).to_dimacs(), -2);
+    }
+
+    #[test]
+    fn test_clause_satisfied() {
+        let clause = vec![Var(0), Not(1)];
+
+        assert!(clause_satisfied(&clause, &vec![true, true]));
+        assert!(clause_satisfied(&clause, &vec![false, false]));
+        assert!(!clause_satisfied(&clause, &vec![false, true]));
+
+        // A variable beyond the assignment makes neither literal true.
+        assert!(!clause_satisfied(&clause, &vec![false]));
+        assert!(!clause_satisfied(&vec![Not(2)], &vec![false]));
+
+        // Empty clause is never satisfied.
+        assert!(!clause_satisfied(&vec![], &vec![true, false]));
+        assert!(!clause_satisfied(&vec![], &vec![]));
+    }
+
+    #[test]
+    fn test_check_sat_map() {
+        let formula = vec![
+            vec![Var(1000), Var(5)],
+            vec![Not(1000), Not(5)],
+            vec![Not(5)],
+            vec![Var(0), Not(0)],
+        ];
+        let model = check_sat_map(&formula).unwrap();
+
+        let mut keys: Vec<usize> = model.keys().copied().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, vec![0, 5, 1000]);
+        assert!(model[&1000]);
+        assert!(!model[&5]);
+
+        let vars = check_sat(&formula).unwrap();
+        for (&var, &truth) in &model {
+            assert_eq!(truth, vars[var]);
+        }
+
+        assert!(check_sat_map(&vec![]).unwrap().is_empty());
+        assert_eq!(check_sat_map(&vec![vec![Var(7)], vec![Not(7)]]), None);
+    }
+
+    #[test]
+    fn test_num_variables() {
+        assert_eq!(num_variables(&vec![]), 0);
+        assert_eq!(num_variables(&vec![vec![]]), 0);
+        assert_eq!(num_variables(&vec![vec![Not(0)]]), 1);
+        assert_eq!(num_variables(&vec![vec![Var(4), Not(2)]]), 5);
+        assert_eq!(
+            num_variables(&vec![vec![Var(1)], vec![], vec![Not(7), Var(3)]]),
+            8
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let formula = vec![vec![Var(0), Not(1)], vec![Var(1), Var(2)]];
+
+        assert!(verify(&formula, &vec![true, false, true]));
+        assert!(verify(&formula, &vec![false, false, true]));
+        assert!(!verify(&formula, &vec![false, true, true]));
+        assert!(!verify(&formula, &vec![true, false, false]));
+
+        // Assignment not covering all variables.
+        assert!(!verify(&formula, &vec![true]));
+
+        // Empty formula is always satisfied.
+        assert!(verify(&vec![], &vec![]));
+    }
+
+    #[test]
+    fn test_unsatisfied_clauses() {
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Not(0), Var(2), Var(3)],
+            vec![Not(1), Not(2)],
+            vec![Var(2), Not(3)],
+            vec![Not(2), Var(4)],
+        ];
+
+        // Satisfies 0, 1 and 3 and falsifies nothing.
+        let partial = vec![Some(true), None, Some(true)];
+        assert_eq!(unsatisfied_clauses(&formula, &partial), vec![2, 4]);
+
+        // Falsifies 3 and satisfies the others but 0, which is left open.
+        let partial = vec![None, None, Some(false), Some(true), Some(true)];
+        assert_eq!(unsatisfied_clauses(&formula, &partial), vec![0]);
+
+        // Every nonempty clause is open without an assignment.
+        let mut with_empty = formula.clone();
+        with_empty.push(vec![]);
+        assert_eq!(unsatisfied_clauses(&with_empty, &[]), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_dont_cares() {
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Var(1), Not(2)],
+            vec![Var(2), Var(3), Var(3)],
+        ];
+        let vars = vec![true, true, false, true];
+
+        // By default, 0 goes first and 1 has to stay for clause 0.
+        assert_eq!(
+            dont_cares(&formula, &vars, &[]),
+            vec![None, Some(true), None, Some(true)]
+        );
+
+        // Preferring 1 keeps 0 and 2 instead.
+        let partial = dont_cares(&formula, &vars, &[1, 7]);
+        assert_eq!(partial, vec![Some(true), None, Some(false), Some(true)]);
+
+        // Every completion of the partial assignment is a model.
+        assert!(unsatisfied_clauses(&formula, &partial).is_empty());
+        for &free in &[false, true] {
+            assert!(verify(&formula, &vec![true, free, false, true]));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "not a model")]
+    fn test_dont_cares_not_model() {
+        dont_cares(&vec![vec![Var(0)]], &vec![false], &[]);
+    }
+
+    #[test]
+    fn test_remove_satisfied() {
+        let original = vec![
+            vec![Var(0), Var(1)],
+            vec![Not(0), Var(2)],
+            vec![Not(1), Not(2), Var(3)],
+            vec![Var(2), Not(3)],
+            vec![Not(2), Var(3)],
+        ];
+        let vars = vec![Some(true), None, Some(false)];
+
+        let mut formula = original.clone();
+        remove_satisfied(&mut formula, &vars);
+        let expect = vec![vec![Not(0), Var(2)], vec![Var(2), Not(3)]];
+        assert_eq!(formula, expect);
+
+        // Unassigned variables, including those beyond the assignment, satisfy
+        // nothing.
+        let mut formula = original.clone();
+        remove_satisfied(&mut formula, &[None, None]);
+        assert_eq!(formula, original);
+
+        // The verdict is unchanged under the assignment.
+        let mut fixed = original.clone();
+        let mut reduced = original;
+        remove_satisfied(&mut reduced, &vars);
+        for (i, value) in vars.iter().enumerate() {
+            if let Some(truth) = *value {
+                let lit = if truth { Var(i) } else { Not(i) };
+                fixed.push(vec![lit]);
+                reduced.push(vec![lit]);
+            }
+        }
+        assert_eq!(check_sat(&fixed).is_some(), check_sat(&reduced).is_some());
+    }
+
+    #[test]
+    fn test_clause_length_histogram() {
+        let formula = vec![
+            vec![Var(0), Not(1), Var(2)],
+            vec![Var(1)],
+            vec![Not(0), Var(2)],
+            vec![Var(0), Var(1), Var(2), Var(3), Var(4)],
+            vec![Not(2), Not(3)],
+            vec![Not(0), Var(1), Not(4)],
+            vec![Not(1), Var(3)],
+        ];
+        let expect = vec![(1, 1), (2, 3), (3, 2), (5, 1)];
+        assert_eq!(clause_length_histogram(&formula), expect);
+
+        assert_eq!(clause_length_histogram(&vec![]), vec![]);
+        assert_eq!(clause_length_histogram(&vec![vec![]]), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_clause_pool() {
+        let mut pool = ClausePool::new();
+        pool.recycle(vec![Vec::with_capacity(16)]);
+
+        let formula = vec![vec![Var(0), Not(1)]];
+        let copy = pool.copy(&formula);
+        assert_eq!(copy, formula);
+        assert!(copy[0].capacity() >= 16);
+        assert!(pool.take().is_empty());
+
+        // A pool filled with used buffers gives the same search.
         let formula = vec![
             vec![Var(0), Var(1), Var(2)],
+            vec![Not(0), Not(1), Var(2)],
+            vec![Var(0), Not(2), Var(3)],
+            vec![Not(1), Not(3), Var(4)],
+            vec![Not(2), Not(4), Not(0)],
+        ];
+        let mut fresh = vec![false; 5];
+        let mut reused = vec![false; 5];
+        let mut heuristic = DominantVariable;
+        let mut search = Search::new(&mut heuristic);
+        search.pool.recycle(vec![vec![Var(7); 3]; 10]);
+        assert!(dpll(
+            &formula,
+            &mut fresh,
+            &mut Search::new(&mut DominantVariable)
+        ));
+        assert!(dpll(&formula, &mut reused, &mut search));
+        assert_eq!(fresh, reused);
+    }
+
+    #[test]
+    fn test_check_sat_until() {
+        /// Sets the interrupt flag at the second decision.
+        struct Interrupting<'a> {
+            flag: &'a AtomicBool,
+            decisions: usize,
+        }
+
+        impl Heuristic for Interrupting<'_> {
+            fn choose(&mut self, formula: &Formula, n_vars: usize) -> Literal {
+                self.decisions += 1;
+                if self.decisions == 2 {
+                    self.flag.store(true, Ordering::Relaxed);
+                }
+                DominantVariable.choose(formula, n_vars)
+            }
+        }
+
+        // Unsatisfiable, and needs more than two decisions.
+        let mut formula = Vec::new();
+        for bits in 0..16 {
+            let clause = (0..4)
+                .map(|i| if bits >> i & 1 == 1 { Var(i) } else { Not(i) })
+                .collect();
+            formula.push(clause);
+        }
+
+        let flag = AtomicBool::new(false);
+        let mut heuristic = Interrupting {
+            flag: &flag,
+            decisions: 0,
+        };
+        let outcome = check_sat_until(&formula, &mut heuristic, &flag);
+        assert_eq!(outcome, Outcome::Unknown);
+        assert_eq!(heuristic.decisions, 2);
+
+        // The search unwinds before making a third decision.
+        let flag = AtomicBool::new(false);
+        let mut heuristic = Interrupting {
+            flag: &flag,
+            decisions: 0,
+        };
+        let (outcome, stats) = check_sat_until_with_stats(&formula, &mut heuristic, &flag);
+        assert_eq!(outcome, Outcome::Unknown);
+        assert_eq!(stats.decisions, 2);
+        assert_eq!(stats.max_depth, 2);
+
+        // The complete search of the 4 variables.
+        let flag = AtomicBool::new(false);
+        let (outcome, stats) = check_sat_until_with_stats(&formula, &mut DominantVariable, &flag);
+        assert_eq!(outcome, Outcome::Unsat);
+        assert_eq!(stats.max_depth, 3);
+        assert_eq!(stats.decisions, 7);
+
+        // Each of the 8 branches propagates the last variable into a conflict.
+        assert_eq!(stats.conflicts, 8);
+        assert_eq!(stats.propagations, 8);
+
+        let flag = AtomicBool::new(false);
+        let outcome = check_sat_until(&formula, &mut DominantVariable, &flag);
+        assert_eq!(outcome, Outcome::Unsat);
+
+        formula.pop();
+        match check_sat_until(&formula, &mut DominantVariable, &flag) {
+            Outcome::Sat(vars) => assert!(verify(&formula, &vars)),
+            outcome => panic!("unexpected: {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn test_is_horn() {
+        assert!(is_horn(&vec![]));
+        assert!(is_horn(&vec![vec![Var(0), Not(1)], vec![Not(0), Not(1)]]));
+        assert!(!is_horn(&vec![vec![Var(0), Var(1)], vec![Not(0)]]));
+    }
+
+    #[test]
+    fn test_solve_horn() {
+        // Satisfiable: 0, 0 => 1, 0 & 1 => 2, !2 | !3.
+        let satisfiable = vec![
+            vec![Var(0)],
             vec![Not(0), Var(1)],
-            vec![Not(1), Var(2)],
-            vec![Var(0), Not(1), Not(2)],
+            vec![Not(0), Not(1), Var(2)],
+            vec![Not(2), Not(3)],
+        ];
+
+        // Unsatisfiable: 0, 0 => 1, !0 | !1.
+        let unsatisfiable = vec![vec![Var(0)], vec![Not(0), Var(1)], vec![Not(0), Not(1)]];
+
+        for formula in &[satisfiable, unsatisfiable] {
+            assert!(is_horn(formula));
+
+            let mut fast = vec![false; 4];
+            let mut general = vec![false; 4];
+            let fast_sat = solve_horn(formula, &mut fast);
+            let general_sat = dpll(
+                formula,
+                &mut general,
+                &mut Search::new(&mut DominantVariable),
+            );
+
+            assert_eq!(fast_sat, general_sat);
+            if fast_sat {
+                assert!(verify(formula, &fast));
+                assert!(verify(formula, &general));
+            }
+            assert_eq!(check_sat(formula).is_some(), fast_sat);
+        }
+    }
+
+    #[test]
+    fn test_check_sat_non_horn() {
+        let formula = vec![vec![Var(0), Var(1)], vec![Not(0)], vec![Not(1), Var(2)]];
+        assert!(!is_horn(&formula));
+
+        let vars = check_sat(&formula).unwrap();
+        assert!(verify(&formula, &vars));
+    }
+
+    #[test]
+    fn test_is_2sat() {
+        assert!(is_2sat(&vec![]));
+        assert!(is_2sat(&vec![vec![Var(0), Not(1)], vec![Var(2)]]));
+        assert!(!is_2sat(&vec![vec![Var(0), Var(1), Var(2)]]));
+    }
+
+    #[test]
+    fn test_solve_2sat() {
+        let formulas = [
+            // Satisfiable chain of implications.
+            vec![
+                vec![Not(0), Var(1)],
+                vec![Not(1), Var(2)],
+                vec![Var(0), Var(2)],
+                vec![Not(2), Not(3)],
+            ],
+            // Satisfiable with a unit clause forcing a chain.
+            vec![vec![Var(0)], vec![Not(0), Not(1)], vec![Var(1), Var(2)]],
+            // Unsatisfiable: 0 <=> 1 and 0 <=> !1.
+            vec![
+                vec![Not(0), Var(1)],
+                vec![Var(0), Not(1)],
+                vec![Var(0), Var(1)],
+                vec![Not(0), Not(1)],
+            ],
+            // Unsatisfiable odd cycle of exclusions (see test_check_sat).
+            vec![
+                vec![Var(0), Var(1)],
+                vec![Not(0), Not(1)],
+                vec![Var(1), Var(2)],
+                vec![Not(1), Not(2)],
+                vec![Var(2), Var(0)],
+                vec![Not(2), Not(0)],
+            ],
+            // Empty clause.
+            vec![vec![Var(0)], vec![]],
+        ];
+        let verdicts = [true, true, false, false, false];
+
+        for (formula, &verdict) in formulas.iter().zip(verdicts.iter()) {
+            assert!(is_2sat(formula));
+
+            let mut fast = vec![false; 4];
+            let mut general = vec![false; 4];
+            assert_eq!(solve_2sat(formula, &mut fast), verdict);
+            assert_eq!(
+                dpll(
+                    formula,
+                    &mut general,
+                    &mut Search::new(&mut DominantVariable)
+                ),
+                verdict
+            );
+
+            if verdict {
+                assert!(verify(formula, &fast));
+                assert!(verify(formula, &general));
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_strongly_connected_components() {
+        // 0 <-> 1 -> 2 <-> 3, 4 (isolated)
+        let graph = vec![vec![1], vec![0, 2], vec![3], vec![2], vec![]];
+        let components = find_strongly_connected_components(&graph);
+
+        assert_eq!(components[0], components[1]);
+        assert_eq!(components[2], components[3]);
+        assert_ne!(components[0], components[2]);
+        assert_ne!(components[4], components[0]);
+        assert_ne!(components[4], components[2]);
+
+        // Reverse topological order: {2, 3} is reachable from {0, 1}.
+        assert!(components[2] < components[0]);
+    }
+
+    #[test]
+    fn test_unit_propagate() {
+        let mut formula = vec![
+            vec![Var(1)],                 // (unit clause)
+            vec![Not(2)],                 // (unit clause)
+            vec![Var(1), Var(2)],         // => true
+            vec![Not(1), Var(2), Var(3)], // => 3 (unit clause)
+            vec![Var(0), Not(3), Var(4)], // => 0 | 4
+        ];
+        let mut vars = vec![false; 5];
+
+        unit_propagate(&mut formula, &mut vars);
+
+        assert!(formula == vec![vec![Var(0), Var(4)]]);
+        assert!(vars == vec![false, true, false, true, false]);
+    }
+
+    #[test]
+    fn test_unit_propagate_by_queue() {
+        let formulas = [
+            vec![
+                vec![Var(1)],
+                vec![Not(2)],
+                vec![Var(1), Var(2)],
+                vec![Not(1), Var(2), Var(3)],
+                vec![Var(0), Not(3), Var(4)],
+            ],
+            // Conflicting units.
+            vec![
+                vec![Var(0)],
+                vec![Not(0), Var(1)],
+                vec![Not(0), Not(1)],
+                vec![Var(2), Not(1), Var(3)],
+            ],
+        ];
+
+        for formula in formulas.iter() {
+            let mut scanned = formula.clone();
+            let mut scanned_vars = vec![false; 5];
+            while let Some(lit) = scanned
+                .iter()
+                .find(|clause| clause.len() == 1)
+                .map(|c| c[0])
+            {
+                let (var, truth) = (lit.variable(), matches!(lit, Var(_)));
+                scanned_vars[var] = truth;
+                simplify_by_scan(&mut scanned, var, truth);
+            }
+
+            let mut queued = formula.clone();
+            let mut queued_vars = vec![false; 5];
+            unit_propagate(&mut queued, &mut queued_vars);
+
+            assert_eq!(normalize(&queued), normalize(&scanned));
+            assert_eq!(queued_vars, scanned_vars);
+        }
+    }
+
+    #[test]
+    fn test_lazy_clauses() {
+        let formula = vec![
+            vec![Var(1)],
+            vec![Not(2)],
+            vec![Var(1), Var(2)],
+            vec![Not(1), Var(2), Var(3)],
+            vec![Var(0), Not(3), Var(4)],
+            vec![Not(4), Var(5), Not(0)],
+        ];
+        let original = formula.clone();
+
+        // Same units as the eager mode.
+        let mut eager_formula = formula.clone();
+        let mut eager_vars = vec![false; 6];
+        unit_propagate(&mut eager_formula, &mut eager_vars);
+
+        let mut lazy = LazyClauses::new(&formula, 6);
+        let mut lazy_vars = vec![false; 6];
+        propagate_units(&mut lazy, &mut lazy_vars);
+
+        assert_eq!(lazy_vars, eager_vars);
+        assert_eq!(lazy_vars, vec![false, true, false, true, false, false]);
+        assert_eq!(lazy.value(0), None);
+        assert_eq!(lazy.value(3), Some(true));
+        assert!(!lazy.has_conflict());
+
+        // The clauses are kept intact and have their status instead.
+        assert_eq!(lazy.status(0), ClauseStatus::Satisfied);
+        assert_eq!(lazy.status(2), ClauseStatus::Satisfied);
+        assert_eq!(lazy.status(3), ClauseStatus::Satisfied);
+        assert_eq!(lazy.status(4), ClauseStatus::Unresolved);
+        assert_eq!(lazy.status(5), ClauseStatus::Unresolved);
+        assert_eq!(formula, original);
+
+        lazy.assign(0, false);
+        assert_eq!(lazy.status(4), ClauseStatus::Unit(Var(4)));
+        assert_eq!(lazy.find_unit(), Some(Var(4)));
+        lazy.assign(4, false);
+        assert!(lazy.has_conflict());
+        assert_eq!(lazy.status(4), ClauseStatus::Falsified);
+        assert_eq!(lazy.find_unit(), None);
+    }
+
+    #[test]
+    fn test_flat_formula() {
+        let formula = vec![
+            vec![Var(0), Not(1)],
+            vec![],
+            vec![Var(2)],
+            vec![Not(0), Var(1)],
+        ];
+        let flat = FlatFormula::from(&formula);
+        assert_eq!(flat.len(), 4);
+        assert!(!flat.is_empty());
+        assert_eq!(flat.clause(0), &[Var(0), Not(1)]);
+        assert_eq!(flat.clause(1), &[]);
+        assert_eq!(flat.literals(), &[Var(0), Not(1), Var(2), Not(0), Var(1)]);
+        assert_eq!(flat.iter().collect::<Vec<_>>(), formula);
+        assert_eq!(flat.to_formula(), formula);
+
+        let empty = FlatFormula::from(&Formula::new());
+        assert!(empty.is_empty());
+        assert_eq!(empty, FlatFormula::default());
+
+        // The results of the solvers are the same on the clauses of the flat
+        // formula, and so are the statuses of the lazy storages.
+        let mut rng = crate::rng::Rng::new(11);
+        for _ in 0..50 {
+            let formula: Formula = random_3sat(&mut rng, 10, 40);
+            let flat = FlatFormula::from(&formula);
+            let restored = flat.to_formula();
+            assert_eq!(check_sat(&restored), check_sat(&formula));
+            assert_eq!(
+                crate::solver::Solver::new(&restored).solve(),
+                crate::solver::Solver::new(&formula).solve()
+            );
+
+            let n_vars = num_variables(&formula);
+            let mut nested = LazyClauses::new(&formula, n_vars);
+            let mut shared = LazyClauses::from_flat(&flat, n_vars);
+            nested.assign(0, true);
+            shared.assign(0, true);
+            let mut nested_vars = vec![false; n_vars];
+            let mut shared_vars = vec![false; n_vars];
+            propagate_units(&mut nested, &mut nested_vars);
+            propagate_units(&mut shared, &mut shared_vars);
+            assert_eq!(shared_vars, nested_vars);
+            for index in 0..formula.len() {
+                assert_eq!(shared.status(index), nested.status(index));
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_sat_with_mode() {
+        let mut formulas = vec![
+            dimacs::load(
+                &mut include_str!("../examples/negative_3.cnf").as_bytes(),
+                true,
+            )
+            .unwrap(),
+            vec![vec![Var(0), Var(1), Var(2)], vec![Not(0)], vec![]],
+        ];
+
+        // Random 3-SAT with duplicate literals and tautologies, both SAT and
+        // UNSAT.
+        let mut rng = crate::rng::Rng::new(7);
+        for _ in 0..100 {
+            let formula: Formula = random_3sat(&mut rng, 14, 60);
+            formulas.push(formula);
+        }
+
+        assert_eq!(SimplifyMode::default(), SimplifyMode::Eager);
+        let mut verdicts = [0, 0];
+        for formula in &formulas {
+            let n_vars = num_variables(formula);
+
+            // Same units from the formula itself.
+            let mut eager_formula = formula.clone();
+            let mut eager_vars = vec![false; n_vars];
+            unit_propagate(&mut eager_formula, &mut eager_vars);
+            let mut lazy_vars = vec![false; n_vars];
+            propagate_units(&mut LazyClauses::new(formula, n_vars), &mut lazy_vars);
+            assert_eq!(lazy_vars, eager_vars);
+
+            let eager = check_sat_with_mode(formula, &mut DominantVariable, SimplifyMode::Eager);
+            let lazy = check_sat_with_mode(formula, &mut DominantVariable, SimplifyMode::Lazy);
+            assert_eq!(eager, check_sat(formula));
+            assert_eq!(lazy.is_some(), eager.is_some());
+            if let Some(vars) = &lazy {
+                assert!(verify(formula, vars));
+            }
+            verdicts[eager.is_some() as usize] += 1;
+        }
+        assert!(verdicts[0] > 0 && verdicts[1] > 0);
+    }
+
+    #[test]
+    fn test_lazy_clauses_priorities() {
+        // Units 0 and 1 imply 2 and its negation, and unit 3 stays apart. The
+        // propagation goes on after the conflict.
+        let formula = vec![
+            vec![Var(0)],
+            vec![Var(1)],
+            vec![Not(0), Var(2)],
+            vec![Not(1), Not(2)],
+            vec![Var(3)],
+        ];
+
+        /// Propagates the units one by one, returning them in order.
+        fn propagation_order(mut lazy: LazyClauses) -> Vec<Literal> {
+            let mut order = Vec::new();
+            while let Some(lit) = lazy.find_unit() {
+                order.push(lit);
+                lazy.assign(lit.variable(), matches!(lit, Var(_)));
+            }
+            assert!(lazy.has_conflict());
+            order
+        }
+
+        let uniform = propagation_order(LazyClauses::new(&formula, 4));
+        assert_eq!(uniform, vec![Var(0), Var(2), Not(1), Var(3)]);
+
+        // The unit of clause 1 goes first and its consequence by clause 3
+        // next, and then clause 4 goes before clause 2.
+        let priorities = [0, 5, 0, 9, 1];
+        let lazy = LazyClauses::new(&formula, 4).with_priorities(&priorities);
+        assert_eq!(
+            propagation_order(lazy),
+            vec![Var(1), Not(2), Var(3), Not(0)]
+        );
+
+        // Priorities leave the verdicts of the search unchanged.
+        let mut rng = crate::rng::Rng::new(9);
+        for _ in 0..50 {
+            let formula: Formula = random_3sat(&mut rng, 12, 50);
+            let priorities: Vec<u32> = formula.iter().map(|_| rng.next_u64() as u32 % 4).collect();
+
+            let expected = check_sat(&formula);
+            let vars = check_sat_with_priorities(&formula, &mut DominantVariable, &priorities);
+            assert_eq!(vars.is_some(), expected.is_some());
+            if let Some(vars) = vars {
+                assert!(verify(&formula, &vars));
+            }
+        }
+    }
+
+    #[test]
+    fn test_lazy_clauses_conflict() {
+        let formula = vec![vec![Var(0)], vec![Not(0), Var(1)], vec![Not(1), Not(0)]];
+        let mut lazy = LazyClauses::new(&formula, 2);
+        let mut vars = vec![false; 2];
+        propagate_units(&mut lazy, &mut vars);
+        assert!(lazy.has_conflict());
+    }
+
+    #[test]
+    fn test_simplify() {
+        // Raw and negated literals are resolved differently.
+        {
+            let mut formula = vec![vec![Var(1), Var(2)], vec![Not(1), Var(3)]];
+            let mut occurrences = Occurrences::new(&formula, 4);
+            let mut units = VecDeque::new();
+            simplify(
+                &mut formula,
+                &mut occurrences,
+                &mut Formula::new(),
+                &mut units,
+                None,
+                1,
+                true,
+            );
+            assert!(formula == vec![vec![Var(3)]]);
+            assert_eq!(units, vec![Var(3)]);
+        }
+
+        // Falsey unit clause becomes an empty clause.
+        {
+            let mut formula = vec![vec![Var(1)], vec![Var(2)]];
+            let mut occurrences = Occurrences::new(&formula, 3);
+            simplify(
+                &mut formula,
+                &mut occurrences,
+                &mut Formula::new(),
+                &mut VecDeque::new(),
+                None,
+                1,
+                false,
+            );
+            assert!(formula == vec![vec![], vec![Var(2)]]);
+        }
+    }
+
+    /// Simplifies a formula by scanning every clause, for reference.
+    fn simplify_by_scan(formula: &mut Formula, var: usize, truth: bool) {
+        let truthy_lit = if truth { Var(var) } else { Not(var) };
+        let falsey_lit = if truth { Not(var) } else { Var(var) };
+
+        formula.retain(|clause| !clause.contains(&truthy_lit));
+        for clause in formula.iter_mut() {
+            clause.retain(|&lit| lit != falsey_lit);
+        }
+    }
+
+    /// Sorts clauses and literals to compare formulas as sets.
+    fn normalize(formula: &Formula) -> Vec<Vec<usize>> {
+        let mut clauses: Vec<Vec<usize>> = formula
+            .iter()
+            .map(|clause| {
+                let mut codes: Vec<usize> = clause.iter().map(|lit| lit.code()).collect();
+                codes.sort_unstable();
+                codes
+            })
+            .collect();
+        clauses.sort();
+        clauses
+    }
+
+    #[test]
+    fn test_simplify_occurrences() {
+        let original = vec![
+            vec![Var(0), Var(1), Not(2)],
+            vec![Not(0), Var(2)],
+            vec![Var(0), Var(0), Var(3)],
+            vec![Not(1), Not(3), Var(0)],
+            vec![Var(2), Not(2)],
+            vec![Not(0), Not(0)],
+            vec![Var(1), Var(3)],
+        ];
+        let assignments = [(0, true), (2, false), (3, true)];
+
+        let mut scanned = original.clone();
+        let mut indexed = original.clone();
+        let mut occurrences = Occurrences::new(&indexed, 4);
+        let mut origins: Vec<usize> = (0..original.len()).collect();
+
+        for &(var, truth) in assignments.iter() {
+            simplify_by_scan(&mut scanned, var, truth);
+            simplify(
+                &mut indexed,
+                &mut occurrences,
+                &mut Formula::new(),
+                &mut VecDeque::new(),
+                Some(&mut origins),
+                var,
+                truth,
+            );
+            assert_eq!(normalize(&indexed), normalize(&scanned));
+
+            // Each clause is what remains of its original clause.
+            assert_eq!(origins.len(), indexed.len());
+            for (clause, &origin) in indexed.iter().zip(origins.iter()) {
+                assert!(clause.iter().all(|lit| original[origin].contains(lit)));
+            }
+
+            // The maintained lists agree with lists built from scratch.
+            let rebuilt = Occurrences::new(&indexed, 4);
+            for (list, expect) in occurrences.lists.iter().zip(rebuilt.lists.iter()) {
+                let mut list = list.clone();
+                list.sort_unstable();
+                assert_eq!(&list, expect);
+            }
+        }
+    }
+
+    #[test]
+    fn test_propagation_leaves_no_false_literal() {
+        // Clauses moved into the place of removed ones, duplicate literals and
+        // tautologies, which the occurrence lists must follow. In the first
+        // formula, assigning 0 removes the last clause before its duplicate
+        // false literals are visited.
+        let mut formulas = vec![
+            vec![vec![Var(1), Var(2)], vec![Var(0), Not(0), Not(0)]],
+            vec![
+                vec![Var(0), Var(1)],
+                vec![Not(1), Var(2), Not(1)],
+                vec![Var(0), Var(0)],
+                vec![Not(2), Var(1), Var(3)],
+            ],
+            vec![
+                vec![Not(0), Var(1)],
+                vec![Not(1), Var(2)],
+                vec![Not(2), Not(0)],
+            ],
+        ];
+
+        // Random clauses of one to four literals over few variables, so that
+        // duplicates and tautologies are common.
+        let mut rng = crate::rng::Rng::new(13);
+        for _ in 0..200 {
+            let formula: Formula = (0..12)
+                .map(|_| {
+                    let len = 1 + (rng.next_u64() % 4) as usize;
+                    (0..len)
+                        .map(|_| {
+                            let var = (rng.next_u64() % 5) as usize;
+                            if rng.next_bool() {
+                                Var(var)
+                            } else {
+                                Not(var)
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            formulas.push(formula);
+        }
+
+        for original in &formulas {
+            let n_vars = num_variables(original);
+            for &truth in &[true, false] {
+                let mut formula = original.clone();
+                let mut vars = vec![false; n_vars];
+                let mut eager = EagerClauses::new(&mut formula, n_vars);
+                let mut lazy = LazyClauses::new(original, n_vars);
+                propagate_units(&mut eager, &mut vars);
+
+                for var in 0..n_vars {
+                    if !eager.assigned[var] {
+                        vars[var] = truth;
+                        eager.assign(var, truth);
+                        lazy.assign(var, truth);
+                        propagate_units(&mut eager, &mut vars);
+                    }
+
+                    // Every literal left is unassigned, so a clause with all
+                    // its literals false has become empty.
+                    for clause in eager.formula.iter() {
+                        assert!(clause.iter().all(|lit| !eager.assigned[lit.variable()]));
+                    }
+                    let empty = eager.formula.iter().any(|clause| clause.is_empty());
+
+                    // The same assignment falsifies a clause of the original.
+                    let mut values = vec![None; n_vars];
+                    for i in 0..n_vars {
+                        if eager.assigned[i] {
+                            values[i] = Some(vars[i]);
+                            if lazy.value(i).is_none() {
+                                lazy.assign(i, vars[i]);
+                            }
+                        }
+                    }
+                    let falsified = original.iter().any(|clause| {
+                        clause.iter().all(|lit| match *lit {
+                            Var(i) => values[i] == Some(false),
+                            Not(i) => values[i] == Some(true),
+                        })
+                    });
+                    assert_eq!(empty, falsified);
+                    assert_eq!(lazy.has_conflict(), falsified);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        // (x0 ∨ x1 ∨ ¬x2) and (¬x0 ∨ ¬x2 ∨ x3) resolve to (x1 ∨ ¬x2 ∨ x3).
+        let a = vec![Var(0), Var(1), Not(2)];
+        let b = vec![Not(2), Not(0), Var(3)];
+        assert_eq!(resolve(&a, &b, 0), Some(vec![Var(1), Not(2), Var(3)]));
+        assert_eq!(resolve(&b, &a, 0), Some(vec![Not(2), Var(3), Var(1)]));
+
+        // Unit clauses resolve to the empty clause.
+        assert_eq!(resolve(&vec![Var(1)], &vec![Not(1)], 1), Some(vec![]));
+
+        // Tautological resolvent: x1 and ¬x1 both remain.
+        let a = vec![Var(0), Var(1)];
+        let b = vec![Not(0), Not(1)];
+        assert_eq!(resolve(&a, &b, 0), None);
+
+        // Not clashing: the pivot has the same polarity or is missing.
+        let a = vec![Var(0), Var(1)];
+        let b = vec![Var(0), Var(2)];
+        assert_eq!(resolve(&a, &b, 0), None);
+        assert_eq!(resolve(&a, &b, 1), None);
+        assert_eq!(resolve(&a, &a, 3), None);
+    }
+
+    #[test]
+    fn test_check_sat_dp() {
+        assert!(check_sat_dp(&vec![]));
+        assert!(!check_sat_dp(&vec![vec![]]));
+        assert!(check_sat_dp(&vec![vec![Var(0), Not(0)]]));
+        assert!(!check_sat_dp(&vec![vec![Var(0), Var(0)], vec![Not(0)]]));
+
+        let formula = dimacs::load(
+            &mut include_str!("../examples/negative_3.cnf").as_bytes(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(check_sat_dp(&formula), check_sat(&formula).is_some());
+
+        // Random 3-SAT around the threshold ratio, both SAT and UNSAT.
+        let mut rng = crate::rng::Rng::new(3);
+        let mut verdicts = [0, 0];
+        for _ in 0..100 {
+            let formula: Formula = random_3sat(&mut rng, 12, 50);
+            let sat = check_sat(&formula).is_some();
+            assert_eq!(check_sat_dp(&formula), sat);
+            verdicts[sat as usize] += 1;
+        }
+        assert!(verdicts[0] > 0 && verdicts[1] > 0);
+
+        // Few variables with large indices are accepted.
+        let spread = |lit: &Literal| match *lit {
+            Var(i) => Var(10_000_000 + 1000 * i),
+            Not(i) => Not(10_000_000 + 1000 * i),
+        };
+        for _ in 0..20 {
+            let formula: Formula = random_3sat(&mut rng, 12, 50);
+            let spread: Formula = formula
+                .iter()
+                .map(|clause| clause.iter().map(spread).collect())
+                .collect();
+            assert_eq!(check_sat_dp(&spread), check_sat_dp(&formula));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "too many variables")]
+    fn test_check_sat_dp_too_many_variables() {
+        let formula: Formula = (0..=DP_MAX_VARIABLES).map(|i| vec![Var(i)]).collect();
+        check_sat_dp(&formula);
+    }
+
+    #[test]
+    fn test_is_trivially_sat() {
+        let formula = vec![
+            vec![Var(0), Var(1), Not(0)],
+            vec![Var(2), Not(2)],
+            vec![Not(1), Var(3), Var(1), Var(2)],
+        ];
+        assert!(is_trivially_sat(&formula));
+        assert!(!is_horn(&formula) && !is_2sat(&formula));
+        assert_eq!(check_sat(&formula), Some(vec![false; 4]));
+        assert!(is_trivially_sat(&vec![]));
+
+        let mut formula = formula;
+        formula.push(vec![Var(0), Var(1)]);
+        assert!(!is_trivially_sat(&formula));
+        assert!(!is_trivially_sat(&vec![vec![]]));
+    }
+
+    #[test]
+    fn test_check_sat_dominant_phase() {
+        // The dominant variable 0 occurs mostly negated. It is tried to be true
+        // first by default, and false first by `DominantPhase`.
+        let formula = vec![
+            vec![Not(0), Var(1), Var(2)],
+            vec![Not(0), Not(1), Var(2)],
+            vec![Not(0), Var(1), Not(2)],
+            vec![Var(0), Var(1), Var(3)],
+            vec![Not(0), Not(3), Var(2)],
         ];
-        assert!(find_dominant_variable(&formula, 3) == 1);
+        assert_eq!(DominantVariable.choose(&formula, 4), Var(0));
+        assert_eq!(DominantPhase.choose(&formula, 4), Not(0));
+
+        let vars = check_sat(&formula).unwrap();
+        assert!(verify(&formula, &vars));
+        assert!(vars[0]);
+
+        let vars = check_sat_with(&formula, &mut DominantPhase).unwrap();
+        assert!(verify(&formula, &vars));
+        assert!(!vars[0]);
+    }
+
+    #[test]
+    fn test_check_sat_literal_counts() {
+        /// Dominant variable recounting the formula at every decision.
+        struct Recounting;
+
+        impl Heuristic for Recounting {
+            fn choose(&mut self, formula: &Formula, n_vars: usize) -> Literal {
+                DominantVariable.choose(formula, n_vars)
+            }
+        }
+
+        let mut formulas = vec![dimacs::load(
+            &mut include_str!("../examples/negative_3.cnf").as_bytes(),
+            true,
+        )
+        .unwrap()];
+
+        // Random 3-SAT with duplicate literals and tautologies, both SAT and
+        // UNSAT.
+        let mut rng = crate::rng::Rng::new(5);
+        for _ in 0..100 {
+            let formula: Formula = random_3sat(&mut rng, 14, 60);
+            formulas.push(formula);
+        }
+
+        let flag = AtomicBool::new(false);
+        let mut verdicts = [0, 0];
+        for formula in &formulas {
+            let counted = check_sat_until_with_stats(formula, &mut DominantVariable, &flag);
+            let recounted = check_sat_until_with_stats(formula, &mut Recounting, &flag);
+            assert_eq!(counted, recounted);
+            verdicts[matches!(counted.0, Outcome::Sat(_)) as usize] += 1;
+        }
+        assert!(verdicts[0] > 0 && verdicts[1] > 0);
     }
 }
@@ -0,0 +1,455 @@
+use super::encode::Expr;
+use std::error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    Syntax,
+    UnsupportedCommand(String),
+    UnsupportedSort(String),
+    UnsupportedFunction(String),
+    UnsupportedOperator(String),
+    UndeclaredSymbol(String),
+    DuplicateSymbol(String),
+    IO(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax => write!(f, "syntax error"),
+            Error::UnsupportedCommand(name) => write!(f, "unsupported command '{}'", name),
+            Error::UnsupportedSort(name) => write!(f, "unsupported sort '{}'", name),
+            Error::UnsupportedFunction(name) => {
+                write!(f, "unsupported function '{}' with parameters", name)
+            }
+            Error::UnsupportedOperator(name) => write!(f, "unsupported operator '{}'", name),
+            Error::UndeclaredSymbol(name) => write!(f, "undeclared symbol '{}'", name),
+            Error::DuplicateSymbol(name) => write!(f, "symbol '{}' declared twice", name),
+            Error::IO(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::IO(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::IO(err)
+    }
+}
+
+/// Propositional problem read from an SMT-LIB script.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem {
+    /// Conjunction of the asserted terms.
+    pub expr: Expr,
+    /// Names of the declared constants. The i-th name is `Expr::Var(i)`.
+    pub names: Vec<String>,
+}
+
+/// Deepest nesting of s-expressions, and of the expressions built from the
+/// terms, accepted by `load`. The terms are parsed and encoded recursively, so
+/// this bounds the use of the stack.
+pub const MAX_DEPTH: usize = 256;
+
+/// Loads an SMT-LIB script using only the propositional fragment.
+///
+/// Constants are declared by `declare-const` or by nullary `declare-fun` of
+/// sort `Bool`, and the terms of `assert` are built from them, `true`, `false`
+/// and the operators `not`, `and`, `or`, `xor`, `=>` and `=`. As in SMT-LIB,
+/// `xor` of more than two operands associates to the left, `=>` to the right,
+/// and `=` is chainable: it holds if all the operands are equal. The commands
+/// `set-logic`, `set-info`, `set-option`, `check-sat`, `get-model` and `exit`
+/// are accepted and ignored. Anything else is rejected, and so is a script
+/// nested deeper than `MAX_DEPTH` with `Error::Syntax`.
+pub fn load(src: &mut dyn io::Read) -> Result<Problem, Error> {
+    let mut text = String::new();
+    src.read_to_string(&mut text)?;
+
+    let mut tokens = tokenize(&text)?.into_iter();
+    let mut names: Vec<String> = Vec::new();
+    let mut asserts = Vec::new();
+
+    while let Some(token) = tokens.next() {
+        let command = match parse_sexpr(token, &mut tokens, 0)? {
+            SExpr::List(items) => items,
+            SExpr::Atom(_) => return Err(Error::Syntax),
+        };
+        let (head, args) = match command.split_first() {
+            Some((SExpr::Atom(head), args)) => (head.as_str(), args),
+            _ => return Err(Error::Syntax),
+        };
+
+        match head {
+            "set-logic" | "set-info" | "set-option" | "check-sat" | "get-model" | "exit" => {}
+            "declare-const" | "declare-fun" => {
+                let (name, sort) = match (head, args) {
+                    ("declare-const", [SExpr::Atom(name), sort]) => (name, sort),
+                    ("declare-fun", [SExpr::Atom(name), SExpr::List(params), sort]) => {
+                        if !params.is_empty() {
+                            return Err(Error::UnsupportedFunction(name.clone()));
+                        }
+                        (name, sort)
+                    }
+                    _ => return Err(Error::Syntax),
+                };
+                match sort {
+                    SExpr::Atom(sort) if sort == "Bool" => {}
+                    SExpr::Atom(sort) => return Err(Error::UnsupportedSort(sort.clone())),
+                    SExpr::List(_) => return Err(Error::UnsupportedSort(format!("{}", sort))),
+                }
+                if names.contains(name) {
+                    return Err(Error::DuplicateSymbol(name.clone()));
+                }
+                names.push(name.clone());
+            }
+            "assert" => match args {
+                [term] => asserts.push(parse_term(term, &names)?.0),
+                _ => return Err(Error::Syntax),
+            },
+            _ => return Err(Error::UnsupportedCommand(head.to_string())),
+        }
+    }
+
+    Ok(Problem {
+        expr: Expr::And(asserts),
+        names,
+    })
+}
+
+enum Token {
+    Open,
+    Close,
+    Symbol(String),
+}
+
+#[derive(Debug)]
+enum SExpr {
+    Atom(String),
+    List(Vec<SExpr>),
+}
+
+impl fmt::Display for SExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SExpr::Atom(atom) => write!(f, "{}", atom),
+            SExpr::List(items) => {
+                write!(f, "(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Splits a script into parentheses and symbols, dropping comments. A symbol
+/// quoted in `|...|` is unquoted. Fails if the closing `|` is missing.
+fn tokenize(text: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            ';' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '|' => {
+                let mut symbol = String::new();
+                while let Some(c) = chars.next_if(|&c| c != '|') {
+                    symbol.push(c);
+                }
+                if chars.next().is_none() {
+                    return Err(Error::Syntax);
+                }
+                tokens.push(Token::Symbol(symbol));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut symbol = c.to_string();
+                while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && !"();|".contains(c)) {
+                    symbol.push(c);
+                }
+                tokens.push(Token::Symbol(symbol));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parses the s-expression starting with `token`, taking the rest of it from
+/// `tokens`. `depth` is the number of lists enclosing the s-expression.
+fn parse_sexpr(
+    token: Token,
+    tokens: &mut impl Iterator<Item = Token>,
+    depth: usize,
+) -> Result<SExpr, Error> {
+    match token {
+        Token::Symbol(symbol) => Ok(SExpr::Atom(symbol)),
+        Token::Close => Err(Error::Syntax),
+        Token::Open if depth >= MAX_DEPTH => Err(Error::Syntax),
+        Token::Open => {
+            let mut items = Vec::new();
+            loop {
+                match tokens.next() {
+                    Some(Token::Close) => return Ok(SExpr::List(items)),
+                    Some(token) => items.push(parse_sexpr(token, tokens, depth + 1)?),
+                    None => return Err(Error::Syntax),
+                }
+            }
+        }
+    }
+}
+
+/// Converts a term to an expression over the declared constants. Returns the
+/// expression and its depth, failing if the depth exceeds `MAX_DEPTH`.
+fn parse_term(term: &SExpr, names: &[String]) -> Result<(Expr, usize), Error> {
+    let (head, args) = match term {
+        SExpr::Atom(atom) => {
+            return match atom.as_str() {
+                "true" => Ok((Expr::And(Vec::new()), 1)),
+                "false" => Ok((Expr::Or(Vec::new()), 1)),
+                name => match names.iter().position(|n| n == name) {
+                    Some(i) => Ok((Expr::Var(i), 1)),
+                    None => Err(Error::UndeclaredSymbol(name.to_string())),
+                },
+            };
+        }
+        SExpr::List(items) => match items.split_first() {
+            Some((SExpr::Atom(head), args)) => (head.as_str(), args),
+            Some((head, _)) => return Err(Error::UnsupportedOperator(format!("{}", head))),
+            None => return Err(Error::Syntax),
+        },
+    };
+
+    let args = args
+        .iter()
+        .map(|arg| parse_term(arg, names))
+        .collect::<Result<Vec<(Expr, usize)>, Error>>()?;
+    let depth = args.iter().map(|&(_, depth)| depth).max().unwrap_or(0);
+    let args: Vec<Expr> = args.into_iter().map(|(expr, _)| expr).collect();
+
+    // The depth added by the operator.
+    let (expr, height) = match (head, args.len()) {
+        ("and", _) => (Expr::And(args), 1),
+        ("or", _) => (Expr::Or(args), 1),
+        ("not", 1) => (Expr::Not(Box::new(args.into_iter().next().unwrap())), 1),
+        // The chains are built shallow so that many operands do not nest as
+        // deep. Xor is associative, and a => (b => c) is a & b => c.
+        ("xor", n) if n >= 2 => {
+            let height = (usize::BITS - (n - 1).leading_zeros()) as usize;
+            (xor_all(args), height)
+        }
+        ("=>", 2) => {
+            let mut args = args.into_iter();
+            let a = args.next().unwrap();
+            let b = args.next().unwrap();
+            (Expr::Imply(Box::new(a), Box::new(b)), 1)
+        }
+        ("=>", n) if n > 2 => {
+            let mut args = args;
+            let last = args.pop().unwrap();
+            let premise = Expr::And(args);
+            (Expr::Imply(Box::new(premise), Box::new(last)), 2)
+        }
+        ("=", n) if n >= 2 => {
+            let equal = |a: &Expr, b: &Expr| {
+                Expr::Not(Box::new(Expr::Xor(
+                    Box::new(a.clone()),
+                    Box::new(b.clone()),
+                )))
+            };
+            let pairs = args.windows(2).map(|pair| equal(&pair[0], &pair[1]));
+            (Expr::And(pairs.collect()), 3)
+        }
+        ("not", _) | ("xor", _) | ("=>", _) | ("=", _) => return Err(Error::Syntax),
+        _ => return Err(Error::UnsupportedOperator(head.to_string())),
+    };
+
+    if depth + height > MAX_DEPTH {
+        return Err(Error::Syntax);
+    }
+    Ok((expr, depth + height))
+}
+
+/// Builds a balanced tree of `Expr::Xor` over non-empty `args`.
+fn xor_all(mut args: Vec<Expr>) -> Expr {
+    if args.len() == 1 {
+        return args.pop().unwrap();
+    }
+    let right = args.split_off(args.len() / 2);
+    Expr::Xor(Box::new(xor_all(args)), Box::new(xor_all(right)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encode;
+    use crate::sat;
+
+    #[test]
+    fn test_load() {
+        let source = "\
+            ; Either a or b, but not both, and b implies c.\n\
+            (set-logic QF_UF)\n\
+            (declare-const a Bool)\n\
+            (declare-fun b () Bool)\n\
+            (declare-const |c d| Bool)\n\
+            (assert (xor a b))\n\
+            (assert (=> b |c d|))\n\
+            (assert (or (not a) false))\n\
+            (check-sat)\n";
+        let problem = match load(&mut source.as_bytes()) {
+            Ok(problem) => problem,
+            Err(err) => panic!("unexpected: {}", err),
+        };
+        assert_eq!(problem.names, vec!["a", "b", "c d"]);
+
+        let (formula, n_vars) = encode::tseitin(&problem.expr);
+        let vars = sat::check_sat(&formula).unwrap();
+        assert!(problem.expr.eval(&vars[..n_vars].to_vec()));
+        assert_eq!(&vars[..n_vars], &[false, true, true]);
+
+        let source = "(declare-const a Bool) (assert (and a (not a)))";
+        let problem = load(&mut source.as_bytes()).unwrap();
+        let (formula, _) = encode::tseitin(&problem.expr);
+        assert_eq!(sat::check_sat(&formula), None);
+    }
+
+    #[test]
+    fn test_load_n_ary() {
+        let source = "\
+            (declare-const a Bool)\n\
+            (declare-const b Bool)\n\
+            (declare-const c Bool)\n\
+            (assert (xor a b c))\n\
+            (assert (=> a b c))\n\
+            (assert (= a b c))\n";
+        let problem = match load(&mut source.as_bytes()) {
+            Ok(problem) => problem,
+            Err(err) => panic!("unexpected: {}", err),
+        };
+
+        // Each assertion against its definition in SMT-LIB.
+        for bits in 0..8 {
+            let vars: Vec<bool> = (0..3).map(|i| bits >> i & 1 == 1).collect();
+            let (a, b, c) = (vars[0], vars[1], vars[2]);
+            let expect = [(a ^ b) ^ c, !a || (!b || c), a == b && b == c];
+            match &problem.expr {
+                Expr::And(asserts) => {
+                    for (assert, &expect) in asserts.iter().zip(expect.iter()) {
+                        assert_eq!(assert.eval(&vars), expect);
+                    }
+                }
+                expr => panic!("unexpected: {:?}", expr),
+            }
+        }
+    }
+
+    #[test]
+    fn test_load_deep() {
+        let nested = |depth: usize| {
+            let mut source = String::from("(declare-const a Bool) (assert ");
+            source += &"(not ".repeat(depth);
+            source += "a";
+            source += &")".repeat(depth + 1);
+            source
+        };
+
+        // The assert command takes one level.
+        let problem = load(&mut nested(MAX_DEPTH - 1).as_bytes()).unwrap();
+        let (formula, n_vars) = encode::tseitin(&problem.expr);
+        let vars = sat::check_sat(&formula).unwrap();
+        assert_eq!(&vars[..n_vars], &[(MAX_DEPTH - 1).is_multiple_of(2)]);
+
+        for &depth in &[MAX_DEPTH, 200_000] {
+            match load(&mut nested(depth).as_bytes()) {
+                Err(Error::Syntax) => {}
+                result => panic!("unexpected: {:?}", result),
+            }
+        }
+
+        // Nor a term within the limit that makes a deeper expression, here
+        // with the xor of 2^10 operands taking 11 levels.
+        let operands = "a ".repeat(1 << 10);
+        let mut source = String::from("(declare-const a Bool) (assert ");
+        source += &"(not ".repeat(MAX_DEPTH - 5);
+        source += &format!("(xor {})", operands);
+        source += &")".repeat(MAX_DEPTH - 5 + 1);
+        match load(&mut source.as_bytes()) {
+            Err(Error::Syntax) => {}
+            result => panic!("unexpected: {:?}", result),
+        }
+
+        // Many operands do not nest deep.
+        let n = 2_000;
+        let mut source = String::new();
+        for i in 0..n {
+            source += &format!("(declare-const a{} Bool)\n", i);
+        }
+        let operands: Vec<String> = (0..n).map(|i| format!("a{}", i)).collect();
+        source += &format!("(assert (xor {}))\n", operands.join(" "));
+        source += &format!("(assert (=> {}))\n", operands.join(" "));
+        let problem = load(&mut source.as_bytes()).unwrap();
+
+        let mut vars = vec![false; n];
+        vars[n - 1] = true;
+        assert!(problem.expr.eval(&vars));
+        vars[0] = true;
+        assert!(!problem.expr.eval(&vars));
+        let (formula, n_vars) = encode::tseitin(&problem.expr);
+        assert_eq!(n_vars, n);
+        assert!(formula.len() > 2 * n);
+    }
+
+    #[test]
+    fn test_load_unsupported() {
+        let errors = [
+            ("(declare-const x Int)", "unsupported sort 'Int'"),
+            (
+                "(declare-fun f (Bool) Bool)",
+                "unsupported function 'f' with parameters",
+            ),
+            (
+                "(declare-const x (_ BitVec 8))",
+                "unsupported sort '(_ BitVec 8)'",
+            ),
+            (
+                "(declare-const x Bool) (assert (ite x x x))",
+                "unsupported operator 'ite'",
+            ),
+            ("(push 1)", "unsupported command 'push'"),
+            ("(assert y)", "undeclared symbol 'y'"),
+            (
+                "(declare-const x Bool) (declare-const x Bool)",
+                "symbol 'x' declared twice",
+            ),
+            ("(declare-const x Bool) (assert (not x x))", "syntax error"),
+            ("(declare-const x Bool) (assert (=> x))", "syntax error"),
+            ("(declare-const x Bool) (assert (= x))", "syntax error"),
+            ("(declare-const |x Bool)", "syntax error"),
+            ("(assert true", "syntax error"),
+            ("assert", "syntax error"),
+        ];
+        for (source, message) in errors.iter() {
+            match load(&mut source.as_bytes()) {
+                Ok(_) => panic!("unexpected success: {}", source),
+                Err(err) => assert_eq!(format!("{}", err), *message),
+            }
+        }
+    }
+}
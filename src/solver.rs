@@ -0,0 +1,3027 @@
+use super::bits::BitAssignment;
+use super::rng::Rng;
+use super::sat::{self, Assignment, Clause, Formula, Literal, Not, Var};
+#[cfg(feature = "search-tree")]
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "search-tree")]
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::{sync::mpsc, thread};
+
+/// Logs a search event through the `log` facade with the feature `log`, and
+/// expands to nothing without it.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::$level!($($arg)+);
+    };
+}
+
+/// Adds a node to the search tree of a solver with the feature `search-tree`,
+/// and expands to nothing without it.
+macro_rules! record {
+    ($solver:expr, $node:expr) => {
+        #[cfg(feature = "search-tree")]
+        $solver.record_node($node);
+    };
+}
+
+/// Assignment trail of a search.
+///
+/// The trail records assigned literals in chronological order. Each assigned
+/// variable carries its decision level and the reason of the assignment: the
+/// index of the clause that forced it by unit propagation, or None if the
+/// variable was assigned by a decision.
+pub struct Trail {
+    literals: Vec<Literal>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    level_starts: Vec<usize>,
+}
+
+impl Trail {
+    /// Creates an empty trail for `n_vars` variables.
+    pub fn new(n_vars: usize) -> Trail {
+        Trail {
+            literals: Vec::new(),
+            values: vec![None; n_vars],
+            levels: vec![0; n_vars],
+            reasons: vec![None; n_vars],
+            level_starts: Vec::new(),
+        }
+    }
+
+    /// Empties the trail and resizes it for `n_vars` variables, reusing the
+    /// buffers.
+    fn reset(&mut self, n_vars: usize) {
+        self.literals.clear();
+        self.values.clear();
+        self.values.resize(n_vars, None);
+        self.levels.clear();
+        self.levels.resize(n_vars, 0);
+        self.reasons.clear();
+        self.reasons.resize(n_vars, None);
+        self.level_starts.clear();
+    }
+
+    /// Returns the current decision level.
+    pub fn decision_level(&self) -> usize {
+        self.level_starts.len()
+    }
+
+    /// Opens a new decision level and makes literal `lit` true.
+    pub fn decide(&mut self, lit: Literal) {
+        self.new_decision_level();
+        self.assign(lit, None);
+    }
+
+    /// Opens a new decision level without assigning anything.
+    pub fn new_decision_level(&mut self) {
+        self.level_starts.push(self.literals.len());
+    }
+
+    /// Makes literal `lit` true at the current decision level. `reason` is the
+    /// index of the clause that forced the assignment.
+    pub fn assign(&mut self, lit: Literal, reason: Option<usize>) {
+        let var = lit.variable();
+        self.values[var] = Some(matches!(lit, Var(_)));
+        self.levels[var] = self.decision_level();
+        self.reasons[var] = reason;
+        self.literals.push(lit);
+    }
+
+    /// Undoes all assignments made above decision level `level`.
+    pub fn backtrack(&mut self, level: usize) {
+        if level >= self.decision_level() {
+            return;
+        }
+        let start = self.level_starts[level];
+        for lit in self.literals.drain(start..) {
+            self.values[lit.variable()] = None;
+        }
+        self.level_starts.truncate(level);
+    }
+
+    /// Returns the truth value of literal `lit`, or None if its variable is not
+    /// assigned.
+    pub fn value(&self, lit: Literal) -> Option<bool> {
+        self.values[lit.variable()].map(|truth| truth == matches!(lit, Var(_)))
+    }
+
+    /// Returns the decision level at which variable `var` is assigned.
+    pub fn level(&self, var: usize) -> usize {
+        self.levels[var]
+    }
+
+    /// Returns the index of the clause that forced variable `var`.
+    pub fn reason(&self, var: usize) -> Option<usize> {
+        self.reasons[var]
+    }
+
+    /// Returns the assigned literals in chronological order.
+    pub fn literals(&self) -> &[Literal] {
+        &self.literals
+    }
+}
+
+/// Removes redundant literals from a learned clause.
+///
+/// # Minimization
+///
+/// Every literal in a learned clause is false under the trail. A literal x is
+/// redundant if the reason clause of its variable consists of ¬x and literals
+/// that are already in the learned clause:
+///
+/// > learned = C ∨ x ,  reason = ¬x ∨ D ,  D ⊆ C .
+///
+/// Resolving the two clauses on x gives C ∨ D = C, so x can be dropped while
+/// the clause remains entailed by the formula (self-subsuming resolution). The
+/// check is applied recursively: a literal in D need not be in the learned
+/// clause if it is redundant by itself, or if it is assigned at level zero.
+/// Decisions have no reason and are never redundant.
+///
+/// `clauses` is the clause database the reasons in `trail` refer to.
+pub fn minimize_learned(clause: &mut Clause, trail: &Trail, clauses: &[Clause]) {
+    let n_vars = trail.values.len();
+    let mut present = vec![false; n_vars];
+    for lit in clause.iter() {
+        present[lit.variable()] = true;
+    }
+
+    let mut implied = vec![None; n_vars];
+    clause.retain(|lit| !is_implied(lit.variable(), trail, clauses, &present, &mut implied));
+}
+
+/// Checks if the assignment of variable `var` is implied by the assignments of
+/// the present variables and level-zero assignments.
+///
+/// The reasons are searched depth-first with an explicit stack, since an
+/// implication chain can be far deeper than the call stack allows. Each entry
+/// is a variable and the position in its reason of the next literal to check.
+fn is_implied(
+    var: usize,
+    trail: &Trail,
+    clauses: &[Clause],
+    present: &[bool],
+    implied: &mut [Option<bool>],
+) -> bool {
+    let mut stack = vec![(var, 0)];
+
+    while let Some(&(top, start)) = stack.last() {
+        if implied[top].is_some() {
+            stack.pop();
+            continue;
+        }
+
+        let reason = match (trail.values[top], trail.reason(top)) {
+            (Some(_), Some(reason)) => &clauses[reason],
+            _ => {
+                implied[top] = Some(false);
+                stack.pop();
+                continue;
+            }
+        };
+
+        // None while the literal at the position is yet to be checked.
+        let mut result = Some(true);
+        for (pos, lit) in reason.iter().enumerate().skip(start) {
+            let other = lit.variable();
+            if other == top || present[other] || trail.level(other) == 0 {
+                continue;
+            }
+            match implied[other] {
+                Some(true) => {}
+                Some(false) => {
+                    result = Some(false);
+                    break;
+                }
+                None => {
+                    // Come back to this literal once `other` is decided.
+                    stack.last_mut().unwrap().1 = pos;
+                    stack.push((other, 0));
+                    result = None;
+                    break;
+                }
+            }
+        }
+
+        if let Some(result) = result {
+            implied[top] = Some(result);
+            stack.pop();
+        }
+    }
+
+    implied[var] == Some(true)
+}
+
+/// Computes the literal block distance (LBD) of a clause: the number of
+/// distinct decision levels among the variables of the clause. Every literal
+/// must be assigned in `trail`.
+///
+/// A learned clause with a low LBD connects few decision levels and tends to
+/// propagate again soon, so it is worth keeping.
+pub fn lbd(clause: &[Literal], trail: &Trail) -> usize {
+    let mut levels: Vec<usize> = clause
+        .iter()
+        .map(|lit| trail.level(lit.variable()))
+        .collect();
+    levels.sort_unstable();
+    levels.dedup();
+    levels.len()
+}
+
+/// Search statistics of a solver.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// Number of decisions made.
+    pub decisions: u64,
+    /// Number of assignments propagated.
+    pub propagations: u64,
+    /// Number of conflicts found.
+    pub conflicts: u64,
+    /// Number of learned clauses deleted by database reduction.
+    pub deleted: u64,
+    /// Number of clauses learned, including units.
+    pub learned: u64,
+    /// Sum of the LBD of the learned clauses.
+    pub lbd_sum: u64,
+}
+
+impl Stats {
+    /// Returns the mean LBD of the learned clauses, or zero if no clause has
+    /// been learned.
+    pub fn average_lbd(&self) -> f64 {
+        if self.learned == 0 {
+            return 0.0;
+        }
+        self.lbd_sum as f64 / self.learned as f64
+    }
+
+    /// Checks if the search made no decision, i.e., unit propagation alone
+    /// determined the result. This also holds if the saved phases were a model
+    /// from the start, e.g., after a warm start.
+    pub fn solved_by_propagation_only(&self) -> bool {
+        self.decisions == 0
+    }
+}
+
+/// Truth value given to a variable when it is decided for the first time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Polarity {
+    True,
+    False,
+    /// Chooses true or false for each variable using the seed of the solver.
+    Random,
+}
+
+/// Variables whose activities are bumped at a conflict.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BumpStrategy {
+    /// The variables of all the clauses resolved by the conflict analysis,
+    /// i.e., the falsified clause and the reasons resolved on. This is the
+    /// default.
+    Resolved,
+    /// Only the variables of the learned clause, which is the cheapest and
+    /// focuses on the variables that the next propagation depends on.
+    Learned,
+    /// The resolved variables and, further, every variable from which a
+    /// literal of the learned clause is implied through the reasons, up to the
+    /// decisions. This is the most thorough and the most expensive.
+    ReasonClosure,
+}
+
+/// Result of a search that may be interrupted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// The formula is satisfiable with the assignment.
+    Sat(Assignment),
+    /// The formula is unsatisfiable.
+    Unsat,
+    /// The search was interrupted before the satisfiability was determined.
+    Unknown,
+}
+
+/// Event of the search reported by `Solver::solve_steps`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// The literal was decided, opening a new decision level.
+    Decide(Literal),
+    /// The literals were assigned by unit propagation, in order.
+    Propagate(Vec<Literal>),
+    /// A conflict was found and the search backtracked to the level. The
+    /// literal asserted by the learned clause, or the flipped decision if not
+    /// learning, is assigned.
+    Backtrack(usize),
+    /// The search ended.
+    Finish(Outcome),
+}
+
+/// State of the search after an event, as reported by `Solver::solve_steps`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartialState {
+    /// Value of each variable, None if unassigned.
+    pub values: Vec<Option<bool>>,
+    /// The event just taken.
+    pub action: Action,
+}
+
+/// Iterator over the states of a search. See `Solver::solve_steps`.
+pub struct Steps<'a> {
+    solver: &'a mut Solver,
+    started: bool,
+    done: bool,
+}
+
+impl Iterator for Steps<'_> {
+    type Item = PartialState;
+
+    fn next(&mut self) -> Option<PartialState> {
+        if self.done {
+            return None;
+        }
+
+        let outcome = if !self.started && self.solver.inconsistent {
+            Outcome::Unsat
+        } else {
+            if !self.started {
+                self.solver.backtrack(0);
+                #[cfg(feature = "search-tree")]
+                self.solver.clear_tree();
+                self.started = true;
+            }
+            match self.solver.step(&[], &mut |_| {}) {
+                Step::Event(action) => {
+                    return Some(PartialState {
+                        values: self.solver.trail.values.clone(),
+                        action,
+                    });
+                }
+                Step::Done(outcome) => outcome,
+            }
+        };
+
+        self.done = true;
+        let values = match &outcome {
+            Outcome::Sat(vars) => vars.iter().map(|&truth| Some(truth)).collect(),
+            _ => self.solver.trail.values.clone(),
+        };
+        Some(PartialState {
+            values,
+            action: Action::Finish(outcome),
+        })
+    }
+}
+
+/// Result of `Solver::step`.
+enum Step {
+    Event(Action),
+    Done(Outcome),
+}
+
+/// Implication graph of the assignments made by a solver.
+///
+/// Nodes are the literals made true, in chronological order. A literal forced
+/// by unit propagation has an incoming edge from the negation of each other
+/// literal of its reason clause, i.e., from the true literals that made the
+/// clause a unit. Decisions have no incoming edge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImplicationGraph {
+    pub nodes: Vec<ImplicationNode>,
+    pub edges: Vec<ImplicationEdge>,
+    /// Literals of the clause falsified by the assignments, if any.
+    pub conflict: Option<Clause>,
+}
+
+/// Literal assigned in an implication graph.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImplicationNode {
+    pub literal: Literal,
+    pub level: usize,
+    /// Literals of the clause that forced the literal, or None for a decision.
+    pub reason: Option<Clause>,
+}
+
+/// Edge of an implication graph: literal `from` is one of the causes forcing
+/// literal `to`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImplicationEdge {
+    pub from: Literal,
+    pub to: Literal,
+}
+
+/// Assignments that led a solver to a model, in the order they were made.
+/// Replaying the entries from an empty assignment reconstructs the model:
+/// each decision is a free choice, and each propagated literal is the only
+/// literal of its reason clause not yet false.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace {
+    pub entries: Vec<TraceEntry>,
+}
+
+/// Assignment recorded in a trace.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEntry {
+    pub literal: Literal,
+    pub level: usize,
+    /// Literals of the clause that forced the literal, or None for a decision.
+    /// The clause is copied since a learned clause may be deleted later. A
+    /// learned unit clause is the reason of its literal at level zero.
+    pub reason: Option<Clause>,
+}
+
+/// Resolution proof of the unsatisfiability of a formula, checked by
+/// `verify_unsat`.
+///
+/// Clauses are numbered from the clauses of the formula, in order, followed by
+/// the resolvents of the steps: the resolvent of the k-th step is numbered
+/// n + k for a formula of n clauses. Each step refers to clauses numbered
+/// before it. The proof is complete if the empty clause is among the clauses.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Proof {
+    pub steps: Vec<Resolution>,
+}
+
+/// Step of a resolution proof: resolves clauses `left` and `right` on the
+/// variable `pivot`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Resolution {
+    pub left: usize,
+    pub right: usize,
+    pub pivot: usize,
+}
+
+/// Checks a resolution proof that a formula is unsatisfiable. Every step must
+/// resolve two clauses that clash on the pivot into a non-tautological
+/// resolvent, and the empty clause must be in the formula or derived.
+pub fn verify_unsat(formula: &Formula, proof: &Proof) -> bool {
+    let mut clauses = formula.clone();
+
+    for step in &proof.steps {
+        if step.left >= clauses.len() || step.right >= clauses.len() {
+            return false;
+        }
+        match sat::resolve(&clauses[step.left], &clauses[step.right], step.pivot) {
+            Some(resolvent) => clauses.push(resolvent),
+            None => return false,
+        }
+    }
+
+    clauses.iter().any(|clause| clause.is_empty())
+}
+
+/// Metadata associated to each clause in the clause database of a solver.
+#[derive(Clone, Debug)]
+struct ClauseMeta {
+    learned: bool,
+    activity: f64,
+    /// LBD of a learned clause at the time it was learned. Zero for problem
+    /// clauses.
+    lbd: usize,
+    /// Number of the clause in the proof: the index in the formula for a
+    /// problem clause, or the resolvent deriving a learned clause.
+    origin: usize,
+}
+
+/// Node of a search tree recorded by a solver for `Solver::search_tree_dot`.
+#[cfg(feature = "search-tree")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TreeNode {
+    Root,
+    Decision(Literal),
+    Conflict,
+    Sat,
+}
+
+/// Search tree recorded by a solver. Each node has the index of its parent,
+/// and `path` has the decision nodes of the current assignment, one per
+/// decision level, starting with the root at level zero.
+#[cfg(feature = "search-tree")]
+#[derive(Clone, Debug)]
+struct SearchTree {
+    nodes: Vec<(usize, TreeNode)>,
+    path: Vec<usize>,
+}
+
+#[cfg(feature = "search-tree")]
+impl SearchTree {
+    fn new() -> SearchTree {
+        SearchTree {
+            nodes: vec![(0, TreeNode::Root)],
+            path: vec![0],
+        }
+    }
+
+    /// Adds a node below the decision of the given level. A decision node
+    /// replaces the path beyond that level.
+    fn add(&mut self, level: usize, node: TreeNode) {
+        let index = self.nodes.len();
+        self.nodes.push((self.path[level], node));
+        if let TreeNode::Decision(_) = node {
+            self.path.truncate(level + 1);
+            self.path.push(index);
+        }
+    }
+}
+
+/// Resolution steps recorded by a solver for `Solver::unsat_proof`.
+#[derive(Clone, Debug)]
+struct ProofLog {
+    n_formula: usize,
+    steps: Vec<Resolution>,
+    /// Number of the empty clause once derived.
+    empty: Option<usize>,
+}
+
+/// SAT solver based on conflict-driven clause learning (CDCL).
+///
+/// # Search
+///
+/// The solver assigns variables one by one (decisions) and propagates unit
+/// clauses using two watched literals per clause. When a clause becomes false,
+/// the conflict is analyzed to learn a new clause that rules out the cause of
+/// the conflict, and the search backjumps to the decision level at which the
+/// learned clause becomes a unit.
+///
+/// # Clause database reduction
+///
+/// Each clause has an activity score that is bumped whenever the clause takes
+/// part in a conflict analysis and decays over time. A learned clause is also
+/// tagged with its LBD when it is learned. Learned clauses would otherwise
+/// accumulate unboundedly, so half of the learned clauses is deleted when their
+/// number reaches a limit: those with the highest LBD, and the least active
+/// among equal LBDs. Problem clauses and clauses that are the reason of a
+/// current assignment are never deleted.
+///
+/// # Phase saving
+///
+/// A decided variable is assigned the value it had when it was last unassigned
+/// by backtracking, or the default polarity if it has never been assigned. The
+/// values of unconstrained variables in a model follow the default polarity.
+///
+/// # Learning
+///
+/// Clause learning can be turned off to compare with plain DPLL. A conflict
+/// then flips the most recent decision not flipped yet, undoing the levels
+/// above it (chronological backtracking), and the formula is unsatisfiable if
+/// every decision has been flipped. No clause is learned and the variable
+/// activities are not bumped, so variables are decided in index order.
+pub struct Solver {
+    clauses: Vec<Clause>,
+    meta: Vec<ClauseMeta>,
+    watches: Vec<Vec<usize>>,
+    trail: Trail,
+    propagated: usize,
+    var_activity: Vec<f64>,
+    var_increment: f64,
+    phases: BitAssignment,
+    /// Variables decided first, in this order, while unassigned.
+    decision_order: Vec<usize>,
+    polarity: Polarity,
+    bump: BumpStrategy,
+    seed: u64,
+    clause_increment: f64,
+    max_learned: Option<usize>,
+    learned_limit: f64,
+    n_learned: usize,
+    inconsistent: bool,
+    learning: bool,
+    /// Whether the decision of each level above zero has been flipped by
+    /// chronological backtracking. Assumption levels count as flipped.
+    flipped: Vec<bool>,
+    conflict: Option<usize>,
+    /// Trace of the model found last, recorded only if requested.
+    trace: Option<Trace>,
+    proof: Option<ProofLog>,
+    /// Number of clauses passed to `add_clause`, including dropped ones.
+    n_added: usize,
+    /// Clause of the formula found false while adding the clauses.
+    falsified: Option<usize>,
+    interrupt: Option<Arc<AtomicBool>>,
+    /// Search tree of the last search, recorded only if requested.
+    #[cfg(feature = "search-tree")]
+    tree: Option<SearchTree>,
+    stats: Stats,
+}
+
+const VAR_DECAY: f64 = 0.95;
+const CLAUSE_DECAY: f64 = 0.999;
+const LEARNED_GROWTH: f64 = 1.1;
+
+/// Number of conflicts between calls to the callback of `solve_with_callback`.
+pub const PROGRESS_INTERVAL: u64 = 100;
+
+impl Solver {
+    /// Creates a solver for a CNF formula.
+    pub fn new(formula: &Formula) -> Solver {
+        let mut solver = Solver {
+            clauses: Vec::new(),
+            meta: Vec::new(),
+            watches: Vec::new(),
+            trail: Trail::new(0),
+            propagated: 0,
+            var_activity: Vec::new(),
+            var_increment: 1.0,
+            phases: BitAssignment::new(0),
+            decision_order: Vec::new(),
+            polarity: Polarity::False,
+            bump: BumpStrategy::Resolved,
+            seed: 0,
+            clause_increment: 1.0,
+            max_learned: None,
+            learned_limit: 0.0,
+            n_learned: 0,
+            inconsistent: false,
+            learning: true,
+            flipped: Vec::new(),
+            conflict: None,
+            trace: None,
+            proof: None,
+            n_added: 0,
+            falsified: None,
+            interrupt: None,
+            #[cfg(feature = "search-tree")]
+            tree: None,
+            stats: Stats::default(),
+        };
+        solver.load(formula);
+        solver
+    }
+
+    /// Replaces the formula with a new one, reusing the allocations of the
+    /// solver. Everything learned about the previous formula is discarded,
+    /// including the learned clauses, activities, saved phases and stats, so
+    /// the solver behaves as a new one for `formula` with the same settings:
+    /// polarity, seed, decision order, learning, proof recording, limit of
+    /// learned clauses and interrupt flag.
+    pub fn reset(&mut self, formula: Formula) {
+        self.load(&formula);
+    }
+
+    /// Loads a formula into the solver, clearing the search state.
+    fn load(&mut self, formula: &Formula) {
+        let n_vars = sat::num_variables(formula);
+
+        self.clauses.clear();
+        self.meta.clear();
+        for list in &mut self.watches {
+            list.clear();
+        }
+        self.watches.resize(2 * n_vars, Vec::new());
+        self.trail.reset(n_vars);
+        self.propagated = 0;
+        self.var_activity.clear();
+        self.var_activity.resize(n_vars, 0.0);
+        self.var_increment = 1.0;
+        self.phases.reset(n_vars);
+        self.reset_phases();
+        self.clause_increment = 1.0;
+        self.n_learned = 0;
+        self.inconsistent = false;
+        self.flipped.clear();
+        self.conflict = None;
+        self.trace = None;
+        self.n_added = 0;
+        self.falsified = None;
+        #[cfg(feature = "search-tree")]
+        self.clear_tree();
+        self.stats = Stats::default();
+
+        // The clauses of the formula are not recorded in a proof.
+        let proof = self.proof.take();
+        for clause in formula {
+            self.add_clause(clause);
+        }
+        if proof.is_some() {
+            self.start_proof();
+        }
+
+        self.learned_limit = match self.max_learned {
+            Some(max_learned) => max_learned as f64,
+            None => (self.clauses.len() as f64 / 3.0).max(100.0),
+        };
+    }
+
+    /// Sets the maximum number of learned clauses kept in the database. By
+    /// default, the limit starts at a third of the number of problem clauses
+    /// and grows after every reduction.
+    pub fn with_max_learned(mut self, max_learned: usize) -> Solver {
+        self.max_learned = Some(max_learned);
+        self.learned_limit = max_learned as f64;
+        self
+    }
+
+    /// Sets the default polarity of decisions. The default is false.
+    pub fn with_default_polarity(mut self, polarity: Polarity) -> Solver {
+        self.polarity = polarity;
+        self.reset_phases();
+        self
+    }
+
+    /// Sets the seed for random choices made by the solver.
+    pub fn with_seed(mut self, seed: u64) -> Solver {
+        self.seed = seed;
+        self.reset_phases();
+        self
+    }
+
+    /// Fixes the order of decisions: the first unassigned variable in `order`
+    /// is decided next, and the activities pick the variables not listed.
+    /// Variables out of the formula are ignored. Together with the default
+    /// polarity, this determines the search tree, e.g., for teaching.
+    pub fn with_decision_order(mut self, order: Vec<usize>) -> Solver {
+        self.decision_order = order;
+        self
+    }
+
+    /// Sets the variables bumped at a conflict. The default is
+    /// `BumpStrategy::Resolved`.
+    pub fn with_bump_strategy(mut self, strategy: BumpStrategy) -> Solver {
+        self.bump = strategy;
+        self
+    }
+
+    /// Enables or disables clause learning. Learning is enabled by default.
+    pub fn with_learning(mut self, enabled: bool) -> Solver {
+        self.learning = enabled;
+        self
+    }
+
+    /// Records the resolutions deriving the learned clauses so that a search
+    /// finding the formula unsatisfiable can produce a proof. See
+    /// `unsat_proof`. Recording takes time and memory for every conflict, and
+    /// must be enabled before solving.
+    pub fn with_proof(mut self) -> Solver {
+        self.start_proof();
+        self
+    }
+
+    /// Starts recording a proof for the clauses added so far.
+    fn start_proof(&mut self) {
+        self.proof = Some(ProofLog {
+            n_formula: self.n_added,
+            steps: Vec::new(),
+            empty: None,
+        });
+
+        if let Some(index) = self.falsified {
+            let clause = self.clauses[index].clone();
+            let origin = self.meta[index].origin;
+            self.refute(origin, &clause);
+        }
+    }
+
+    /// Records the search tree of each search for `search_tree_dot`. Every
+    /// decision and every leaf is kept until the next search, so the tree of
+    /// a hard formula takes a lot of memory.
+    #[cfg(feature = "search-tree")]
+    pub fn with_search_tree(mut self) -> Solver {
+        self.tree = Some(SearchTree::new());
+        self
+    }
+
+    /// Sets a flag that interrupts the search when it becomes true. The flag
+    /// is checked before each decision, and an interrupted search returns
+    /// `Outcome::Unknown`. The flag is not cleared by the solver.
+    pub fn with_interrupt(mut self, flag: Arc<AtomicBool>) -> Solver {
+        self.interrupt = Some(flag);
+        self
+    }
+
+    /// Seeds the saved phases with a previous assignment so that the search
+    /// tries those values first. Variables not covered by `previous` get the
+    /// default polarity.
+    pub fn warm_start(&mut self, previous: &Assignment) {
+        self.reset_phases();
+        for (var, &truth) in previous.iter().enumerate().take(self.phases.len()) {
+            self.phases.set(var, truth);
+        }
+    }
+
+    /// Returns an iterator over the problem clauses, excluding learned ones.
+    ///
+    /// Duplicate literals and tautological clauses are dropped when clauses are
+    /// added, and the search may reorder the literals within a clause.
+    pub fn clauses(&self) -> impl Iterator<Item = &[Literal]> + '_ {
+        self.clauses
+            .iter()
+            .zip(&self.meta)
+            .filter(|(_, meta)| !meta.learned)
+            .map(|(clause, _)| clause.as_slice())
+    }
+
+    /// Returns the literals assigned at decision level zero in chronological
+    /// order: the unit clauses and the literals they force by propagation,
+    /// including those of the unit clauses learned by the search.
+    ///
+    /// These literals are implied by the formula, so they are in the backbone
+    /// if the formula is satisfiable. The list grows as the search propagates
+    /// and learns, which gives part of the backbone without the cost of
+    /// `backbone`.
+    pub fn implied_literals(&self) -> Vec<Literal> {
+        let end = match self.trail.level_starts.first() {
+            Some(&start) => start,
+            None => self.trail.literals.len(),
+        };
+        self.trail.literals[..end].to_vec()
+    }
+
+    /// Returns the number of learned clauses currently in the database.
+    pub fn num_learned(&self) -> usize {
+        self.n_learned
+    }
+
+    /// Returns the search statistics accumulated so far.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Solves the formula.
+    ///
+    /// Returns a variable assignment if the formula is satisfiable, or None if
+    /// the formula is unsatisfiable.
+    pub fn solve(&mut self) -> Option<Assignment> {
+        self.solve_under_assumptions(&[])
+    }
+
+    /// Solves the formula under the assumption that the given literals are
+    /// true. The assumptions only hold during this call; clauses learned in
+    /// the search remain valid for subsequent calls.
+    ///
+    /// Returns a variable assignment if the formula and the assumptions are
+    /// satisfiable together, or None otherwise. None is also returned if the
+    /// search is interrupted; use `search` to tell the two cases apart. An
+    /// assumption contradicting a unit clause of the formula gives None before
+    /// any propagation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an assumption refers to a variable not in the formula.
+    pub fn solve_under_assumptions(&mut self, assumptions: &[Literal]) -> Option<Assignment> {
+        match self.search(assumptions) {
+            Outcome::Sat(vars) => Some(vars),
+            Outcome::Unsat | Outcome::Unknown => None,
+        }
+    }
+
+    /// Solves the formula like `solve`, calling `callback` with the current
+    /// statistics every `PROGRESS_INTERVAL` conflicts. This is meant for
+    /// reporting the progress of a long search.
+    pub fn solve_with_callback(&mut self, mut callback: impl FnMut(&Stats)) -> Option<Assignment> {
+        match self.search_with_callback(&[], &mut callback) {
+            Outcome::Sat(vars) => Some(vars),
+            Outcome::Unsat | Outcome::Unknown => None,
+        }
+    }
+
+    /// Solves the formula like `solve` and also returns the trace of the
+    /// decisions and propagations that reached the model. Assignments undone
+    /// by backjumping are not included.
+    ///
+    /// Returns None if the formula is unsatisfiable or the search is
+    /// interrupted.
+    pub fn solve_with_trace(&mut self) -> Option<(Assignment, Trace)> {
+        self.trace = Some(Trace::default());
+        let outcome = self.search(&[]);
+        let trace = self.trace.take();
+
+        match outcome {
+            Outcome::Sat(vars) => trace.map(|trace| (vars, trace)),
+            Outcome::Unsat | Outcome::Unknown => None,
+        }
+    }
+
+    /// Solves the formula step by step. Each item of the iterator is the state
+    /// after one event of the search: a decision, a round of propagation, or
+    /// a conflict followed by backtracking. The last item has the outcome with
+    /// the model, if any, as its assignment.
+    ///
+    /// The saved phases are not checked for a model up front as in `solve`,
+    /// so every assignment of the search is seen. Dropping the iterator stops
+    /// the search, and the next search starts over.
+    pub fn solve_steps(&mut self) -> Steps<'_> {
+        Steps {
+            solver: self,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Returns the search tree of the last search in the Graphviz DOT
+    /// language. The root has the decisions of the first level as children,
+    /// each decision node is labeled with its literal in DIMACS form, and its
+    /// children are the decisions of the next level. After chronological
+    /// backtracking, the two phases of a variable are siblings. The leaves
+    /// are the conflicts and the model, drawn as boxes.
+    ///
+    /// Returns a graph of the root alone unless recording is enabled with
+    /// `with_search_tree`.
+    #[cfg(feature = "search-tree")]
+    pub fn search_tree_dot(&self) -> String {
+        let mut dot = String::from("digraph search {\n");
+        let nodes = match &self.tree {
+            Some(tree) => &tree.nodes[..],
+            None => &[(0, TreeNode::Root)],
+        };
+        for (index, &(parent, node)) in nodes.iter().enumerate() {
+            // Writing to a String never fails.
+            let _ = match node {
+                TreeNode::Root => writeln!(dot, "  n{} [label=\"root\"];", index),
+                TreeNode::Decision(lit) => writeln!(dot, "  n{} [label=\"{}\"];", index, lit),
+                TreeNode::Conflict => {
+                    writeln!(dot, "  n{} [label=\"conflict\", shape=box];", index)
+                }
+                TreeNode::Sat => writeln!(dot, "  n{} [label=\"sat\", shape=box];", index),
+            };
+            if index > 0 {
+                let _ = writeln!(dot, "  n{} -> n{};", parent, index);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Empties the search tree, if recorded, for a new search.
+    #[cfg(feature = "search-tree")]
+    fn clear_tree(&mut self) {
+        if self.tree.is_some() {
+            self.tree = Some(SearchTree::new());
+        }
+    }
+
+    /// Adds a node to the search tree at the current decision level.
+    #[cfg(feature = "search-tree")]
+    fn record_node(&mut self, node: TreeNode) {
+        let level = self.trail.decision_level();
+        if let Some(tree) = &mut self.tree {
+            match node {
+                TreeNode::Decision(_) => tree.add(level - 1, node),
+                _ => tree.add(level, node),
+            }
+        }
+    }
+
+    /// Returns a resolution proof that the formula is unsatisfiable, which can
+    /// be checked with `verify_unsat`. Returns None unless proof recording is
+    /// enabled with `with_proof` and a search has found the formula itself
+    /// unsatisfiable, not just under assumptions. The search must also learn
+    /// clauses, since chronological backtracking derives no clause.
+    pub fn unsat_proof(&self) -> Option<Proof> {
+        let log = self.proof.as_ref()?;
+        log.empty?;
+        Some(Proof {
+            steps: log.steps.clone(),
+        })
+    }
+
+    /// Solves the formula under assumptions like `solve_under_assumptions`,
+    /// but reports an interrupted search as `Outcome::Unknown`.
+    pub fn search(&mut self, assumptions: &[Literal]) -> Outcome {
+        self.search_with_callback(assumptions, &mut |_| {})
+    }
+
+    fn search_with_callback(
+        &mut self,
+        assumptions: &[Literal],
+        callback: &mut dyn FnMut(&Stats),
+    ) -> Outcome {
+        #[cfg(feature = "search-tree")]
+        self.clear_tree();
+
+        if self.inconsistent {
+            record!(self, TreeNode::Conflict);
+            return Outcome::Unsat;
+        }
+
+        // Undo the assignments left by decide_and_propagate.
+        self.backtrack(0);
+
+        if self.falsified_assumption(assumptions).is_some() {
+            record!(self, TreeNode::Conflict);
+            return Outcome::Unsat;
+        }
+
+        // The saved phases may already be a model, e.g., after a warm start.
+        // The search would end up with the same model, which is needed if the
+        // trace is recorded.
+        if self.trace.is_none() && self.phases_satisfy(assumptions) {
+            record!(self, TreeNode::Sat);
+            return Outcome::Sat(self.phases.to_assignment());
+        }
+
+        loop {
+            if let Step::Done(outcome) = self.step(assumptions, callback) {
+                event!(
+                    debug,
+                    "search finished: {} after {} decisions and {} conflicts",
+                    match outcome {
+                        Outcome::Sat(_) => "sat",
+                        Outcome::Unsat => "unsat",
+                        Outcome::Unknown => "unknown",
+                    },
+                    self.stats.decisions,
+                    self.stats.conflicts
+                );
+                return outcome;
+            }
+        }
+    }
+
+    /// Advances the search by one event: a round of propagation, either
+    /// reaching a conflict and backtracking or not, or a decision. The search
+    /// ends with the outcome.
+    fn step(&mut self, assumptions: &[Literal], callback: &mut dyn FnMut(&Stats)) -> Step {
+        let start = self.trail.literals.len();
+
+        if let Some(conflict) = self.propagate() {
+            self.stats.conflicts += 1;
+            record!(self, TreeNode::Conflict);
+            event!(
+                trace,
+                "conflict in clause {} at level {}",
+                conflict,
+                self.trail.decision_level()
+            );
+            if self.stats.conflicts.is_multiple_of(PROGRESS_INTERVAL) {
+                callback(&self.stats);
+            }
+
+            if self.trail.decision_level() == 0 {
+                if self.proof.is_some() {
+                    let clause = self.clauses[conflict].clone();
+                    self.refute(self.meta[conflict].origin, &clause);
+                }
+                self.inconsistent = true;
+                return Step::Done(Outcome::Unsat);
+            }
+
+            if !self.learning {
+                return match self.flipped.iter().rposition(|&flipped| !flipped) {
+                    Some(level) => {
+                        let lit = self.trail.literals[self.trail.level_starts[level]];
+                        self.backtrack(level);
+                        self.trail.decide(lit.negate());
+                        record!(self, TreeNode::Decision(lit.negate()));
+                        self.flipped.push(true);
+                        Step::Event(Action::Backtrack(level))
+                    }
+                    None => {
+                        self.inconsistent = assumptions.is_empty();
+                        self.backtrack(0);
+                        Step::Done(Outcome::Unsat)
+                    }
+                };
+            }
+
+            let (learned, level) = self.analyze(conflict);
+            let lbd = lbd(&learned, &self.trail);
+            let origin = match self.proof {
+                Some(_) => {
+                    let clause = self.clauses[conflict].clone();
+                    self.derive(self.meta[conflict].origin, &clause, &learned)
+                }
+                None => 0,
+            };
+            event!(
+                trace,
+                "learned clause of {} literals with LBD {}, backjumping to level {}",
+                learned.len(),
+                lbd,
+                level
+            );
+            self.backtrack(level);
+            self.learn(learned, lbd, origin);
+            self.var_increment /= VAR_DECAY;
+            self.clause_increment /= CLAUSE_DECAY;
+            return Step::Event(Action::Backtrack(level));
+        }
+
+        if self.trail.literals.len() > start {
+            return Step::Event(Action::Propagate(self.trail.literals[start..].to_vec()));
+        }
+
+        // Assumptions are decided first, one per decision level. A level is
+        // opened even for an assumption that already holds so that the levels
+        // and the assumptions stay aligned.
+        let level = self.trail.decision_level();
+        if level < assumptions.len() {
+            let lit = assumptions[level];
+            match self.trail.value(lit) {
+                Some(true) => self.trail.new_decision_level(),
+                Some(false) => {
+                    record!(self, TreeNode::Conflict);
+                    self.backtrack(0);
+                    return Step::Done(Outcome::Unsat);
+                }
+                None => self.trail.decide(lit),
+            }
+            record!(self, TreeNode::Decision(lit));
+            event!(trace, "assume {:?} at level {}", lit, level + 1);
+            self.flipped.push(true);
+            return Step::Event(Action::Decide(lit));
+        }
+
+        if let Some(flag) = &self.interrupt {
+            if flag.load(Ordering::Relaxed) {
+                self.backtrack(0);
+                return Step::Done(Outcome::Unknown);
+            }
+        }
+
+        match self.pick_branching_variable() {
+            Some(var) => {
+                self.stats.decisions += 1;
+                let lit = if self.phases.get(var) {
+                    Var(var)
+                } else {
+                    Not(var)
+                };
+                self.trail.decide(lit);
+                record!(self, TreeNode::Decision(lit));
+                event!(
+                    trace,
+                    "decide {:?} at level {}",
+                    lit,
+                    self.trail.decision_level()
+                );
+                self.flipped.push(false);
+                Step::Event(Action::Decide(lit))
+            }
+            None => {
+                let vars = self.trail.values.iter().map(|v| v == &Some(true)).collect();
+                if self.trace.is_some() {
+                    self.trace = Some(self.record_trace());
+                }
+                record!(self, TreeNode::Sat);
+                self.backtrack(0);
+                Step::Done(Outcome::Sat(vars))
+            }
+        }
+    }
+
+    /// Decides the literals in order, each at a new decision level, and
+    /// propagates the consequences. Stops at the first conflict and returns
+    /// false. The assignments are kept until the next search so that they can
+    /// be inspected with `implication_graph`.
+    ///
+    /// A literal already true is skipped. A literal already false is reported
+    /// as a conflict without a falsified clause.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a literal refers to a variable not in the formula.
+    pub fn decide_and_propagate(&mut self, decisions: &[Literal]) -> bool {
+        if self.inconsistent {
+            return false;
+        }
+
+        let mut decisions = decisions.iter();
+        loop {
+            if let Some(conflict) = self.propagate() {
+                // The formula is refuted if nothing is decided yet.
+                if self.trail.decision_level() == 0 {
+                    if self.proof.is_some() {
+                        let clause = self.clauses[conflict].clone();
+                        self.refute(self.meta[conflict].origin, &clause);
+                    }
+                    self.inconsistent = true;
+                }
+                self.conflict = Some(conflict);
+                return false;
+            }
+
+            match decisions.next() {
+                Some(&lit) => match self.trail.value(lit) {
+                    Some(true) => {}
+                    Some(false) => return false,
+                    None => self.trail.decide(lit),
+                },
+                None => return true,
+            }
+        }
+    }
+
+    /// Returns the implication graph of the current assignments, e.g., after
+    /// `decide_and_propagate`. Only the level-zero assignments remain after a
+    /// search.
+    pub fn implication_graph(&self) -> ImplicationGraph {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+
+        for &lit in self.trail.literals() {
+            let reason = self.trail.reason(lit.variable());
+            let clause = reason.map(|index| self.clauses[index].clone());
+
+            if let Some(clause) = &clause {
+                for &other in clause.iter().filter(|&&other| other != lit) {
+                    edges.push(ImplicationEdge {
+                        from: other.negate(),
+                        to: lit,
+                    });
+                }
+            }
+
+            nodes.push(ImplicationNode {
+                literal: lit,
+                level: self.trail.level(lit.variable()),
+                reason: clause,
+            });
+        }
+
+        ImplicationGraph {
+            nodes,
+            edges,
+            conflict: self.conflict.map(|index| self.clauses[index].clone()),
+        }
+    }
+
+    /// Copies the current trail into a trace.
+    fn record_trace(&self) -> Trace {
+        let entries = self
+            .trail
+            .literals()
+            .iter()
+            .map(|&lit| TraceEntry {
+                literal: lit,
+                level: self.trail.level(lit.variable()),
+                reason: self
+                    .trail
+                    .reason(lit.variable())
+                    .map(|index| self.clauses[index].clone()),
+            })
+            .collect();
+
+        Trace { entries }
+    }
+
+    /// Finds a minimal subset of the assumptions that makes the formula
+    /// unsatisfiable. Removing any single literal from the returned core makes
+    /// the formula satisfiable again, although a smaller core may exist.
+    ///
+    /// Returns None if the formula and the assumptions are satisfiable
+    /// together. The core is empty if the formula is unsatisfiable by itself.
+    ///
+    /// The core is minimized by deletion: each assumption is dropped in turn
+    /// and kept out if the rest are still unsatisfiable. This takes as many
+    /// solver calls as there are assumptions.
+    pub fn unsat_core(&mut self, assumptions: &[Literal]) -> Option<Vec<Literal>> {
+        // An assumption contradicting a unit clause is a core by itself.
+        let mut core = match self.falsified_assumption(assumptions) {
+            Some(lit) => vec![lit],
+            None if self.solve_under_assumptions(assumptions).is_some() => return None,
+            None => assumptions.to_vec(),
+        };
+        let mut i = 0;
+
+        while i < core.len() {
+            let lit = core.remove(i);
+            if self.solve_under_assumptions(&core).is_some() {
+                core.insert(i, lit);
+                i += 1;
+            }
+        }
+
+        Some(core)
+    }
+
+    /// Returns the first assumption that is false at level zero, where the
+    /// unit clauses of the formula are assigned. Such an assumption makes the
+    /// formula unsatisfiable regardless of the others.
+    fn falsified_assumption(&self, assumptions: &[Literal]) -> Option<Literal> {
+        assumptions.iter().copied().find(|&lit| {
+            self.trail.value(lit) == Some(false) && self.trail.level(lit.variable()) == 0
+        })
+    }
+
+    /// Adds a problem clause. Tautologies are dropped and unit clauses are
+    /// assigned at level zero.
+    fn add_clause(&mut self, clause: &Clause) {
+        let origin = self.n_added;
+        self.n_added += 1;
+
+        let mut literals = Clause::new();
+        for &lit in clause {
+            if literals.contains(&lit.negate()) {
+                return;
+            }
+            if !literals.contains(&lit) {
+                literals.push(lit);
+            }
+        }
+
+        let index = self.clauses.len();
+
+        match literals.len() {
+            0 => {
+                self.inconsistent = true;
+                self.falsified = self.falsified.or(Some(index));
+            }
+            1 => match self.trail.value(literals[0]) {
+                Some(true) => {}
+                Some(false) => {
+                    self.inconsistent = true;
+                    self.falsified = self.falsified.or(Some(index));
+                }
+                None => self.trail.assign(literals[0], Some(index)),
+            },
+            _ => {
+                self.watches[literals[0].code()].push(index);
+                self.watches[literals[1].code()].push(index);
+            }
+        }
+
+        self.clauses.push(literals);
+        self.meta.push(ClauseMeta {
+            learned: false,
+            activity: 0.0,
+            lbd: 0,
+            origin,
+        });
+    }
+
+    /// Records the resolutions deriving a clause from clause `start` numbered
+    /// `origin` in the proof. Every literal of `start` must be false. Walking
+    /// the trail backwards, each false literal outside `keep` is resolved away
+    /// with the reason of its variable, which brings in only earlier literals.
+    /// Returns the number of the derived clause.
+    ///
+    /// For a learned clause, `keep` is the clause itself: the literals resolved
+    /// by the conflict analysis, including those dropped by minimization or for
+    /// being at level zero, all have reasons, so the result is the learned
+    /// clause. Nothing is kept for the empty clause.
+    fn derive(&mut self, origin: usize, start: &[Literal], keep: &[Literal]) -> usize {
+        let log = match &mut self.proof {
+            Some(log) => log,
+            None => return origin,
+        };
+
+        let mut pending = vec![false; self.trail.values.len()];
+        for lit in start.iter().filter(|lit| !keep.contains(lit)) {
+            pending[lit.variable()] = true;
+        }
+
+        let mut current = origin;
+        for &lit in self.trail.literals.iter().rev() {
+            let var = lit.variable();
+            if !pending[var] {
+                continue;
+            }
+            pending[var] = false;
+
+            let index = self.trail.reason(var).expect("decision resolved");
+            let (reason, literals) = (self.meta[index].origin, &self.clauses[index][..]);
+            for other in literals.iter().filter(|other| other.variable() != var) {
+                if !keep.contains(other) {
+                    pending[other.variable()] = true;
+                }
+            }
+
+            log.steps.push(Resolution {
+                left: current,
+                right: reason,
+                pivot: var,
+            });
+            current = log.n_formula + log.steps.len() - 1;
+        }
+
+        current
+    }
+
+    /// Derives the empty clause from a false clause at level zero.
+    fn refute(&mut self, origin: usize, clause: &[Literal]) {
+        let empty = self.derive(origin, clause, &[]);
+        if let Some(log) = &mut self.proof {
+            log.empty = Some(empty);
+        }
+    }
+
+    /// Propagates the assignments on the trail that are not yet propagated.
+    /// Returns the index of a falsified clause if a conflict is found.
+    fn propagate(&mut self) -> Option<usize> {
+        let trail = &mut self.trail;
+        let clauses = &mut self.clauses;
+        let watches = &mut self.watches;
+
+        while self.propagated < trail.literals.len() {
+            let false_lit = trail.literals[self.propagated].negate();
+            self.propagated += 1;
+            self.stats.propagations += 1;
+
+            // Every clause watching the falsified literal needs a new watch.
+            let mut watchers = core::mem::take(&mut watches[false_lit.code()]);
+            let mut conflict = None;
+            let mut i = 0;
+
+            while i < watchers.len() {
+                let index = watchers[i];
+                let clause = &mut clauses[index];
+
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+
+                let other = clause[0];
+                if trail.value(other) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+
+                if let Some(k) = (2..clause.len()).find(|&k| trail.value(clause[k]) != Some(false))
+                {
+                    clause.swap(1, k);
+                    watches[clause[1].code()].push(index);
+                    watchers.swap_remove(i);
+                    continue;
+                }
+
+                // All the literals but the other watch are false.
+                if trail.value(other) == Some(false) {
+                    conflict = Some(index);
+                    break;
+                }
+                trail.assign(other, Some(index));
+                i += 1;
+            }
+
+            watches[false_lit.code()] = watchers;
+
+            // The falsified literal and the rest of the trail are left to
+            // propagate again in case a backtrack keeps them.
+            if conflict.is_some() {
+                self.propagated -= 1;
+                return conflict;
+            }
+        }
+
+        None
+    }
+
+    /// Derives a learned clause from a conflict by resolving the falsified
+    /// clause with reasons until a single literal of the current decision level
+    /// remains (the first unique implication point). Returns the learned clause
+    /// with the asserting literal first, along with the decision level to
+    /// backjump to.
+    fn analyze(&mut self, conflict: usize) -> (Clause, usize) {
+        let level = self.trail.decision_level();
+        let mut seen = vec![false; self.trail.values.len()];
+        let mut learned = vec![Not(0)];
+        let mut pending = 0;
+        let mut index = self.trail.literals.len();
+        let mut reason = conflict;
+        let mut resolved = None;
+
+        loop {
+            self.bump_clause(reason);
+
+            for i in 0..self.clauses[reason].len() {
+                let lit = self.clauses[reason][i];
+                let var = lit.variable();
+                if Some(var) == resolved || seen[var] || self.trail.level(var) == 0 {
+                    continue;
+                }
+                seen[var] = true;
+                if self.bump != BumpStrategy::Learned {
+                    self.bump_variable(var);
+                }
+
+                if self.trail.level(var) == level {
+                    pending += 1;
+                } else {
+                    learned.push(lit);
+                }
+            }
+
+            // Pick the most recently assigned literal involved in the conflict.
+            loop {
+                index -= 1;
+                if seen[self.trail.literals[index].variable()] {
+                    break;
+                }
+            }
+            let lit = self.trail.literals[index];
+            let var = lit.variable();
+            seen[var] = false;
+            pending -= 1;
+
+            if pending == 0 {
+                learned[0] = lit.negate();
+                break;
+            }
+
+            resolved = Some(var);
+            reason = self.trail.reason(var).unwrap();
+        }
+
+        minimize_learned(&mut learned, &self.trail, &self.clauses);
+        debug_assert!(self.trail.level(learned[0].variable()) == level);
+
+        match self.bump {
+            BumpStrategy::Resolved => {}
+            BumpStrategy::Learned => {
+                for &lit in &learned {
+                    self.bump_variable(lit.variable());
+                }
+            }
+            BumpStrategy::ReasonClosure => self.bump_reason_closure(&learned),
+        }
+
+        // Watch the literal of the highest level among the rest so that the
+        // clause becomes a unit right after backjumping.
+        let mut backjump_level = 0;
+        for i in 1..learned.len() {
+            let lit_level = self.trail.level(learned[i].variable());
+            if lit_level > backjump_level {
+                backjump_level = lit_level;
+                learned.swap(1, i);
+            }
+        }
+
+        (learned, backjump_level)
+    }
+
+    /// Adds a learned clause and asserts its first literal. The solver must
+    /// have backjumped to the level where the clause is a unit.
+    /// `origin` is the number of the clause in the proof, if recorded.
+    fn learn(&mut self, learned: Clause, lbd: usize, origin: usize) {
+        self.stats.learned += 1;
+        self.stats.lbd_sum += lbd as u64;
+
+        // A unit is asserted at level zero for good, so it needs no watches.
+        // It is kept as the reason of its literal, which also keeps it from
+        // being deleted, and is not counted in the database.
+        let unit = learned.len() == 1;
+        if !unit && self.n_learned as f64 >= self.learned_limit {
+            self.reduce_learned();
+        }
+
+        let index = self.clauses.len();
+        if !unit {
+            self.watches[learned[0].code()].push(index);
+            self.watches[learned[1].code()].push(index);
+            self.n_learned += 1;
+        }
+        self.trail.assign(learned[0], Some(index));
+        self.clauses.push(learned);
+        self.meta.push(ClauseMeta {
+            learned: true,
+            activity: self.clause_increment,
+            lbd,
+            origin,
+        });
+    }
+
+    /// Deletes half of the learned clauses that are not the reason of a current
+    /// assignment, preferring those with high LBD and then low activity.
+    fn reduce_learned(&mut self) {
+        let mut locked = vec![false; self.clauses.len()];
+        for lit in &self.trail.literals {
+            if let Some(reason) = self.trail.reason(lit.variable()) {
+                locked[reason] = true;
+            }
+        }
+
+        let mut candidates: Vec<usize> = (0..self.clauses.len())
+            .filter(|&i| self.meta[i].learned && !locked[i])
+            .collect();
+        candidates.sort_by(|&a, &b| {
+            let (a, b) = (&self.meta[a], &self.meta[b]);
+            b.lbd
+                .cmp(&a.lbd)
+                .then(a.activity.partial_cmp(&b.activity).unwrap())
+        });
+
+        let mut deleted = vec![false; self.clauses.len()];
+        for &i in &candidates[..candidates.len() / 2] {
+            deleted[i] = true;
+        }
+        event!(
+            debug,
+            "deleting {} of {} learned clauses",
+            candidates.len() / 2,
+            self.n_learned
+        );
+
+        // Compact the database and renumber the references to the clauses.
+        let mut renumber = vec![0; self.clauses.len()];
+        let mut n_kept = 0;
+        for i in 0..self.clauses.len() {
+            if !deleted[i] {
+                self.clauses.swap(n_kept, i);
+                self.meta.swap(n_kept, i);
+                renumber[i] = n_kept;
+                n_kept += 1;
+            }
+        }
+        self.clauses.truncate(n_kept);
+        self.meta.truncate(n_kept);
+
+        for reason in self.trail.reasons.iter_mut() {
+            *reason = reason.filter(|&r| !deleted[r]).map(|r| renumber[r]);
+        }
+
+        for watchers in self.watches.iter_mut() {
+            watchers.clear();
+        }
+        for (i, clause) in self.clauses.iter().enumerate() {
+            if clause.len() >= 2 {
+                self.watches[clause[0].code()].push(i);
+                self.watches[clause[1].code()].push(i);
+            }
+        }
+
+        let n_deleted = candidates.len() / 2;
+        self.n_learned -= n_deleted;
+        self.stats.deleted += n_deleted as u64;
+
+        if self.max_learned.is_none() {
+            self.learned_limit *= LEARNED_GROWTH;
+        }
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if level < self.trail.decision_level() {
+            let start = self.trail.level_starts[level];
+            for lit in &self.trail.literals[start..] {
+                self.phases.set(lit.variable(), matches!(lit, Var(_)));
+            }
+        }
+        self.trail.backtrack(level);
+        self.flipped.truncate(level);
+        self.propagated = self.propagated.min(self.trail.literals.len());
+        self.conflict = None;
+    }
+
+    /// Checks if the saved phases satisfy all the clauses and assumptions.
+    fn phases_satisfy(&self, assumptions: &[Literal]) -> bool {
+        let truth = |lit: &Literal| self.phases.get(lit.variable()) == matches!(lit, Var(_));
+        assumptions.iter().all(truth) && self.clauses.iter().all(|clause| clause.iter().any(truth))
+    }
+
+    /// Sets the saved phase of every variable to the default polarity.
+    fn reset_phases(&mut self) {
+        match self.polarity {
+            Polarity::True => self.phases.fill(true),
+            Polarity::False => self.phases.fill(false),
+            Polarity::Random => {
+                let mut rng = Rng::new(self.seed);
+                for var in 0..self.phases.len() {
+                    self.phases.set(var, rng.next_bool());
+                }
+            }
+        }
+    }
+
+    /// Picks the first unassigned variable in the decision order, or the
+    /// unassigned variable with the highest activity.
+    fn pick_branching_variable(&self) -> Option<usize> {
+        let values = &self.trail.values;
+        if let Some(&var) = self
+            .decision_order
+            .iter()
+            .find(|&&var| var < values.len() && values[var].is_none())
+        {
+            return Some(var);
+        }
+
+        let mut best = None;
+        let mut max = -1.0;
+
+        for (var, &activity) in self.var_activity.iter().enumerate() {
+            if self.trail.values[var].is_none() && activity > max {
+                max = activity;
+                best = Some(var);
+            }
+        }
+
+        best
+    }
+
+    fn bump_variable(&mut self, var: usize) {
+        self.var_activity[var] += self.var_increment;
+
+        if self.var_activity[var] > 1e100 {
+            for activity in self.var_activity.iter_mut() {
+                *activity *= 1e-100;
+            }
+            self.var_increment *= 1e-100;
+        }
+    }
+
+    /// Bumps the variables implied through the reasons to the literals of a
+    /// learned clause that the conflict analysis has not bumped. Those are the
+    /// variables of the reasons of the literals below the current level, the
+    /// variables of their reasons, and so on.
+    fn bump_reason_closure(&mut self, learned: &Clause) {
+        let level = self.trail.decision_level();
+        let mut visited = vec![false; self.trail.values.len()];
+        let mut stack: Vec<usize> = learned[1..].iter().map(|lit| lit.variable()).collect();
+        for &var in &stack {
+            visited[var] = true;
+        }
+
+        while let Some(var) = stack.pop() {
+            let reason = match self.trail.reason(var) {
+                Some(reason) => reason,
+                None => continue,
+            };
+            for i in 0..self.clauses[reason].len() {
+                let other = self.clauses[reason][i].variable();
+                let other_level = self.trail.level(other);
+                if visited[other] || other_level == 0 || other_level == level {
+                    continue;
+                }
+                visited[other] = true;
+                self.bump_variable(other);
+                stack.push(other);
+            }
+        }
+    }
+
+    fn bump_clause(&mut self, index: usize) {
+        self.meta[index].activity += self.clause_increment;
+
+        if self.meta[index].activity > 1e20 {
+            for meta in self.meta.iter_mut() {
+                meta.activity *= 1e-20;
+            }
+            self.clause_increment *= 1e-20;
+        }
+    }
+}
+
+/// Finds the backbone of a formula, i.e., the literals that are true in every
+/// model of the formula.
+///
+/// Each literal of a model is tested by solving under the assumption of its
+/// negation. The literal is in the backbone if the assumption makes the
+/// formula unsatisfiable. Otherwise, the model found rules out every other
+/// candidate literal it falsifies.
+///
+/// Returns the literals in the order of variables, or an empty vector if the
+/// formula is unsatisfiable.
+pub fn backbone(formula: &Formula) -> Vec<Literal> {
+    let mut solver = Solver::new(formula);
+
+    let model = match solver.solve() {
+        Some(model) => model,
+        None => return Vec::new(),
+    };
+
+    let mut candidates: Vec<Option<Literal>> = model
+        .iter()
+        .enumerate()
+        .map(|(i, &truth)| Some(if truth { Var(i) } else { Not(i) }))
+        .collect();
+
+    let mut literals = Vec::new();
+
+    for i in 0..candidates.len() {
+        let lit = match candidates[i] {
+            Some(lit) => lit,
+            None => continue,
+        };
+
+        match solver.solve_under_assumptions(&[lit.negate()]) {
+            Some(other) => {
+                for candidate in candidates.iter_mut() {
+                    if let Some(lit) = *candidate {
+                        if other[lit.variable()] != matches!(lit, Var(_)) {
+                            *candidate = None;
+                        }
+                    }
+                }
+            }
+            None => literals.push(lit),
+        }
+    }
+
+    literals
+}
+
+/// Finds up to `k` distinct models of a formula that differ from each other in
+/// as many variables as possible.
+///
+/// The models are chosen greedily. Each new model is blocked from being found
+/// again, and the next search assumes the values opposite to the majority of
+/// the models found so far, starting with the variables on which they agree
+/// most. These assumptions are soft: the longest prefix of them that is
+/// satisfiable is found by binary search and the rest are dropped.
+///
+/// Returns fewer than `k` models if the formula has fewer, and an empty vector
+/// if it is unsatisfiable.
+pub fn diverse_models(formula: &Formula, k: usize) -> Vec<Assignment> {
+    let mut blocked = formula.clone();
+    let mut models: Vec<Assignment> = Vec::new();
+
+    while models.len() < k {
+        let mut solver = Solver::new(&blocked);
+        let mut model = match solver.solve() {
+            Some(model) => model,
+            None => break,
+        };
+
+        let preferred = opposite_of_majority(&models, model.len());
+
+        // Invariant: the first `lo` preferred literals are satisfiable and
+        // the first `hi` are not (or `hi` is past the end).
+        let (mut lo, mut hi) = (0, preferred.len() + 1);
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            match solver.solve_under_assumptions(&preferred[..mid]) {
+                Some(found) => {
+                    lo = mid;
+                    model = found;
+                }
+                None => hi = mid,
+            }
+        }
+
+        blocked.push(
+            model
+                .iter()
+                .enumerate()
+                .map(|(i, &truth)| if truth { Not(i) } else { Var(i) })
+                .collect(),
+        );
+        models.push(model);
+    }
+
+    models
+}
+
+/// Returns the literal opposite to the majority value of each variable in the
+/// models, ordered by the margin of the majority. Variables without a majority
+/// are left out.
+fn opposite_of_majority(models: &[Assignment], n_vars: usize) -> Vec<Literal> {
+    let mut margins: Vec<(usize, Literal)> = Vec::new();
+
+    for i in 0..n_vars {
+        let trues = models.iter().filter(|model| model[i]).count();
+        let falses = models.len() - trues;
+        if trues > falses {
+            margins.push((trues - falses, Not(i)));
+        } else if falses > trues {
+            margins.push((falses - trues, Var(i)));
+        }
+    }
+
+    margins.sort_by_key(|&(margin, _)| core::cmp::Reverse(margin));
+    margins.into_iter().map(|(_, lit)| lit).collect()
+}
+
+/// Solves a formula with a portfolio of solvers running in parallel and returns
+/// the result of the first one to finish.
+///
+/// `n_threads` solvers are started with different default polarities and
+/// seeds so that they explore different parts of the search space. The others
+/// are interrupted once a result is found. A model found by any of them is
+/// checked against the formula before it is returned.
+#[cfg(feature = "std")]
+pub fn solve_parallel(formula: &Formula, n_threads: usize) -> Option<Assignment> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel();
+
+    let outcome = thread::scope(|scope| {
+        for i in 0..n_threads.max(1) {
+            let stop = stop.clone();
+            let sender = sender.clone();
+
+            scope.spawn(move || {
+                let polarity = match i % 3 {
+                    0 => Polarity::False,
+                    1 => Polarity::True,
+                    _ => Polarity::Random,
+                };
+                let mut solver = Solver::new(formula)
+                    .with_default_polarity(polarity)
+                    .with_seed(i as u64)
+                    .with_interrupt(stop);
+
+                // The receiver may be gone if another solver has finished.
+                let outcome = solver.search(&[]);
+                if outcome != Outcome::Unknown {
+                    let _ = sender.send(outcome);
+                }
+            });
+        }
+        drop(sender);
+
+        let outcome = receiver.recv();
+        stop.store(true, Ordering::Relaxed);
+        outcome
+    });
+
+    match outcome {
+        Ok(Outcome::Sat(vars)) => {
+            assert!(
+                super::sat::verify(formula, &vars),
+                "portfolio solver found an invalid model"
+            );
+            Some(vars)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dimacs;
+    use crate::sat;
+    use crate::testing::random_3sat;
+
+    /// Generates the pigeonhole formula asserting that n + 1 pigeons fit in n
+    /// holes, which is unsatisfiable and hard for resolution.
+    fn pigeonhole(n: usize) -> Formula {
+        let var = |pigeon: usize, hole: usize| pigeon * n + hole;
+        let mut formula = Formula::new();
+
+        for pigeon in 0..=n {
+            formula.push((0..n).map(|hole| Var(var(pigeon, hole))).collect());
+        }
+
+        for hole in 0..n {
+            for a in 0..=n {
+                for b in a + 1..=n {
+                    formula.push(vec![Not(var(a, hole)), Not(var(b, hole))]);
+                }
+            }
+        }
+
+        formula
+    }
+
+    #[test]
+    fn test_trail_backtrack() {
+        let mut trail = Trail::new(3);
+
+        trail.decide(Var(0));
+        trail.assign(Not(1), Some(0));
+        trail.decide(Var(2));
+        assert_eq!(trail.decision_level(), 2);
+        assert_eq!(trail.level(1), 1);
+        assert_eq!(trail.reason(1), Some(0));
+        assert_eq!(trail.value(Var(1)), Some(false));
+        assert_eq!(trail.value(Not(1)), Some(true));
+
+        trail.backtrack(1);
+        assert_eq!(trail.decision_level(), 1);
+        assert_eq!(trail.literals(), &[Var(0), Not(1)]);
+        assert_eq!(trail.value(Var(2)), None);
+    }
+
+    #[test]
+    fn test_minimize_learned() {
+        // a = 0, b = 1, c = 2, d = 3.
+        let clauses = vec![
+            vec![Not(0), Var(1)],         // a => b
+            vec![Not(1), Var(2)],         // b => c
+            vec![Not(0), Not(2), Not(3)], // a & c => !d
+        ];
+
+        // Implication graph: a (decision) -> b -> c, d (decision).
+        let mut trail = Trail::new(4);
+        trail.decide(Var(0));
+        trail.assign(Var(1), Some(0));
+        trail.assign(Var(2), Some(1));
+        trail.decide(Var(3));
+
+        // !c is redundant via reasons c <- b <- a, and a is in the clause.
+        let mut learned = vec![Not(0), Not(2), Not(3)];
+        minimize_learned(&mut learned, &trail, &clauses);
+        assert_eq!(learned, vec![Not(0), Not(3)]);
+
+        // The minimized clause is still entailed by the formula.
+        for bits in 0..16 {
+            let vars: sat::Assignment = (0..4).map(|i| bits >> i & 1 == 1).collect();
+            if sat::verify(&clauses, &vars) {
+                assert!(sat::verify(&vec![learned.clone()], &vars));
+            }
+        }
+    }
+
+    #[test]
+    fn test_lbd() {
+        let mut trail = Trail::new(5);
+        trail.assign(Not(4), None);
+        trail.decide(Var(0));
+        trail.assign(Var(1), Some(0));
+        trail.decide(Var(2));
+        trail.assign(Not(3), Some(1));
+
+        // Levels 1 and 2.
+        assert_eq!(lbd(&[Not(0), Not(1), Not(2), Var(3)], &trail), 2);
+        assert_eq!(lbd(&[Not(1), Not(2)], &trail), 2);
+        assert_eq!(lbd(&[Not(0), Not(1)], &trail), 1);
+        // Level zero counts as a level as well.
+        assert_eq!(lbd(&[Var(4), Not(2)], &trail), 2);
+    }
+
+    #[test]
+    fn test_solver_learned_lbd() {
+        let mut solver = Solver::new(&pigeonhole(5));
+        assert!(solver.solve().is_none());
+
+        let stats = solver.stats();
+        assert!(stats.learned > 0);
+        assert!(stats.lbd_sum >= stats.learned);
+        assert!(stats.average_lbd() >= 1.0);
+
+        for (clause, meta) in solver.clauses.iter().zip(&solver.meta) {
+            if meta.learned {
+                assert!(meta.lbd >= 1 && meta.lbd <= clause.len());
+            } else {
+                assert_eq!(meta.lbd, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimize_learned_long_chain() {
+        // x0 (decision) -> x1 -> ... -> x(n-1), and a decision xn. The chain is
+        // too deep for a recursive search of the reasons.
+        let n = 1_000_000;
+        let clauses: Vec<Clause> = (1..n).map(|i| vec![Not(i - 1), Var(i)]).collect();
+
+        let mut trail = Trail::new(n + 1);
+        trail.decide(Var(0));
+        for i in 1..n {
+            trail.assign(Var(i), Some(i - 1));
+        }
+        trail.decide(Var(n));
+
+        let mut learned = vec![Not(0), Not(n - 1), Not(n)];
+        minimize_learned(&mut learned, &trail, &clauses);
+        assert_eq!(learned, vec![Not(0), Not(n)]);
+
+        // Without x0 in the clause, the chain ends at a decision.
+        let mut learned = vec![Not(n - 1), Not(n)];
+        minimize_learned(&mut learned, &trail, &clauses);
+        assert_eq!(learned, vec![Not(n - 1), Not(n)]);
+    }
+
+    #[test]
+    fn test_minimize_learned_keeps_decisions() {
+        let clauses = vec![vec![Not(0), Var(1)]];
+
+        let mut trail = Trail::new(3);
+        trail.decide(Var(0));
+        trail.assign(Var(1), Some(0));
+        trail.decide(Var(2));
+
+        // !b is implied by the absent decision a, so nothing can be removed.
+        let mut learned = vec![Not(1), Not(2)];
+        minimize_learned(&mut learned, &trail, &clauses);
+        assert_eq!(learned, vec![Not(1), Not(2)]);
+    }
+
+    #[test]
+    fn test_solver_solve() {
+        // Same examples as sat::test_check_sat.
+        let formula = vec![
+            vec![Var(0), Var(0), Var(1)],
+            vec![Not(0), Not(1), Not(1)],
+            vec![Not(0), Var(1), Var(1)],
+        ];
+        let vars = Solver::new(&formula).solve().unwrap();
+        assert!(sat::verify(&formula, &vars));
+
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Not(0), Not(1)],
+            vec![Var(1), Var(2)],
+            vec![Not(1), Not(2)],
+            vec![Var(2), Var(0)],
+            vec![Not(2), Not(0)],
+        ];
+        assert!(Solver::new(&formula).solve().is_none());
+
+        assert_eq!(Solver::new(&vec![]).solve(), Some(vec![]));
+        assert!(Solver::new(&vec![vec![]]).solve().is_none());
+    }
+
+    #[test]
+    fn test_solver_solve_examples() {
+        let sources = [
+            include_str!("../examples/negative_3.cnf"),
+            include_str!("../examples/qg3-08.cnf"),
+        ];
+
+        for source in sources.iter() {
+            let formula = match dimacs::load(&mut source.as_bytes(), true) {
+                Ok(formula) => formula,
+                Err(err) => panic!("unexpected: {}", err),
+            };
+            let vars = Solver::new(&formula).solve().unwrap();
+            assert!(sat::verify(&formula, &vars));
+        }
+    }
+
+    #[test]
+    fn test_solver_bump_strategies() {
+        let sources = [
+            include_str!("../examples/negative_3.cnf"),
+            include_str!("../examples/qg3-08.cnf"),
+        ];
+        let mut formulas: Vec<(Formula, bool)> = sources
+            .iter()
+            .map(|source| (dimacs::load(&mut source.as_bytes(), true).unwrap(), true))
+            .collect();
+        formulas.push((pigeonhole(5), false));
+
+        let strategies = [
+            BumpStrategy::Resolved,
+            BumpStrategy::Learned,
+            BumpStrategy::ReasonClosure,
+        ];
+        let mut decisions = Vec::new();
+
+        for strategy in strategies.iter() {
+            for (formula, expect) in &formulas {
+                let mut solver = Solver::new(formula).with_bump_strategy(*strategy);
+                match solver.solve() {
+                    Some(vars) => assert!(*expect && sat::verify(formula, &vars)),
+                    None => assert!(!*expect),
+                }
+                if !*expect {
+                    decisions.push(solver.stats().decisions);
+                }
+            }
+        }
+
+        // The strategies steer the search differently.
+        assert!(decisions[0] != decisions[1] || decisions[0] != decisions[2]);
+    }
+
+    #[test]
+    fn test_solver_reduce_learned() {
+        let formula = pigeonhole(6);
+        let mut solver = Solver::new(&formula).with_max_learned(50);
+
+        assert!(solver.solve().is_none());
+        assert!(solver.stats().conflicts > 200);
+        assert!(solver.stats().deleted > 0);
+        assert!(solver.num_learned() <= 50);
+    }
+
+    #[test]
+    fn test_solver_default_polarity() {
+        // Variables 1 to 3 are unconstrained.
+        let formula = vec![vec![Var(0), Var(4)], vec![Not(0), Not(4)]];
+
+        let vars = Solver::new(&formula)
+            .with_default_polarity(Polarity::True)
+            .solve()
+            .unwrap();
+        assert!(sat::verify(&formula, &vars));
+        assert_eq!(&vars[1..4], &[true, true, true]);
+
+        let vars = Solver::new(&formula)
+            .with_default_polarity(Polarity::False)
+            .solve()
+            .unwrap();
+        assert!(sat::verify(&formula, &vars));
+        assert_eq!(&vars[1..4], &[false, false, false]);
+
+        // Random polarity is reproducible for a seed.
+        let solve_random = |seed| {
+            Solver::new(&formula)
+                .with_default_polarity(Polarity::Random)
+                .with_seed(seed)
+                .solve()
+                .unwrap()
+        };
+        assert_eq!(solve_random(1), solve_random(1));
+        assert!(sat::verify(&formula, &solve_random(1)));
+    }
+
+    #[test]
+    fn test_solver_solve_under_assumptions() {
+        // 0 => 1, 1 => !2
+        let formula = vec![vec![Not(0), Var(1)], vec![Not(1), Not(2)]];
+        let mut solver = Solver::new(&formula);
+
+        let vars = solver.solve_under_assumptions(&[Var(0)]).unwrap();
+        assert!(sat::verify(&formula, &vars));
+        assert!(vars[0] && vars[1] && !vars[2]);
+
+        assert!(solver.solve_under_assumptions(&[Var(0), Var(2)]).is_none());
+        assert!(solver.solve_under_assumptions(&[Var(1), Var(1)]).is_some());
+
+        // Assumptions do not persist.
+        let vars = solver.solve_under_assumptions(&[Var(2)]).unwrap();
+        assert!(sat::verify(&formula, &vars));
+        assert!(!vars[0] && !vars[1] && vars[2]);
+    }
+
+    #[test]
+    fn test_solver_unsat_core() {
+        // 1 => 3, 3 => !4
+        let formula = vec![
+            vec![Not(1), Var(3)],
+            vec![Not(3), Not(4)],
+            vec![Var(0), Var(2), Var(5)],
+        ];
+        let mut solver = Solver::new(&formula);
+
+        let assumptions = [Var(0), Var(1), Var(2), Var(4), Not(5)];
+        assert_eq!(solver.unsat_core(&assumptions), Some(vec![Var(1), Var(4)]));
+        assert_eq!(solver.unsat_core(&[Var(1), Not(4)]), None);
+
+        // The core is still useful after the solver has learned clauses.
+        let vars = solver.solve_under_assumptions(&[Var(4)]).unwrap();
+        assert!(sat::verify(&formula, &vars));
+
+        let formula = vec![vec![Var(0)], vec![Not(0)]];
+        let mut solver = Solver::new(&formula);
+        assert_eq!(solver.unsat_core(&[Var(0)]), Some(vec![]));
+    }
+
+    #[test]
+    fn test_solver_assumption_contradicting_unit() {
+        let formula = vec![
+            vec![Var(0), Var(1), Var(2)],
+            vec![Not(1)],
+            vec![Not(0), Var(2), Var(3)],
+        ];
+        let mut solver = Solver::new(&formula);
+
+        // Found before any propagation or decision.
+        assert!(solver.solve_under_assumptions(&[Var(0), Var(1)]).is_none());
+        assert_eq!(solver.stats(), &Stats::default());
+
+        let assumptions = [Var(0), Not(2), Var(1), Var(3)];
+        assert_eq!(solver.unsat_core(&assumptions), Some(vec![Var(1)]));
+
+        // Also after a search and with assignments left by a probe.
+        assert!(solver.solve_under_assumptions(&[Var(0)]).is_some());
+        assert!(solver.decide_and_propagate(&[Not(2)]));
+        assert!(solver.solve_under_assumptions(&[Not(2), Var(1)]).is_none());
+        assert!(solver.solve_under_assumptions(&[Not(2)]).is_some());
+
+        // The core is empty if the formula itself is unsatisfiable.
+        let formula = vec![vec![Not(0)], vec![Var(0)]];
+        let mut solver = Solver::new(&formula);
+        assert_eq!(solver.unsat_core(&[Var(0)]), Some(vec![]));
+    }
+
+    #[test]
+    fn test_solver_solve_with_callback() {
+        let mut solver = Solver::new(&pigeonhole(6));
+        let mut reported = Vec::new();
+        let result = solver.solve_with_callback(|stats| reported.push(stats.clone()));
+        assert!(result.is_none());
+
+        let stats = solver.stats();
+        assert!(!reported.is_empty());
+        assert_eq!(reported.len() as u64, stats.conflicts / PROGRESS_INTERVAL);
+
+        for (i, progress) in reported.iter().enumerate() {
+            assert_eq!(progress.conflicts, (i as u64 + 1) * PROGRESS_INTERVAL);
+            assert!(progress.decisions <= stats.decisions);
+        }
+    }
+
+    #[test]
+    fn test_solver_interrupt() {
+        let flag = Arc::new(AtomicBool::new(true));
+        let mut solver = Solver::new(&pigeonhole(5)).with_interrupt(flag.clone());
+        assert_eq!(solver.search(&[]), Outcome::Unknown);
+        assert!(solver.solve().is_none());
+
+        flag.store(false, Ordering::Relaxed);
+        assert_eq!(solver.search(&[]), Outcome::Unsat);
+
+        // The flag is checked even if propagation has assigned everything.
+        let formula = vec![vec![Var(0)], vec![Not(0), Var(1)]];
+        let flag = Arc::new(AtomicBool::new(true));
+        let mut solver = Solver::new(&formula).with_interrupt(flag);
+        assert_eq!(solver.search(&[]), Outcome::Unknown);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_solve_parallel() {
+        let sources = [
+            include_str!("../examples/negative_3.cnf"),
+            include_str!("../examples/qg3-08.cnf"),
+        ];
+
+        for source in sources.iter() {
+            let formula = match dimacs::load(&mut source.as_bytes(), true) {
+                Ok(formula) => formula,
+                Err(err) => panic!("unexpected: {}", err),
+            };
+            let expect = Solver::new(&formula).solve().is_some();
+
+            for &n_threads in &[0, 1, 4] {
+                let vars = solve_parallel(&formula, n_threads);
+                assert_eq!(vars.is_some(), expect);
+                if let Some(vars) = vars {
+                    assert!(sat::verify(&formula, &vars));
+                }
+            }
+        }
+
+        for _ in 0..10 {
+            assert!(solve_parallel(&pigeonhole(5), 4).is_none());
+        }
+    }
+
+    #[test]
+    fn test_solver_implied_literals() {
+        // 0, 0 => 1, 1 => !2, and the rest needs decisions.
+        let formula = vec![
+            vec![Not(0), Var(1)],
+            vec![Var(0)],
+            vec![Not(1), Not(2)],
+            vec![Var(3), Var(4)],
+            vec![Not(3), Var(5)],
+            vec![Not(4), Not(5)],
+        ];
+        let mut solver = Solver::new(&formula);
+        assert_eq!(solver.implied_literals(), vec![Var(0)]);
+
+        assert!(solver.solve().is_some());
+        assert!(solver.stats().decisions > 0);
+        assert_eq!(solver.implied_literals(), vec![Var(0), Var(1), Not(2)]);
+
+        // Every reported literal, including those of learned units, is in
+        // the backbone.
+        let mut rng = Rng::new(7);
+        for _ in 0..20 {
+            let formula: Formula = random_3sat(&mut rng, 14, 55);
+
+            let mut solver = Solver::new(&formula);
+            if solver.solve().is_none() {
+                continue;
+            }
+            let backbone = backbone(&formula);
+            for lit in solver.implied_literals() {
+                assert!(backbone.contains(&lit));
+            }
+        }
+    }
+
+    #[test]
+    fn test_backbone() {
+        let formula = vec![
+            vec![Var(0)],
+            vec![Not(0), Var(1)],
+            vec![Var(2), Var(3)],
+            vec![Not(1), Not(4)],
+            // Backbone not derived by unit propagation.
+            vec![Var(5), Var(6)],
+            vec![Var(5), Not(6)],
+        ];
+        assert_eq!(backbone(&formula), vec![Var(0), Var(1), Not(4), Var(5)]);
+
+        let formula = vec![vec![Var(0)], vec![Not(0)]];
+        assert_eq!(backbone(&formula), vec![]);
+    }
+
+    #[test]
+    fn test_diverse_models() {
+        let formula = vec![vec![Var(0), Var(1), Var(2), Var(3), Var(4), Var(5)]];
+        let models = diverse_models(&formula, 4);
+        assert_eq!(models.len(), 4);
+
+        for (i, model) in models.iter().enumerate() {
+            assert!(sat::verify(&formula, model));
+            assert!(models[..i].iter().all(|other| other != model));
+        }
+
+        // The second model flips every variable unless the first one is all
+        // true, whose complement is not a model.
+        let distance = models[0]
+            .iter()
+            .zip(&models[1])
+            .filter(|(a, b)| a != b)
+            .count();
+        assert!(distance >= 5);
+
+        // Fewer models than requested.
+        let formula = vec![vec![Var(0), Var(1)]];
+        let mut models = diverse_models(&formula, 5);
+        models.sort();
+        assert_eq!(
+            models,
+            vec![vec![false, true], vec![true, false], vec![true, true]]
+        );
+
+        let formula = vec![vec![Var(0)], vec![Not(0)]];
+        assert_eq!(diverse_models(&formula, 3), Vec::<Assignment>::new());
+    }
+
+    #[test]
+    fn test_solver_warm_start() {
+        let source = include_str!("../examples/qg3-08.cnf");
+        let formula = match dimacs::load(&mut source.as_bytes(), true) {
+            Ok(formula) => formula,
+            Err(err) => panic!("unexpected: {}", err),
+        };
+
+        let mut solver = Solver::new(&formula);
+        let model = solver.solve().unwrap();
+        assert!(solver.stats().decisions > 0);
+
+        let mut solver = Solver::new(&formula);
+        solver.warm_start(&model);
+        assert_eq!(solver.solve(), Some(model));
+        assert_eq!(solver.stats().decisions, 0);
+    }
+
+    #[test]
+    fn test_solver_warm_start_partial() {
+        // Variables 2 and 3 are unconstrained and not in the previous model.
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Not(0), Not(1)],
+            vec![Var(3), Not(3)],
+        ];
+
+        let mut solver = Solver::new(&formula).with_default_polarity(Polarity::True);
+        solver.warm_start(&vec![false, true]);
+        assert_eq!(solver.solve(), Some(vec![false, true, true, true]));
+    }
+
+    #[test]
+    fn test_solver_implication_graph() {
+        let formula = vec![
+            vec![Not(0), Var(1)],
+            vec![Not(1), Var(2)],
+            vec![Not(0), Not(2), Var(3)],
+            vec![Var(4), Var(5)],
+            vec![Not(3), Not(1), Not(5)],
+        ];
+        let mut solver = Solver::new(&formula);
+        assert!(solver.decide_and_propagate(&[Var(0)]));
+
+        let graph = solver.implication_graph();
+        let literals: Vec<Literal> = graph.nodes.iter().map(|node| node.literal).collect();
+        assert_eq!(
+            literals,
+            vec![Var(0), Var(1), Var(2), Var(3), Not(5), Var(4)]
+        );
+        assert!(graph.nodes.iter().all(|node| node.level == 1));
+        assert_eq!(graph.nodes[0].reason, None);
+        assert!(graph.conflict.is_none());
+
+        let edge = |from, to| ImplicationEdge { from, to };
+        for expect in &[
+            edge(Var(0), Var(1)),
+            edge(Var(1), Var(2)),
+            edge(Var(0), Var(3)),
+            edge(Var(2), Var(3)),
+            edge(Var(3), Not(5)),
+            edge(Var(1), Not(5)),
+            edge(Not(5), Var(4)),
+        ] {
+            assert!(graph.edges.contains(expect), "{:?}", expect);
+        }
+        assert_eq!(graph.edges.len(), 7);
+
+        // Deciding 5 first makes the last clause falsified.
+        let mut solver = Solver::new(&formula);
+        assert!(!solver.decide_and_propagate(&[Var(5), Var(0)]));
+        let graph = solver.implication_graph();
+        let conflict = graph.conflict.unwrap();
+        assert!(formula.iter().any(|clause| clause.len() == conflict.len()
+            && clause.iter().all(|lit| conflict.contains(lit))));
+        for lit in &conflict {
+            assert!(graph.nodes.iter().any(|node| node.literal == lit.negate()));
+        }
+        assert_eq!(graph.nodes[0].level, 1);
+        assert_eq!(graph.nodes.last().unwrap().level, 2);
+
+        // The search starts over from the level-zero assignments.
+        let vars = solver.solve().unwrap();
+        assert!(sat::verify(&formula, &vars));
+        assert!(solver.implication_graph().nodes.is_empty());
+    }
+
+    #[test]
+    fn test_solver_decide_and_propagate_then_solve() {
+        // Propagation alone refutes the formula.
+        let formula = vec![vec![Var(0)], vec![Not(0), Var(1)], vec![Not(1)]];
+        let mut solver = Solver::new(&formula);
+        assert!(!solver.decide_and_propagate(&[]));
+        assert!(solver.implication_graph().conflict.is_some());
+        assert_eq!(solver.solve(), None);
+        assert_eq!(solver.solve_under_assumptions(&[Var(1)]), None);
+        assert!(!solver.decide_and_propagate(&[]));
+
+        // A conflict above level zero leaves the level-zero assignments to be
+        // propagated again by the search.
+        let formula = vec![
+            vec![Var(0)],
+            vec![Not(0), Var(1)],
+            vec![Not(2), Var(3)],
+            vec![Not(2), Not(3)],
+            vec![Not(1), Var(2), Var(4)],
+        ];
+        let mut solver = Solver::new(&formula);
+        assert!(!solver.decide_and_propagate(&[Var(2)]));
+        let vars = solver.solve().unwrap();
+        assert!(sat::verify(&formula, &vars));
+
+        // Also under assumptions.
+        let mut solver = Solver::new(&formula);
+        assert!(!solver.decide_and_propagate(&[Var(2)]));
+        assert_eq!(solver.solve_under_assumptions(&[Not(4)]), None);
+        let vars = solver.solve_under_assumptions(&[Var(4)]).unwrap();
+        assert!(sat::verify(&formula, &vars));
+    }
+
+    #[test]
+    fn test_solver_solve_with_trace() {
+        let mut formulas = vec![vec![
+            vec![Not(0), Var(1)],
+            vec![Var(2)],
+            vec![Not(1), Not(2), Var(3)],
+            vec![Var(0), Var(3), Not(4)],
+        ]];
+        for source in &[
+            include_str!("../examples/negative_3.cnf"),
+            include_str!("../examples/qg3-08.cnf"),
+        ] {
+            formulas.push(dimacs::load(&mut source.as_bytes(), true).unwrap());
+        }
+
+        // Satisfiable random 3-SAT, on some of which the search learns units.
+        let mut rng = Rng::new(6);
+        while formulas.len() < 30 {
+            let formula = random_3sat(&mut rng, 20, 85);
+            if Solver::new(&formula).solve().is_some() {
+                formulas.push(formula);
+            }
+        }
+
+        let mut learned_units = 0;
+        for formula in &formulas {
+            let (vars, trace) = Solver::new(formula).solve_with_trace().unwrap();
+            assert!(sat::verify(formula, &vars));
+
+            // Replay the trace, checking that every propagated literal is
+            // forced by its reason.
+            let mut replayed: Vec<Option<bool>> = vec![None; vars.len()];
+            let mut level = 0;
+            for entry in &trace.entries {
+                let lit = entry.literal;
+                assert_eq!(replayed[lit.variable()], None);
+                match &entry.reason {
+                    Some(reason) => {
+                        if reason.len() == 1 && !formula.contains(reason) {
+                            learned_units += 1;
+                        }
+                        assert_eq!(entry.level, level);
+                        assert!(reason.contains(&lit));
+                        for other in reason.iter().filter(|&&other| other != lit) {
+                            let truth = replayed[other.variable()];
+                            assert_eq!(truth, Some(matches!(other, Not(_))));
+                        }
+                    }
+                    None => {
+                        level += 1;
+                        assert_eq!(entry.level, level);
+                    }
+                }
+                replayed[lit.variable()] = Some(matches!(lit, Var(_)));
+            }
+
+            let replayed: Vec<bool> = replayed.into_iter().map(Option::unwrap).collect();
+            assert_eq!(replayed, vars);
+        }
+        assert!(learned_units > 0);
+
+        // No trace for an unsatisfiable formula.
+        assert_eq!(Solver::new(&pigeonhole(3)).solve_with_trace(), None);
+    }
+
+    #[test]
+    fn test_solver_reset() {
+        let source = include_str!("../examples/qg3-08.cnf");
+        let first = dimacs::load(&mut source.as_bytes(), true).unwrap();
+
+        let mut rng = Rng::new(4);
+        let mut formulas = vec![pigeonhole(4)];
+        for _ in 0..10 {
+            let formula: Formula = random_3sat(&mut rng, 14, 60);
+            formulas.push(formula);
+        }
+
+        let configure = |solver: Solver| {
+            solver
+                .with_seed(5)
+                .with_default_polarity(Polarity::Random)
+                .with_proof()
+        };
+
+        // Reset after a search that learned clauses and left a model.
+        let mut solver = configure(Solver::new(&first));
+        assert!(solver.solve().is_some());
+
+        for formula in formulas {
+            let mut fresh = configure(Solver::new(&formula));
+            let expect = fresh.solve();
+
+            solver.reset(formula.clone());
+            assert_eq!(solver.clauses().count(), fresh.clauses().count());
+            assert_eq!(solver.num_learned(), 0);
+            assert_eq!(solver.stats(), &Stats::default());
+
+            assert_eq!(solver.solve(), expect);
+            assert_eq!(solver.stats(), fresh.stats());
+            if expect.is_none() {
+                let proof = solver.unsat_proof().unwrap();
+                assert!(verify_unsat(&formula, &proof));
+            }
+        }
+
+        // Reset to a smaller and then larger formula, and from inconsistent.
+        solver.reset(vec![vec![]]);
+        assert_eq!(solver.solve(), None);
+        solver.reset(first.clone());
+        let vars = solver.solve().unwrap();
+        assert!(sat::verify(&first, &vars));
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_solver_log_events() {
+        use std::sync::Mutex;
+
+        /// Logger keeping the messages of the solver.
+        struct Capture(Mutex<Vec<String>>);
+
+        impl log::Log for Capture {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                if record.target() == module_path!().trim_end_matches("::test") {
+                    self.0.lock().unwrap().push(format!("{}", record.args()));
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let mut solver = Solver::new(&pigeonhole(3));
+        assert!(solver.solve().is_none());
+
+        let messages = CAPTURE.0.lock().unwrap();
+        assert!(messages.iter().any(|m| m.starts_with("decide ")));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("conflict in clause ")));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("search finished: unsat")));
+    }
+
+    #[cfg(feature = "search-tree")]
+    #[test]
+    fn test_solver_search_tree_dot() {
+        // Deciding 0 false fails, and 0 true leaves 1, whose saved phase is
+        // true from the conflict, and 2 to decide.
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Var(0), Not(1)],
+            vec![Var(2), Var(3)],
+        ];
+        let mut solver = Solver::new(&formula)
+            .with_learning(false)
+            .with_decision_order(vec![0, 1, 2, 3])
+            .with_search_tree();
+        assert!(solver.solve().is_some());
+
+        let dot = solver.search_tree_dot();
+        assert!(dot.starts_with("digraph search {\n"));
+        assert!(dot.ends_with("}\n"));
+        let decisions: Vec<&str> = dot
+            .lines()
+            .filter(|line| line.contains("[label=") && !line.contains("shape=box"))
+            .filter(|line| !line.contains("root"))
+            .collect();
+        assert_eq!(
+            decisions,
+            vec![
+                "  n1 [label=\"-1\"];",
+                "  n3 [label=\"1\"];",
+                "  n4 [label=\"2\"];",
+                "  n5 [label=\"-3\"];",
+            ]
+        );
+        assert_eq!(dot.matches("\"conflict\"").count(), 1);
+        assert_eq!(dot.matches("\"sat\"").count(), 1);
+        for edge in &[
+            "n0 -> n1", "n1 -> n2", "n0 -> n3", "n3 -> n4", "n4 -> n5", "n5 -> n6",
+        ] {
+            assert!(dot.contains(edge), "missing {}", edge);
+        }
+
+        // Without recording, the tree is the root alone.
+        let mut solver = Solver::new(&formula);
+        assert!(solver.solve().is_some());
+        assert_eq!(
+            solver.search_tree_dot(),
+            "digraph search {\n  n0 [label=\"root\"];\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_solver_with_decision_order() {
+        // Exactly one of 0 and 1 is true, and the first decided gets false.
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Not(0), Not(1)],
+            vec![Var(0), Var(1), Var(2)],
+        ];
+
+        let mut solver = Solver::new(&formula).with_decision_order(vec![0, 1]);
+        assert_eq!(solver.solve(), Some(vec![false, true, false]));
+        assert_eq!(solver.stats().decisions, 2);
+
+        let mut solver = Solver::new(&formula).with_decision_order(vec![9, 1, 2, 0]);
+        assert_eq!(solver.solve(), Some(vec![true, false, false]));
+
+        let mut solver = Solver::new(&formula)
+            .with_default_polarity(Polarity::True)
+            .with_decision_order(vec![2, 1]);
+        assert_eq!(solver.solve(), Some(vec![false, true, true]));
+
+        // Verdicts are unchanged.
+        let mut rng = Rng::new(3);
+        for _ in 0..20 {
+            let formula: Formula = random_3sat(&mut rng, 10, 45);
+            let expect = sat::check_sat(&formula).is_some();
+
+            let order = (0..10).rev().collect();
+            let mut solver = Solver::new(&formula).with_decision_order(order);
+            match solver.solve() {
+                Some(vars) => {
+                    assert!(expect);
+                    assert!(sat::verify(&formula, &vars));
+                }
+                None => assert!(!expect),
+            }
+        }
+    }
+
+    #[test]
+    fn test_solver_solve_steps() {
+        let formula = vec![vec![Var(0), Var(1)], vec![Not(0), Not(1)]];
+        let mut solver = Solver::new(&formula);
+        let mut steps = solver.solve_steps();
+
+        let state = |values: &[Option<bool>], action| PartialState {
+            values: values.to_vec(),
+            action,
+        };
+        assert_eq!(
+            steps.next(),
+            Some(state(&[Some(false), None], Action::Decide(Not(0))))
+        );
+        assert_eq!(
+            steps.next(),
+            Some(state(
+                &[Some(false), Some(true)],
+                Action::Propagate(vec![Var(1)])
+            ))
+        );
+        assert_eq!(
+            steps.next(),
+            Some(state(
+                &[Some(false), Some(true)],
+                Action::Finish(Outcome::Sat(vec![false, true]))
+            ))
+        );
+        assert_eq!(steps.next(), None);
+
+        let formula = vec![vec![Var(0)], vec![Not(0)]];
+        let mut solver = Solver::new(&formula);
+        let actions: Vec<Action> = solver.solve_steps().map(|state| state.action).collect();
+        assert_eq!(actions, vec![Action::Finish(Outcome::Unsat)]);
+    }
+
+    #[test]
+    fn test_solver_solve_steps_model() {
+        let source = include_str!("../examples/qg3-08.cnf");
+        let formula = dimacs::load(&mut source.as_bytes(), true).unwrap();
+
+        let mut solver = Solver::new(&formula);
+        let states: Vec<PartialState> = solver.solve_steps().collect();
+        assert!(states
+            .iter()
+            .any(|state| matches!(state.action, Action::Backtrack(_))));
+
+        let last = states.last().unwrap();
+        let model = Solver::new(&formula).solve().unwrap();
+        assert_eq!(last.action, Action::Finish(Outcome::Sat(model.clone())));
+        let values: Vec<Option<bool>> = model.iter().map(|&truth| Some(truth)).collect();
+        assert_eq!(last.values, values);
+
+        // The steps are the search of `solve`.
+        let decisions = states
+            .iter()
+            .filter(|state| matches!(state.action, Action::Decide(_)))
+            .count();
+        assert_eq!(decisions as u64, solver.stats().decisions);
+    }
+
+    #[test]
+    fn test_solver_without_learning() {
+        let mut formulas = vec![pigeonhole(3), pigeonhole(4)];
+        for source in &[
+            include_str!("../examples/negative_3.cnf"),
+            include_str!("../examples/network_8.cnf"),
+            include_str!("../examples/qg3-08.cnf"),
+        ] {
+            // The header of network_8 declares one clause more than it has.
+            let (formula, _) = dimacs::load_lenient(&mut source.as_bytes(), true).unwrap();
+            formulas.push(formula);
+        }
+
+        // Random 3-SAT around the threshold ratio, both SAT and UNSAT.
+        let mut rng = Rng::new(7);
+        for _ in 0..20 {
+            let formula: Formula = random_3sat(&mut rng, 20, 85);
+            formulas.push(formula);
+        }
+
+        for formula in &formulas {
+            let mut solver = Solver::new(formula).with_learning(false);
+            let plain = solver.solve();
+            let cdcl = Solver::new(formula).solve();
+            assert_eq!(plain.is_some(), cdcl.is_some());
+            if let Some(vars) = &plain {
+                assert!(sat::verify(formula, vars));
+            }
+            assert_eq!(solver.num_learned(), 0);
+            assert_eq!(solver.stats().learned, 0);
+        }
+
+        // Assumptions are not flipped.
+        let formula = vec![vec![Var(0), Var(1)], vec![Var(0), Not(1)]];
+        let mut solver = Solver::new(&formula).with_learning(false);
+        assert_eq!(solver.solve_under_assumptions(&[Not(0)]), None);
+        assert_eq!(solver.solve().map(|vars| vars[0]), Some(true));
+    }
+
+    #[test]
+    fn test_solver_solved_by_propagation_only() {
+        // A Horn formula whose facts force every variable.
+        let formula = vec![
+            vec![Var(0)],
+            vec![Not(0), Var(1)],
+            vec![Not(0), Not(1), Var(2)],
+            vec![Not(2), Not(3)],
+        ];
+        assert!(sat::is_horn(&formula));
+        let mut solver = Solver::new(&formula);
+        assert_eq!(solver.solve(), Some(vec![true, true, true, false]));
+        assert_eq!(solver.stats().decisions, 0);
+        assert!(solver.stats().solved_by_propagation_only());
+
+        let mut solver = Solver::new(&pigeonhole(3));
+        assert_eq!(solver.solve(), None);
+        assert!(!solver.stats().solved_by_propagation_only());
+    }
+
+    #[test]
+    fn test_solver_unsat_proof() {
+        // The unsatisfiable 3-variable example of sat::check_sat: every two of
+        // the variables differ.
+        let mut formulas = vec![vec![
+            vec![Var(0), Var(1)],
+            vec![Not(0), Not(1)],
+            vec![Var(1), Var(2)],
+            vec![Not(1), Not(2)],
+            vec![Var(2), Var(0)],
+            vec![Not(2), Not(0)],
+        ]];
+        formulas.push(pigeonhole(3));
+        formulas.push(pigeonhole(4));
+        // Found false while adding the clauses, with duplicate literals.
+        formulas.push(vec![
+            vec![Var(0), Var(0)],
+            vec![Not(1), Not(0)],
+            vec![Var(1)],
+        ]);
+        formulas.push(vec![vec![Var(0)], vec![]]);
+
+        for formula in &formulas {
+            let mut solver = Solver::new(formula).with_proof();
+            assert_eq!(solver.solve(), None);
+            let proof = solver.unsat_proof().unwrap();
+            assert!(verify_unsat(formula, &proof));
+
+            // Solving again does not lose the proof.
+            assert_eq!(solver.solve(), None);
+            assert_eq!(solver.unsat_proof(), Some(proof));
+        }
+
+        // Random 3-SAT beyond the threshold ratio, mostly UNSAT.
+        let mut rng = Rng::new(11);
+        for _ in 0..20 {
+            let formula: Formula = random_3sat(&mut rng, 20, 120);
+            let mut solver = Solver::new(&formula).with_proof();
+            if solver.solve().is_none() {
+                assert!(verify_unsat(&formula, &solver.unsat_proof().unwrap()));
+            }
+        }
+
+        // Learned clauses are derived as well with a smaller database.
+        let formula = pigeonhole(5);
+        let mut solver = Solver::new(&formula).with_max_learned(10).with_proof();
+        assert_eq!(solver.solve(), None);
+        assert!(solver.stats().deleted > 0);
+        assert!(verify_unsat(&formula, &solver.unsat_proof().unwrap()));
+
+        // No proof without recording, if satisfiable, or only under
+        // assumptions.
+        let mut solver = Solver::new(&formulas[0]);
+        assert_eq!(solver.solve(), None);
+        assert_eq!(solver.unsat_proof(), None);
+
+        let formula = vec![vec![Var(0), Var(1)], vec![Not(0)]];
+        let mut solver = Solver::new(&formula).with_proof();
+        assert!(solver.solve().is_some());
+        assert_eq!(solver.solve_under_assumptions(&[Not(1)]), None);
+        assert_eq!(solver.unsat_proof(), None);
+    }
+
+    #[test]
+    fn test_verify_unsat() {
+        let formula = vec![
+            vec![Var(0), Var(1)],
+            vec![Var(0), Not(1)],
+            vec![Not(0), Var(1)],
+            vec![Not(0), Not(1)],
+        ];
+        let step = |left, right, pivot| Resolution { left, right, pivot };
+        let proof = Proof {
+            steps: vec![step(0, 1, 1), step(2, 3, 1), step(4, 5, 0)],
+        };
+        assert!(verify_unsat(&formula, &proof));
+
+        // Not reaching the empty clause.
+        let partial = Proof {
+            steps: proof.steps[..2].to_vec(),
+        };
+        assert!(!verify_unsat(&formula, &partial));
+
+        // Wrong pivot, tautological resolvent and reference to a later step.
+        for corrupt in &[step(0, 1, 0), step(0, 3, 0), step(4, 6, 0)] {
+            let mut corrupted = proof.clone();
+            corrupted.steps[0] = *corrupt;
+            assert!(!verify_unsat(&formula, &corrupted), "{:?}", corrupt);
+        }
+
+        // A proof recorded by the solver fails when a step is corrupted.
+        let formula = pigeonhole(3);
+        let mut solver = Solver::new(&formula).with_proof();
+        assert_eq!(solver.solve(), None);
+        let mut proof = solver.unsat_proof().unwrap();
+        let last = proof.steps.len() - 1;
+        proof.steps[last].pivot = formula.len();
+        assert!(!verify_unsat(&formula, &proof));
+
+        // The empty clause in the formula needs no step.
+        assert!(verify_unsat(&vec![vec![Var(0)], vec![]], &Proof::default()));
+        assert!(!verify_unsat(&vec![vec![Var(0)]], &Proof::default()));
+    }
+
+    #[test]
+    fn test_solver_clauses() {
+        let formula = vec![
+            vec![Var(0), Not(1)],
+            vec![Var(2)],
+            vec![Var(1), Var(2), Var(1)],
+            vec![Var(0), Not(0)],
+        ];
+        let solver = Solver::new(&formula);
+
+        let clauses: Vec<&[Literal]> = solver.clauses().collect();
+        assert_eq!(
+            clauses,
+            vec![&[Var(0), Not(1)][..], &[Var(2)][..], &[Var(1), Var(2)][..]]
+        );
+
+        // Learned clauses are not included.
+        let mut solver = Solver::new(&pigeonhole(4));
+        assert!(solver.solve().is_none());
+        assert!(solver.num_learned() > 0);
+        assert_eq!(solver.clauses().count(), pigeonhole(4).len());
+    }
+}
@@ -0,0 +1,359 @@
+use super::sat::{self, Assignment, Clause, Formula, Literal, Not, Var};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Solver accepting the clauses of a formula one by one.
+///
+/// Unit propagation runs as clauses arrive: each clause is reduced by the
+/// assignments implied so far, satisfied clauses are dropped, and new unit
+/// clauses are propagated into the clauses kept. So the solver holds only the
+/// part of the formula that is still undetermined, and a conflict found early
+/// makes the rest of the input irrelevant.
+///
+/// The kept clauses are indexed by their literals, so propagating a unit only
+/// visits the clauses containing its variable, and the total work of
+/// propagation is linear in the size of the input.
+///
+/// Deciding unsatisfiability needs the whole formula, so the search is done by
+/// `finish`. Pure literal elimination is also deferred until then since a later
+/// clause may contain the opposite literal.
+#[derive(Debug, Default)]
+pub struct StreamingSolver {
+    clauses: Formula,
+    // Number of unassigned literals of each clause, or None once satisfied.
+    n_free: Vec<Option<usize>>,
+    // Indices of the clauses containing each literal, indexed by code.
+    occurrences: Vec<Vec<usize>>,
+    // Number of clauses not satisfied.
+    n_open: usize,
+    vars: Vec<Option<bool>>,
+    conflict: bool,
+}
+
+impl StreamingSolver {
+    /// Creates a solver with no clause.
+    pub fn new() -> StreamingSolver {
+        StreamingSolver::default()
+    }
+
+    /// Adds a clause and propagates the unit clauses it gives rise to.
+    pub fn feed_clause(&mut self, clause: &[Literal]) {
+        if self.conflict {
+            return;
+        }
+
+        for lit in clause {
+            if lit.variable() >= self.vars.len() {
+                self.vars.resize(lit.variable() + 1, None);
+                self.occurrences.resize(2 * self.vars.len(), Vec::new());
+            }
+        }
+
+        if let Some(clause) = self.reduce(clause) {
+            match clause.len() {
+                0 => self.conflict = true,
+                1 => self.propagate(clause[0]),
+                _ => {
+                    let index = self.clauses.len();
+                    for lit in &clause {
+                        self.occurrences[lit.code()].push(index);
+                    }
+                    self.n_free.push(Some(clause.len()));
+                    self.n_open += 1;
+                    self.clauses.push(clause);
+                }
+            }
+        }
+    }
+
+    /// Returns the value of a variable implied by the clauses fed so far, or
+    /// None if the variable is not determined by unit propagation.
+    pub fn value(&self, var: usize) -> Option<bool> {
+        self.vars.get(var).copied().flatten()
+    }
+
+    /// Returns the number of clauses kept, i.e., the clauses that are neither
+    /// satisfied nor reduced to a unit by the implied values.
+    pub fn num_clauses(&self) -> usize {
+        self.n_open
+    }
+
+    /// Returns true if the clauses fed so far are found to be unsatisfiable by
+    /// unit propagation.
+    pub fn is_conflicting(&self) -> bool {
+        self.conflict
+    }
+
+    /// Solves the formula made of all the clauses fed.
+    ///
+    /// Returns a variable assignment if the formula is satisfiable, or None if
+    /// the formula is unsatisfiable.
+    pub fn finish(mut self) -> Option<Assignment> {
+        if self.conflict {
+            return None;
+        }
+
+        // The kept clauses reduced to the undetermined variables, so a model
+        // of them extends the implied values.
+        let mut clauses: Formula = self
+            .clauses
+            .iter()
+            .zip(&self.n_free)
+            .filter(|(_, n_free)| n_free.is_some())
+            .map(|(clause, _)| {
+                let free = clause
+                    .iter()
+                    .filter(|&&lit| self.literal_value(lit).is_none());
+                free.copied().collect()
+            })
+            .collect();
+
+        self.eliminate_pure_literals(&mut clauses);
+
+        let model = sat::check_sat(&clauses)?;
+        let vars = self
+            .vars
+            .iter()
+            .enumerate()
+            .map(|(i, value)| value.unwrap_or_else(|| model.get(i) == Some(&true)))
+            .collect();
+
+        Some(vars)
+    }
+
+    /// Removes the false literals from a clause. Returns None if the clause is
+    /// satisfied by the implied values.
+    fn reduce(&self, clause: &[Literal]) -> Option<Clause> {
+        let mut reduced = Clause::new();
+        for &lit in clause {
+            match self.literal_value(lit) {
+                Some(true) => return None,
+                Some(false) => {}
+                None => {
+                    if !reduced.contains(&lit) {
+                        reduced.push(lit);
+                    }
+                }
+            }
+        }
+        Some(reduced)
+    }
+
+    fn literal_value(&self, lit: Literal) -> Option<bool> {
+        match lit {
+            Var(i) => self.vars[i],
+            Not(i) => self.vars[i].map(|truth| !truth),
+        }
+    }
+
+    /// Makes an unassigned literal true and propagates the consequences to
+    /// the kept clauses.
+    fn propagate(&mut self, lit: Literal) {
+        self.vars[lit.variable()] = Some(matches!(lit, Var(_)));
+        let mut units = vec![lit];
+
+        while let Some(lit) = units.pop() {
+            for &index in &self.occurrences[lit.code()] {
+                if self.n_free[index].take().is_some() {
+                    self.n_open -= 1;
+                }
+            }
+
+            for i in 0..self.occurrences[lit.negate().code()].len() {
+                let index = self.occurrences[lit.negate().code()][i];
+                let n_free = match &mut self.n_free[index] {
+                    Some(n_free) => {
+                        *n_free -= 1;
+                        *n_free
+                    }
+                    None => continue,
+                };
+
+                match n_free {
+                    0 => {
+                        self.conflict = true;
+                        return;
+                    }
+                    // The last literal not false may already be true, waiting
+                    // in `units` to satisfy the clause.
+                    1 => {
+                        let clause = &self.clauses[index];
+                        let unit = clause
+                            .iter()
+                            .copied()
+                            .find(|&other| self.literal_value(other) != Some(false));
+                        if let Some(unit) = unit {
+                            if self.literal_value(unit).is_none() {
+                                self.vars[unit.variable()] = Some(matches!(unit, Var(_)));
+                                units.push(unit);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Assigns the variables occurring in only one polarity in `clauses` so
+    /// that all the clauses containing them are satisfied, and removes them.
+    fn eliminate_pure_literals(&mut self, clauses: &mut Formula) {
+        loop {
+            let mut positive = vec![false; self.vars.len()];
+            let mut negative = vec![false; self.vars.len()];
+            for lit in clauses.iter().flatten() {
+                match *lit {
+                    Var(i) => positive[i] = true,
+                    Not(i) => negative[i] = true,
+                }
+            }
+
+            let mut changed = false;
+            for i in 0..self.vars.len() {
+                if positive[i] != negative[i] {
+                    self.vars[i] = Some(positive[i]);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+
+            sat::remove_satisfied(clauses, &self.vars);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dimacs;
+    use crate::testing::random_3sat;
+
+    #[test]
+    fn test_streaming_solver_feed_clause() {
+        let formula = vec![
+            vec![Var(0), Var(1), Var(2)],
+            vec![Not(0), Var(2), Var(3)],
+            vec![Not(1), Not(3)],
+            vec![Not(2)],
+            vec![Var(0), Not(3)],
+            vec![Var(3), Var(4), Not(5)],
+            vec![Not(4), Var(5)],
+        ];
+
+        let mut solver = StreamingSolver::new();
+        let mut kept = Vec::new();
+        for clause in &formula {
+            solver.feed_clause(clause);
+            kept.push(solver.num_clauses());
+        }
+
+        // !2 reduces the first two clauses, which are kept as binary ones.
+        assert_eq!(kept, vec![1, 2, 3, 3, 4, 5, 6]);
+        assert_eq!(solver.value(2), Some(false));
+        assert!(!solver.is_conflicting());
+
+        let vars = solver.finish().unwrap();
+        assert!(sat::verify(&formula, &vars));
+    }
+
+    #[test]
+    fn test_streaming_solver_propagation() {
+        // A chain of implications is resolved as the clauses arrive.
+        let mut solver = StreamingSolver::new();
+        solver.feed_clause(&[Not(0), Var(1)]);
+        solver.feed_clause(&[Not(1), Var(2)]);
+        solver.feed_clause(&[Not(2), Not(3)]);
+        assert_eq!(solver.num_clauses(), 3);
+
+        solver.feed_clause(&[Var(0)]);
+        assert_eq!(solver.num_clauses(), 0);
+        assert_eq!(
+            (0..4).map(|i| solver.value(i)).collect::<Vec<_>>(),
+            vec![Some(true), Some(true), Some(true), Some(false)]
+        );
+
+        solver.feed_clause(&[Var(3), Var(1)]);
+        assert_eq!(solver.num_clauses(), 0);
+
+        solver.feed_clause(&[Var(3), Not(2)]);
+        assert!(solver.is_conflicting());
+        solver.feed_clause(&[Var(4)]);
+        assert_eq!(solver.finish(), None);
+
+        // A long chain is propagated by a single unit at the end.
+        let n = 100_000;
+        let mut solver = StreamingSolver::new();
+        for i in 0..n {
+            solver.feed_clause(&[Not(i), Var(i + 1)]);
+        }
+        solver.feed_clause(&[Var(0)]);
+        assert_eq!(solver.num_clauses(), 0);
+        assert!((0..=n).all(|i| solver.value(i) == Some(true)));
+    }
+
+    #[test]
+    fn test_streaming_solver_read_clauses() {
+        let source = include_str!("../examples/negative_3.cnf");
+        let mut solver = StreamingSolver::new();
+        let result = dimacs::read_clauses(&mut source.as_bytes(), true, &mut |clause| {
+            solver.feed_clause(&clause)
+        });
+        if let Err(err) = result {
+            panic!("unexpected: {}", err);
+        }
+
+        let formula = match dimacs::load(&mut source.as_bytes(), true) {
+            Ok(formula) => formula,
+            Err(err) => panic!("unexpected: {}", err),
+        };
+        assert!(sat::verify(&formula, &solver.finish().unwrap()));
+    }
+
+    #[test]
+    fn test_streaming_solver_agrees_with_check_sat() {
+        let mut formulas = vec![
+            vec![],
+            vec![vec![]],
+            vec![vec![Var(0), Var(0)], vec![Not(0), Var(1)]],
+            vec![
+                vec![Var(0), Var(1)],
+                vec![Var(0), Not(1)],
+                vec![Not(0), Var(1)],
+                vec![Not(0), Not(1)],
+            ],
+            vec![
+                vec![Var(0), Var(1), Var(2)],
+                vec![Not(0), Not(1)],
+                vec![Not(1), Not(2)],
+                vec![Not(0), Not(2)],
+                vec![Var(1), Not(3), Var(4)],
+            ],
+        ];
+
+        // Random 3-SAT with units mixed in, both SAT and UNSAT.
+        let mut rng = crate::rng::Rng::new(8);
+        for _ in 0..100 {
+            let mut formula = random_3sat(&mut rng, 12, 45);
+            for i in 0..3 {
+                let lit = formula[10 * i][0];
+                formula.insert(15 * i, vec![lit]);
+            }
+            formulas.push(formula);
+        }
+
+        for formula in &formulas {
+            let mut solver = StreamingSolver::new();
+            for clause in formula {
+                solver.feed_clause(clause);
+            }
+            let result = solver.finish();
+
+            assert_eq!(result.is_some(), sat::check_sat(formula).is_some());
+            if let Some(vars) = result {
+                assert!(sat::verify(formula, &vars));
+            }
+        }
+    }
+}
@@ -0,0 +1,24 @@
+//! Helpers shared by the tests of the modules.
+
+use super::rng::Rng;
+use super::sat::{Formula, Not, Var};
+
+/// Generates a random 3-SAT formula of `n_clauses` clauses over `n_vars`
+/// variables. A literal may repeat in a clause and a clause may contain both
+/// polarities of a variable.
+pub fn random_3sat(rng: &mut Rng, n_vars: usize, n_clauses: usize) -> Formula {
+    (0..n_clauses)
+        .map(|_| {
+            (0..3)
+                .map(|_| {
+                    let var = (rng.next_u64() % n_vars as u64) as usize;
+                    if rng.next_bool() {
+                        Var(var)
+                    } else {
+                        Not(var)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,455 @@
+use dpll_sat::{cli, dimacs, sat};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::sync::atomic::AtomicBool;
+
+fn dpll_sat(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_dpll-sat"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Calls `cli::run` with in-memory streams and returns the exit code, stdout
+/// and stderr.
+fn run(args: &[&str], input: &str) -> (i32, String, String) {
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let args = args.iter().map(|s| s.to_string());
+    let code = cli::run(args, &mut input.as_bytes(), &mut out, &mut err);
+    (
+        code,
+        String::from_utf8(out).unwrap(),
+        String::from_utf8(err).unwrap(),
+    )
+}
+
+/// Encodes bytes in base64 with padding.
+fn encode_base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+            bits | (byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[test]
+fn test_cnf_base64() {
+    let cnf = "p cnf 3 3\n1 2 0\n-1 0\n-2 3 0\n";
+    let output = dpll_sat(&["--cnf-base64", &encode_base64(cnf.as_bytes())]);
+
+    assert_eq!(output.status.code(), Some(cli::EXIT_SAT));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "sat\n-1 2 3\n");
+}
+
+#[test]
+fn test_cnf_base64_invalid() {
+    let output = dpll_sat(&["--cnf-base64", "not base64!"]);
+
+    assert_eq!(output.status.code(), Some(cli::EXIT_ERROR));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid base64"));
+}
+
+#[test]
+fn test_stats() {
+    let cnf = "p cnf 4 4\n1 2 0\n-1 0\n-2 3 0\n1 -3 4 0\n";
+    let output = dpll_sat(&["--stats", "--cnf-base64", &encode_base64(cnf.as_bytes())]);
+
+    assert_eq!(output.status.code(), Some(cli::EXIT_SAT));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "sat\n-1 2 3 4\n");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let expect = "variables: 4\n\
+                  clauses: 4\n\
+                  clauses of length 1: 1\n\
+                  clauses of length 2: 2\n\
+                  clauses of length 3: 1\n";
+    assert_eq!(stderr, expect);
+}
+
+#[test]
+fn test_run_stats_json() {
+    let cnf = "p cnf 3 4\n1 2 0\n-1 2 0\n1 -2 0\n-1 -2 3 0\n";
+    let (code, out, err) = run(&["--stats-json"], cnf);
+    assert_eq!(code, cli::EXIT_SAT);
+    assert_eq!(out, "sat\n1 2 3\n");
+
+    // A single object with the keys in order.
+    assert_eq!(err.lines().count(), 1);
+    let json = err.trim_end();
+    assert!(json.starts_with('{') && json.ends_with('}'));
+    let keys: Vec<&str> = json[1..json.len() - 1]
+        .split(',')
+        .map(|pair| pair.split(':').next().unwrap())
+        .collect();
+    let expect = [
+        "\"outcome\"",
+        "\"decisions\"",
+        "\"propagations\"",
+        "\"conflicts\"",
+        "\"max_depth\"",
+        "\"elapsed\"",
+    ];
+    assert_eq!(keys, expect);
+    assert!(json.contains("\"outcome\":\"sat\""));
+
+    let (code, _, err) = run(&["--stats-json"], "p cnf 1 2\n1 0\n-1 0\n");
+    assert_eq!(code, cli::EXIT_UNSAT);
+    assert!(err.starts_with("{\"outcome\":\"unsat\",\"decisions\":0,"));
+}
+
+#[test]
+fn test_run_analyze() {
+    // 1 is negative only, 4 is positive only and 2 is unused.
+    let cnf = "p cnf 5 4\n-1 3 0\n3 -5 0\n-3 5 4 0\n-1 0\n";
+    let (code, out, err) = run(&["--analyze"], cnf);
+    assert_eq!(code, cli::EXIT_SAT);
+    assert!(out.starts_with("sat\n"));
+    assert_eq!(
+        err,
+        "positive only: 1\n\
+         negative only: 1\n\
+         both polarities: 2\n\
+         unit clauses: 1\n\
+         horn: no\n\
+         2-sat: no\n"
+    );
+
+    let (_, _, err) = run(&["--analyze", "-q"], "p cnf 2 2\n1 -2 0\n-1 0\n");
+    assert!(err.ends_with("horn: yes\n2-sat: yes\n"));
+}
+
+#[test]
+fn test_run_sat() {
+    let (code, out, err) = run(&[], "p cnf 3 3\n1 2 0\n-1 0\n-2 3 0\n");
+    assert_eq!(code, 10);
+    assert_eq!(out, "sat\n-1 2 3\n");
+    assert_eq!(err, "");
+}
+
+#[test]
+fn test_run_unsat() {
+    let (code, out, err) = run(&[], "p cnf 1 2\n1 0\n-1 0\n");
+    assert_eq!(code, 20);
+    assert_eq!(out, "unsat\n");
+    assert_eq!(err, "");
+}
+
+#[test]
+fn test_run_parse_error() {
+    let (code, out, err) = run(&[], "p cnf 1 1\n1 x 0\n");
+    assert_eq!(code, 1);
+    assert_eq!(out, "");
+    assert_eq!(err, "error: bad clause\n");
+
+    let (code, out, err) = run(&["--bogus"], "");
+    assert_eq!(code, 1);
+    assert_eq!(out, "");
+    assert_eq!(err, "error: unknown argument: --bogus\n");
+}
+
+#[test]
+fn test_run_quiet() {
+    let (code, out, _) = run(&["--quiet"], "p cnf 1 1\n1 0\n");
+    assert_eq!(code, 10);
+    assert_eq!(out, "");
+
+    let (code, out, _) = run(&["-q"], "p cnf 1 2\n1 0\n-1 0\n");
+    assert_eq!(code, 20);
+    assert_eq!(out, "");
+}
+
+#[test]
+fn test_run_heuristic() {
+    let cnf = "p cnf 3 4\n1 2 3 0\n-1 -2 0\n-2 -3 0\n-1 -3 0\n";
+    let (code, out, _) = run(&["--heuristic", "jeroslow-wang"], cnf);
+    assert_eq!(code, 10);
+    assert!(out.starts_with("sat\n"));
+
+    let (code, _, err) = run(&["--heuristic", "bogus"], cnf);
+    assert_eq!(code, 1);
+    assert_eq!(err, "error: unknown heuristic: bogus\n");
+}
+
+#[test]
+fn test_exit_codes() {
+    let sat = "p cnf 2 2\n1 2 0\n-1 0\n";
+    let output = dpll_sat(&["--cnf-base64", &encode_base64(sat.as_bytes())]);
+    assert_eq!(output.status.code(), Some(10));
+
+    let unsat = "p cnf 2 3\n1 2 0\n-1 0\n-2 0\n";
+    let output = dpll_sat(&["--cnf-base64", &encode_base64(unsat.as_bytes())]);
+    assert_eq!(output.status.code(), Some(20));
+
+    let malformed = "p cnf 2 1\n1 two 0\n";
+    let output = dpll_sat(&["--cnf-base64", &encode_base64(malformed.as_bytes())]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = dpll_sat(&["--no-such-option"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_multiple_files() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let header = dir.join("multiple_files_header.cnf");
+    let clauses = dir.join("multiple_files_clauses.cnf");
+
+    // The last line of the first file lacks a newline.
+    fs::write(&header, "c header and one clause\np cnf 3 3\n1 2 0").unwrap();
+    fs::write(&clauses, "c the rest\n-1 0\n-2 3 0\n").unwrap();
+
+    let header = header.to_str().unwrap();
+    let clauses = clauses.to_str().unwrap();
+
+    let output = dpll_sat(&[header, clauses]);
+    assert_eq!(output.status.code(), Some(cli::EXIT_SAT));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "sat\n-1 2 3\n");
+
+    // The clause count covers all the files.
+    let output = dpll_sat(&[header]);
+    assert_eq!(output.status.code(), Some(cli::EXIT_ERROR));
+
+    let output = dpll_sat(&[header, "no-such-file.cnf"]);
+    assert_eq!(output.status.code(), Some(cli::EXIT_ERROR));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: no-such-file.cnf: "));
+}
+
+#[test]
+fn test_multiple_files_with_headers() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let first = dir.join("multiple_headers_first.cnf");
+    let second = dir.join("multiple_headers_second.cnf");
+
+    // The headers merge into one of 3 variables and 3 clauses.
+    fs::write(&first, "c first\np cnf 2 2\n1 2 0\n-1 0\n").unwrap();
+    fs::write(&second, "c second\n\np cnf 3 1\n-2 3 0\n").unwrap();
+
+    let first = first.to_str().unwrap();
+    let second = second.to_str().unwrap();
+
+    let output = dpll_sat(&[first, second]);
+    assert_eq!(output.status.code(), Some(cli::EXIT_SAT));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "sat\n-1 2 3\n");
+
+    let output = dpll_sat(&[second, first]);
+    assert_eq!(output.status.code(), Some(cli::EXIT_SAT));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "sat\n-1 2 3\n");
+
+    // A clause beyond the counts is still an error.
+    let extra = dir.join("multiple_headers_extra.cnf");
+    fs::write(&extra, "p cnf 3 0\n3 0\n").unwrap();
+    let output = dpll_sat(&[first, second, extra.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(cli::EXIT_ERROR));
+}
+
+#[test]
+fn test_run_interrupted() {
+    // Neither Horn nor 2-SAT, and unit propagation alone does not solve it.
+    let cnf = "p cnf 3 4\n1 2 3 0\n-1 -2 0\n-2 -3 0\n-1 -3 0\n";
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let args = Vec::<String>::new().into_iter();
+    let interrupt = AtomicBool::new(true);
+    let code = cli::run_with_interrupt(args, &mut cnf.as_bytes(), &mut out, &mut err, &interrupt);
+
+    assert_eq!(code, cli::EXIT_UNKNOWN);
+    assert_eq!(String::from_utf8(out).unwrap(), "unknown\n");
+    assert_eq!(
+        String::from_utf8(err).unwrap(),
+        "interrupted\ndecisions: 0\nmax depth: 0\n"
+    );
+
+    let interrupt = AtomicBool::new(false);
+    let code = cli::run_with_interrupt(
+        Vec::<String>::new().into_iter(),
+        &mut cnf.as_bytes(),
+        &mut Vec::new(),
+        &mut Vec::new(),
+        &interrupt,
+    );
+    assert_eq!(code, cli::EXIT_SAT);
+}
+
+#[test]
+fn test_run_time_limit() {
+    let cnf = fs::read_to_string("examples/qg3-08.cnf").unwrap();
+    let (code, out, err) = run(&["--time-limit", "0"], &cnf);
+    assert_eq!(code, cli::EXIT_UNKNOWN);
+    assert_eq!(out, "unknown\n");
+
+    // The deadline is noticed at the first decision, and the search stops
+    // before the next one.
+    assert_eq!(err, "time limit exceeded\ndecisions: 1\nmax depth: 1\n");
+
+    let (code, _, _) = run(&["--time-limit", "60"], "p cnf 2 2\n1 2 0\n-1 -2 0\n");
+    assert_eq!(code, cli::EXIT_SAT);
+
+    // A limit beyond the range of instants does not stop the search.
+    let (code, _, _) = run(&["--time-limit", "1e19"], "p cnf 2 2\n1 2 0\n-1 -2 0\n");
+    assert_eq!(code, cli::EXIT_SAT);
+
+    let (code, _, err) = run(&["--time-limit", "1e300"], "p cnf 2 2\n1 2 0\n-1 -2 0\n");
+    assert_eq!(code, cli::EXIT_ERROR);
+    assert!(
+        err.contains("--time-limit requires a number of seconds"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_var_map() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let map = dir.join("var_map.txt");
+    fs::write(&map, "# circuit signals\n1 reset\n3 signal_foo\n9 unused\n").unwrap();
+    let map = map.to_str().unwrap();
+
+    let (code, out, _) = run(&["--var-map", map], "p cnf 3 3\n1 2 0\n-1 0\n-2 3 0\n");
+    assert_eq!(code, cli::EXIT_SAT);
+    assert_eq!(out, "sat\n-1 2 3\nreset = false\nsignal_foo = true\n");
+
+    let bad = dir.join("var_map_bad.txt");
+    fs::write(&bad, "1 reset\nfoo bar\n").unwrap();
+    let bad = bad.to_str().unwrap();
+
+    let (code, out, err) = run(&["--var-map", bad], "p cnf 1 1\n1 0\n");
+    assert_eq!(code, cli::EXIT_ERROR);
+    assert_eq!(out, "");
+    assert_eq!(err, format!("error: {}: line 2: bad variable: foo\n", bad));
+}
+
+#[test]
+fn test_run_lenient() {
+    let too_few = "p cnf 2 3\n1 2 0\n-1 0\n";
+    let too_many = "p cnf 2 1\n1 2 0\n-1 0\n";
+
+    for cnf in &[too_few, too_many] {
+        let (code, out, err) = run(&[], cnf);
+        assert_eq!(code, cli::EXIT_ERROR);
+        assert_eq!(out, "");
+        assert_eq!(err, "error: unexpected number of clauses\n");
+
+        let (code, out, err) = run(&["--lenient"], cnf);
+        assert_eq!(code, cli::EXIT_SAT);
+        assert_eq!(out, "sat\n-1 2\n");
+        assert!(err.starts_with("warning: header declares "));
+    }
+
+    let no_keyword = "p 2 2\n1 2 0\n-1 0\n";
+    let (code, _, err) = run(&[], no_keyword);
+    assert_eq!(code, cli::EXIT_ERROR);
+    assert_eq!(err, "error: bad header\n");
+    let (code, out, _) = run(&["--lenient"], no_keyword);
+    assert_eq!(code, cli::EXIT_SAT);
+    assert_eq!(out, "sat\n-1 2\n");
+
+    // A count of zero is unknown, so no warning.
+    let unknown = "p cnf 2 0\n1 2 0\n-1 0\n";
+    let (code, _, err) = run(&[], unknown);
+    assert_eq!(code, cli::EXIT_ERROR);
+    assert_eq!(err, "error: unexpected number of clauses\n");
+    let (code, out, err) = run(&["--lenient"], unknown);
+    assert_eq!(code, cli::EXIT_SAT);
+    assert_eq!(out, "sat\n-1 2\n");
+    assert_eq!(err, "");
+}
+
+#[test]
+fn test_run_warn_width() {
+    let literals: Vec<String> = (1..=50).map(|i| i.to_string()).collect();
+    let cnf = format!("p cnf 50 3\n1 -2 0\n{} 0\n-1 0\n", literals.join(" "));
+
+    // Disabled by default.
+    let (code, _, err) = run(&[], &cnf);
+    assert_eq!(code, cli::EXIT_SAT);
+    assert_eq!(err, "");
+
+    let (code, _, err) = run(&["--warn-width", "10"], &cnf);
+    assert_eq!(code, cli::EXIT_SAT);
+    assert_eq!(err, "warning: clause 2 has 50 literals, exceeding 10\n");
+
+    let (_, _, err) = run(&["--warn-width", "50"], &cnf);
+    assert_eq!(err, "");
+}
+
+#[test]
+fn test_run_full_model() {
+    // Variables 2, 4 and 6 are declared but not used, and 6 is beyond the
+    // largest used one.
+    let cnf = "p cnf 6 2\n1 3 0\n-1 5 0\n";
+
+    let (code, out, _) = run(&[], cnf);
+    assert_eq!(code, cli::EXIT_SAT);
+    assert_eq!(out.lines().nth(1).unwrap().split(' ').count(), 5);
+
+    let (code, out, _) = run(&["--full-model"], cnf);
+    assert_eq!(code, cli::EXIT_SAT);
+    let model: Vec<i32> = out
+        .lines()
+        .nth(1)
+        .unwrap()
+        .split(' ')
+        .map(|lit| lit.parse().unwrap())
+        .collect();
+    assert_eq!(model.len(), 6);
+    for (i, &lit) in model.iter().enumerate() {
+        assert_eq!(lit.abs(), i as i32 + 1);
+    }
+    assert_eq!(model[5], -6);
+    assert!((model[0] > 0 && model[4] > 0) || (model[0] < 0 && model[2] > 0));
+}
+
+#[test]
+fn test_run_randomize() {
+    let cnf = fs::read_to_string("examples/negative_3.cnf").unwrap();
+    let formula = dimacs::load(&mut cnf.as_bytes(), true).unwrap();
+
+    for seed in &["1", "2", "12345"] {
+        let (code, out, _) = run(&["--randomize", seed], &cnf);
+        assert_eq!(code, cli::EXIT_SAT);
+
+        let model = out.strip_prefix("sat\n").unwrap();
+        let vars: sat::Assignment = model
+            .split_whitespace()
+            .map(|token| !token.starts_with('-'))
+            .collect();
+        assert!(sat::verify(&formula, &vars));
+
+        // Reproducible by the seed.
+        assert_eq!(run(&["--randomize", seed], &cnf).1, out);
+    }
+
+    let (code, out, _) = run(
+        &["--randomize", "1"],
+        "p cnf 2 4\n1 2 0\n-1 2 0\n1 -2 0\n-1 -2 0\n",
+    );
+    assert_eq!(code, cli::EXIT_UNSAT);
+    assert_eq!(out, "unsat\n");
+}
+
+#[test]
+fn test_run_walksat() {
+    let cnf = fs::read_to_string("examples/negative_3.cnf").unwrap();
+    let (code, out, _) = run(&["--walksat", "1000"], &cnf);
+    assert_eq!(code, cli::EXIT_SAT);
+    assert!(out.starts_with("sat\n"));
+
+    // Falls back to DPLL, which proves unsatisfiability.
+    let (code, out, _) = run(&["--walksat", "1000"], "p cnf 1 2\n1 0\n-1 0\n");
+    assert_eq!(code, cli::EXIT_UNSAT);
+    assert_eq!(out, "unsat\n");
+}
@@ -0,0 +1,40 @@
+//! Uses the solver core from a `no_std` crate. Run with the `std` feature
+//! disabled to check that the core does not depend on std:
+//!
+//! ```console
+//! $ cargo test --no-default-features --test no_std
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec;
+use dpll_sat::sat::{self, Not, Var};
+use dpll_sat::solver::Solver;
+
+#[test]
+fn test_check_sat() {
+    let formula = vec![
+        vec![Var(0), Var(1)],
+        vec![Not(0), Var(1)],
+        vec![Not(1), Var(2)],
+    ];
+    let vars = sat::check_sat(&formula).unwrap();
+    assert!(sat::verify(&formula, &vars));
+
+    let formula = vec![vec![Var(0)], vec![Not(0), Var(1)], vec![Not(1)]];
+    assert_eq!(sat::check_sat(&formula), None);
+}
+
+#[test]
+fn test_solver() {
+    let formula = vec![
+        vec![Var(0), Var(1), Var(2)],
+        vec![Not(0), Not(1)],
+        vec![Not(1), Not(2)],
+        vec![Not(0), Not(2)],
+    ];
+    let vars = Solver::new(&formula).solve().unwrap();
+    assert!(sat::verify(&formula, &vars));
+}